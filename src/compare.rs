@@ -0,0 +1,223 @@
+use crate::config;
+use crate::io::engine::BenchmarkResults;
+use crate::output::METRICS;
+use crate::stats;
+
+/// Regression gate settings
+#[derive(Debug, Clone, Copy)]
+pub struct Thresholds {
+    /// Largest tolerated change for the worse, in percent
+    pub max_regression_percent: f64,
+    /// Significance level for the t-test when both sides have repeated samples
+    pub alpha: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    Improved,
+    Regression,
+    /// Worse than the threshold, but within run-to-run noise
+    NotSignificant,
+}
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Status::Ok => write!(f, "ok"),
+            Status::Improved => write!(f, "improved"),
+            Status::Regression => write!(f, "REGRESSION"),
+            Status::NotSignificant => write!(f, "not significant"),
+        }
+    }
+}
+
+/// One metric of one job, baseline against candidate
+#[derive(Debug, Clone)]
+pub struct MetricDelta {
+    pub job: String,
+    pub metric: &'static str,
+    pub unit: &'static str,
+    pub baseline: f64,
+    pub candidate: f64,
+    /// Change relative to the baseline, in percent
+    pub delta_percent: f64,
+    /// Welch's t-test p-value, when both sides have at least two samples
+    pub p_value: Option<f64>,
+    pub status: Status,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Comparison {
+    pub deltas: Vec<MetricDelta>,
+    /// Jobs present on only one side
+    pub baseline_only: Vec<String>,
+    pub candidate_only: Vec<String>,
+}
+
+impl Comparison {
+    pub fn regressions(&self) -> usize {
+        self.deltas
+            .iter()
+            .filter(|d| d.status == Status::Regression)
+            .count()
+    }
+}
+
+/// Repeated samples of one job from a results file
+struct JobSamples<'a> {
+    name: String,
+    signature: String,
+    runs: Vec<&'a BenchmarkResults>,
+}
+
+/// Group a results file by job. Multi-job runs contribute their jobs; a job name
+/// that appears in several runs (e.g., from --repeat) becomes repeated samples.
+fn collect_jobs(results: &[BenchmarkResults]) -> Vec<JobSamples<'_>> {
+    let mut jobs: Vec<JobSamples> = Vec::new();
+    let entries = results.iter().flat_map(|result| {
+        if result.jobs.is_empty() {
+            std::slice::from_ref(result)
+        } else {
+            result.jobs.as_slice()
+        }
+    });
+    for entry in entries {
+        match jobs.iter_mut().find(|job| job.name == entry.name) {
+            Some(job) => job.runs.push(entry),
+            None => jobs.push(JobSamples {
+                name: entry.name.clone(),
+                signature: signature(entry),
+                runs: vec![entry],
+            }),
+        }
+    }
+    jobs
+}
+
+/// What a job ran, for matching jobs that were renamed between runs
+fn signature(result: &BenchmarkResults) -> String {
+    let config = &result.config;
+    format!(
+        "{:?} bs={} qd={} threads={} read={}%",
+        config.workload,
+        config::format_block_size(config.block_size),
+        config.queue_depth,
+        config.threads,
+        config.read_percent(),
+    )
+}
+
+/// Align jobs by name, then pair the leftovers by configuration, and compare every metric
+pub fn compare(
+    baseline: &[BenchmarkResults],
+    candidate: &[BenchmarkResults],
+    thresholds: Thresholds,
+) -> Comparison {
+    let baseline_jobs = collect_jobs(baseline);
+    let mut candidate_jobs: Vec<Option<JobSamples>> =
+        collect_jobs(candidate).into_iter().map(Some).collect();
+
+    let mut pairs = Vec::new();
+    let mut unmatched = Vec::new();
+    for base in baseline_jobs {
+        let by_name = candidate_jobs
+            .iter()
+            .position(|c| c.as_ref().is_some_and(|c| c.name == base.name));
+        match by_name {
+            Some(i) => pairs.push((base, candidate_jobs[i].take().unwrap())),
+            None => unmatched.push(base),
+        }
+    }
+
+    let mut comparison = Comparison::default();
+    for base in unmatched {
+        let by_config = candidate_jobs
+            .iter()
+            .position(|c| c.as_ref().is_some_and(|c| c.signature == base.signature));
+        match by_config {
+            Some(i) => pairs.push((base, candidate_jobs[i].take().unwrap())),
+            None => comparison.baseline_only.push(base.name),
+        }
+    }
+    comparison.candidate_only = candidate_jobs
+        .into_iter()
+        .flatten()
+        .map(|c| c.name)
+        .collect();
+
+    for (base, cand) in pairs {
+        let job = if base.name == cand.name {
+            base.name.clone()
+        } else {
+            format!("{} -> {}", base.name, cand.name)
+        };
+        for metric in &METRICS {
+            let a: Vec<f64> = base.runs.iter().map(|r| (metric.value)(r)).collect();
+            let b: Vec<f64> = cand.runs.iter().map(|r| (metric.value)(r)).collect();
+            let (base_mean, cand_mean) = (stats::mean(&a), stats::mean(&b));
+            // Nothing to compare against (e.g., write MB/s of a read-only job)
+            if base_mean == 0.0 {
+                continue;
+            }
+            let delta_percent = (cand_mean - base_mean) / base_mean * 100.0;
+            let worse_percent = if metric.higher_is_better {
+                -delta_percent
+            } else {
+                delta_percent
+            };
+            let p_value = stats::welch_t_test(&a, &b);
+            let status = if worse_percent > thresholds.max_regression_percent {
+                match p_value {
+                    Some(p) if p >= thresholds.alpha => Status::NotSignificant,
+                    _ => Status::Regression,
+                }
+            } else if -worse_percent > thresholds.max_regression_percent {
+                Status::Improved
+            } else {
+                Status::Ok
+            };
+            comparison.deltas.push(MetricDelta {
+                job: job.clone(),
+                metric: metric.name,
+                unit: metric.unit,
+                baseline: base_mean,
+                candidate: cand_mean,
+                delta_percent,
+                p_value,
+                status,
+            });
+        }
+    }
+    comparison
+}
+
+pub fn print_comparison(comparison: &Comparison) {
+    println!(
+        "{:<24} {:<14} {:>14} {:>14} {:>9} {:>8}  Status",
+        "Job", "Metric", "Baseline", "Candidate", "Delta", "p-value"
+    );
+    println!("{}", "-".repeat(100));
+    for delta in &comparison.deltas {
+        let p_value = delta
+            .p_value
+            .map(|p| format!("{:.4}", p))
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "{:<24} {:<14} {:>14} {:>14} {:>+8.2}% {:>8}  {}",
+            delta.job,
+            delta.metric,
+            format!("{:.2}{}", delta.baseline, delta.unit),
+            format!("{:.2}{}", delta.candidate, delta.unit),
+            delta.delta_percent,
+            p_value,
+            delta.status
+        );
+    }
+    for name in &comparison.baseline_only {
+        println!("Job '{}' is missing from the candidate", name);
+    }
+    for name in &comparison.candidate_only {
+        println!("Job '{}' is new in the candidate", name);
+    }
+}
@@ -4,7 +4,7 @@ use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
-    pub device: PathBuf,
+    pub devices: Vec<PathBuf>,
     pub workload: Workload,
     pub block_size: usize,
     pub queue_depth: usize,
@@ -12,6 +12,695 @@ pub struct Config {
     pub duration: Duration,
     pub optimize: bool,
     pub monitor: bool,
+    pub numa: Option<NumaPolicy>,
+    pub cpus: Option<Vec<usize>>,
+    pub cpu_mapping: CpuMapping,
+    pub memory_mode: MemoryMode,
+    pub rate: Option<RateLimit>,
+    /// Read share of mixed workloads; None uses the workload's default
+    pub read_percent: Option<u8>,
+    /// Weighted mix of block sizes; None issues every operation at block_size
+    pub block_size_split: Option<BlockSizeSplit>,
+    /// How random offsets are spread over the device
+    pub distribution: AccessDistribution,
+    /// Warm-up time before statistics are collected (not counted in duration)
+    pub ramp_time: Duration,
+    /// Jobs run concurrently; unset job fields inherit from this config
+    pub jobs: Vec<JobSpec>,
+    /// Write even to devices that are mounted, in use or carry data signatures
+    pub allow_destructive: bool,
+    /// Go through the page cache instead of opening targets with O_DIRECT
+    pub buffered: bool,
+    pub sync: SyncMode,
+    /// Bytes of each target to use; missing or shorter regular files are created
+    /// at this size
+    pub size: Option<u64>,
+    /// How new regular files are allocated
+    pub file_layout: FileLayout,
+    /// Name of each worker's own file when targets are directories (e.g., "$jobname.$jobnum")
+    pub filename_format: Option<String>,
+    /// Block queue attributes set on the targets' disks for the run, then restored
+    pub set_queue: QueueSettings,
+    /// Weighted mix of operation types; None derives reads and writes from read_percent
+    pub op_mix: Option<OpMix>,
+    /// Discard each target's whole range before the run starts
+    pub trim_before: bool,
+    /// Flush issued by each worker after every N writes
+    pub flush: Option<FlushPolicy>,
+    /// Link each flush to the write before it so it starts once that write completes
+    pub link_flush: bool,
+    /// Group commit and checkpoint settings of the wal workload
+    pub wal: WalSettings,
+}
+
+impl Default for Config {
+    /// The run's CLI defaults, with no targets
+    fn default() -> Self {
+        Config {
+            devices: Vec::new(),
+            workload: Workload::SeqRead,
+            block_size: Workload::SeqRead.default_block_size(),
+            queue_depth: 32,
+            threads: 1,
+            duration: Duration::from_secs(60),
+            optimize: false,
+            monitor: false,
+            numa: None,
+            cpus: None,
+            cpu_mapping: Default::default(),
+            memory_mode: Default::default(),
+            rate: None,
+            read_percent: None,
+            block_size_split: None,
+            distribution: Default::default(),
+            ramp_time: Duration::ZERO,
+            jobs: Vec::new(),
+            allow_destructive: false,
+            buffered: false,
+            sync: Default::default(),
+            size: None,
+            file_layout: Default::default(),
+            filename_format: None,
+            set_queue: Default::default(),
+            op_mix: None,
+            trim_before: false,
+            flush: None,
+            link_flush: false,
+            wal: Default::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Resolve each job into a full config (a single unnamed job when none are defined)
+    pub fn job_configs(&self) -> anyhow::Result<Vec<(String, Config)>> {
+        if self.jobs.is_empty() {
+            let mut config = self.clone();
+            config.jobs.clear();
+            return Ok(Self::with_checkpoints(vec![(
+                self.workload.name().to_string(),
+                config,
+            )]));
+        }
+
+        let mut resolved = Vec::with_capacity(self.jobs.len());
+        for job in &self.jobs {
+            if resolved.iter().any(|(name, _)| name == &job.name) {
+                return Err(anyhow::anyhow!("Duplicate job name: {}", job.name));
+            }
+            let mut config = self.clone();
+            config.jobs.clear();
+            if let Some(workload) = job.workload {
+                config.workload = workload;
+            }
+            if let Some(block_size) = job.block_size {
+                config.block_size = block_size;
+            }
+            if let Some(queue_depth) = job.queue_depth {
+                config.queue_depth = queue_depth;
+            }
+            if let Some(threads) = job.threads {
+                config.threads = threads;
+            }
+            if job.rate.is_some() {
+                config.rate = job.rate;
+            }
+            if job.read_percent.is_some() {
+                config.read_percent = job.read_percent;
+            }
+            if job.block_size_split.is_some() {
+                config.block_size_split = job.block_size_split.clone();
+            }
+            if let Some(distribution) = job.distribution {
+                config.distribution = distribution;
+            }
+            if job.op_mix.is_some() {
+                config.op_mix = job.op_mix.clone();
+            }
+            if job.flush.is_some() {
+                config.flush = job.flush;
+            }
+            if config.workload == Workload::All {
+                return Err(anyhow::anyhow!(
+                    "Job {}: workload 'all' cannot run concurrently with other jobs",
+                    job.name
+                ));
+            }
+            resolved.push((job.name.clone(), config));
+        }
+        Ok(Self::with_checkpoints(resolved))
+    }
+
+    /// Follow each wal job that asks for checkpoint readers with a job of them
+    fn with_checkpoints(jobs: Vec<(String, Config)>) -> Vec<(String, Config)> {
+        let mut expanded = Vec::with_capacity(jobs.len());
+        for (name, config) in jobs {
+            let checkpoint = (config.workload == Workload::Wal
+                && config.wal.checkpoint_threads > 0)
+                .then(|| {
+                    let mut reader = config.clone();
+                    reader.workload = Workload::SeqRead;
+                    reader.block_size = Workload::SeqRead.default_block_size();
+                    reader.threads = config.wal.checkpoint_threads;
+                    reader.block_size_split = None;
+                    reader.read_percent = None;
+                    reader.op_mix = None;
+                    reader.flush = None;
+                    reader.rate = None;
+                    (format!("{}-checkpoint", name), reader)
+                });
+            expanded.push((name, config));
+            expanded.extend(checkpoint);
+        }
+        expanded
+    }
+
+    /// Whether any job issues writes
+    pub fn writes(&self) -> anyhow::Result<bool> {
+        Ok(self
+            .job_configs()?
+            .iter()
+            .any(|(_, config)| config.read_percent() < 100))
+    }
+
+    /// Read share for this config's workload, honoring an explicit mix
+    pub fn read_percent(&self) -> u8 {
+        if let Some(mix) = &self.op_mix {
+            return mix.read_percent();
+        }
+        self.read_percent
+            .unwrap_or_else(|| self.workload.read_percent())
+    }
+
+    /// Weighted mix of block sizes, defaulting to variable record sizes for the wal workload
+    pub fn block_size_split(&self) -> Option<BlockSizeSplit> {
+        self.block_size_split.clone().or_else(|| {
+            (self.workload == Workload::Wal).then(|| {
+                "4k/50:8k/30:16k/15:64k/5"
+                    .parse()
+                    .expect("valid default wal record sizes")
+            })
+        })
+    }
+
+    /// Operations other than reads and writes this config issues, if any: the
+    /// explicit mix, or the trim workloads' single operation
+    pub fn op_mix(&self) -> Option<OpMix> {
+        self.op_mix
+            .clone()
+            .or_else(|| self.workload.op().map(|op| OpMix(vec![(op, 100)])))
+            .filter(OpMix::has_trims)
+    }
+}
+
+/// A named job in a multi-job run. Unset fields inherit from the run's Config.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JobSpec {
+    pub name: String,
+    pub workload: Option<Workload>,
+    pub block_size: Option<usize>,
+    pub queue_depth: Option<usize>,
+    pub threads: Option<usize>,
+    pub rate: Option<RateLimit>,
+    pub read_percent: Option<u8>,
+    pub block_size_split: Option<BlockSizeSplit>,
+    pub distribution: Option<AccessDistribution>,
+    pub op_mix: Option<OpMix>,
+    pub flush: Option<FlushPolicy>,
+}
+
+impl std::str::FromStr for JobSpec {
+    type Err = anyhow::Error;
+
+    /// Parse "name=reader,workload=randread,bs=4k,qd=32,threads=2,rate=20000"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut job = JobSpec::default();
+        for field in s.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+            let (key, value) = field
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("Invalid job field: {} (expected key=value)", field))?;
+            let value = value.trim();
+            match key.trim() {
+                "name" => job.name = value.to_string(),
+                "workload" | "rw" => job.workload = Some(value.parse()?),
+                "bs" | "block_size" => job.block_size = Some(parse_block_size(value)?),
+                "qd" | "queue_depth" | "iodepth" => {
+                    job.queue_depth = Some(
+                        value
+                            .parse()
+                            .map_err(|_| anyhow::anyhow!("Invalid queue depth: {}", value))?,
+                    )
+                }
+                "threads" | "numjobs" => {
+                    job.threads = Some(
+                        value
+                            .parse()
+                            .map_err(|_| anyhow::anyhow!("Invalid thread count: {}", value))?,
+                    )
+                }
+                "rate" => job.rate = Some(value.parse()?),
+                "rwmixread" | "read_percent" => job.read_percent = Some(parse_percent(value)?),
+                "bssplit" => job.block_size_split = Some(value.parse()?),
+                "distribution" | "random_distribution" => job.distribution = Some(value.parse()?),
+                "opmix" | "op_mix" => job.op_mix = Some(value.parse()?),
+                "fsync" => job.flush = FlushPolicy::every(FlushKind::Fsync, value)?,
+                "fdatasync" => job.flush = FlushPolicy::every(FlushKind::Fdatasync, value)?,
+                other => return Err(anyhow::anyhow!("Unknown job field: {}", other)),
+            }
+        }
+        if job.name.is_empty() {
+            return Err(anyhow::anyhow!("Job definition needs a name: {}", s));
+        }
+        Ok(job)
+    }
+}
+
+/// Per-worker rate limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RateLimit {
+    Iops(u64),
+    BytesPerSec(u64),
+}
+
+impl RateLimit {
+    /// Operations per second allowed at the given block size
+    pub fn ops_per_sec(&self, block_size: usize) -> f64 {
+        match *self {
+            RateLimit::Iops(iops) => iops as f64,
+            RateLimit::BytesPerSec(bytes) => bytes as f64 / block_size.max(1) as f64,
+        }
+    }
+}
+
+impl std::str::FromStr for RateLimit {
+    type Err = anyhow::Error;
+
+    /// "5000" or "5000iops" for IOPS, "200m" or "200mb" for bytes per second
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        let s = s.strip_suffix("/s").unwrap_or(&s);
+        if let Some(iops) = s.strip_suffix("iops") {
+            return iops
+                .trim()
+                .parse()
+                .map(RateLimit::Iops)
+                .map_err(|_| anyhow::anyhow!("Invalid IOPS rate: {}", s));
+        }
+        if let Ok(iops) = s.parse() {
+            return Ok(RateLimit::Iops(iops));
+        }
+        let bytes = s.strip_suffix('b').unwrap_or(s);
+        parse_block_size(bytes)
+            .map(|b| RateLimit::BytesPerSec(b as u64))
+            .map_err(|_| anyhow::anyhow!("Invalid rate: {}", s))
+    }
+}
+
+impl std::fmt::Display for RateLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RateLimit::Iops(iops) => write!(f, "{} IOPS", iops),
+            RateLimit::BytesPerSec(bytes) => {
+                write!(f, "{:.2} MB/s", *bytes as f64 / (1024.0 * 1024.0))
+            }
+        }
+    }
+}
+
+/// Weighted block sizes, written fio style: "4k/10:64k/50:32k/40".
+/// Entries without a percentage share whatever the others leave over.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockSizeSplit(Vec<(usize, u32)>);
+
+impl BlockSizeSplit {
+    /// (block size, percentage) pairs; percentages add up to 100
+    pub fn entries(&self) -> &[(usize, u32)] {
+        &self.0
+    }
+
+    pub fn max_block_size(&self) -> usize {
+        self.0.iter().map(|&(bs, _)| bs).max().unwrap_or(0)
+    }
+
+    pub fn min_block_size(&self) -> usize {
+        self.0.iter().map(|&(bs, _)| bs).min().unwrap_or(0)
+    }
+}
+
+impl std::str::FromStr for BlockSizeSplit {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut entries = Vec::new();
+        let mut unweighted = Vec::new();
+        for entry in s.split(':').map(str::trim).filter(|e| !e.is_empty()) {
+            let (bs, pct) = match entry.split_once('/') {
+                Some((bs, pct)) => (bs, Some(pct.trim())),
+                None => (entry, None),
+            };
+            let bs = parse_block_size(bs)?;
+            if bs == 0 {
+                return Err(anyhow::anyhow!("Invalid block size split entry: {}", entry));
+            }
+            match pct.filter(|p| !p.is_empty()) {
+                Some(pct) => entries.push((bs, parse_percent(pct)? as u32)),
+                None => unweighted.push(bs),
+            }
+        }
+
+        let assigned: u32 = entries.iter().map(|&(_, pct)| pct).sum();
+        if assigned > 100 {
+            return Err(anyhow::anyhow!("Block size split adds up to {}%: {}", assigned, s));
+        }
+        if !unweighted.is_empty() {
+            // Spread the remainder evenly, giving any rounding leftover to the first entry
+            let share = (100 - assigned) / unweighted.len() as u32;
+            let extra = (100 - assigned) % unweighted.len() as u32;
+            for (i, bs) in unweighted.into_iter().enumerate() {
+                entries.push((bs, share + if i == 0 { extra } else { 0 }));
+            }
+        } else if assigned != 100 {
+            return Err(anyhow::anyhow!("Block size split adds up to {}%, not 100%: {}", assigned, s));
+        }
+        entries.retain(|&(_, pct)| pct > 0);
+        if entries.is_empty() {
+            return Err(anyhow::anyhow!("Empty block size split: {}", s));
+        }
+        Ok(BlockSizeSplit(entries))
+    }
+}
+
+impl std::fmt::Display for BlockSizeSplit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let entries: Vec<String> = self
+            .0
+            .iter()
+            .map(|&(bs, pct)| format!("{}/{}", format_block_size(bs), pct))
+            .collect();
+        write!(f, "{}", entries.join(":"))
+    }
+}
+
+/// Kind of operation a worker issues
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OpKind {
+    Read,
+    Write,
+    /// BLKDISCARD on block devices, a punched hole in files
+    Discard,
+    /// BLKZEROOUT on block devices, a zeroed range in files
+    WriteZeroes,
+    /// BLKSECDISCARD; block devices only
+    SecureErase,
+}
+
+impl OpKind {
+    /// Discard-like operations, counted as trims in the results
+    pub fn is_trim(&self) -> bool {
+        matches!(self, OpKind::Discard | OpKind::WriteZeroes | OpKind::SecureErase)
+    }
+}
+
+impl std::str::FromStr for OpKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "read" => Ok(OpKind::Read),
+            "write" => Ok(OpKind::Write),
+            "discard" | "trim" => Ok(OpKind::Discard),
+            "write-zeroes" | "writezeroes" | "zeroout" => Ok(OpKind::WriteZeroes),
+            "secure-erase" | "secure-discard" => Ok(OpKind::SecureErase),
+            _ => Err(anyhow::anyhow!(
+                "Invalid operation: {}. Valid options: read, write, discard, write-zeroes, secure-erase",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for OpKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            OpKind::Read => "read",
+            OpKind::Write => "write",
+            OpKind::Discard => "discard",
+            OpKind::WriteZeroes => "write-zeroes",
+            OpKind::SecureErase => "secure-erase",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Weighted mix of operation types, written like bssplit: "read/70:write/20:discard/10"
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpMix(Vec<(OpKind, u32)>);
+
+impl OpMix {
+    /// (operation, percentage) pairs; percentages add up to 100
+    pub fn entries(&self) -> &[(OpKind, u32)] {
+        &self.0
+    }
+
+    pub fn read_percent(&self) -> u8 {
+        self.percent(|op| op == OpKind::Read) as u8
+    }
+
+    pub fn has_trims(&self) -> bool {
+        self.0.iter().any(|(op, _)| op.is_trim())
+    }
+
+    fn percent(&self, matches: impl Fn(OpKind) -> bool) -> u32 {
+        self.0
+            .iter()
+            .filter(|(op, _)| matches(*op))
+            .map(|(_, pct)| pct)
+            .sum()
+    }
+}
+
+impl std::str::FromStr for OpMix {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut entries: Vec<(OpKind, u32)> = Vec::new();
+        let mut unweighted = Vec::new();
+        for entry in s.split(':').map(str::trim).filter(|e| !e.is_empty()) {
+            let (op, pct) = match entry.split_once('/') {
+                Some((op, pct)) => (op, Some(pct.trim())),
+                None => (entry, None),
+            };
+            let op: OpKind = op.parse()?;
+            if entries.iter().any(|(o, _)| *o == op) || unweighted.contains(&op) {
+                return Err(anyhow::anyhow!("Operation listed twice in mix: {}", op));
+            }
+            match pct.filter(|p| !p.is_empty()) {
+                Some(pct) => entries.push((op, parse_percent(pct)? as u32)),
+                None => unweighted.push(op),
+            }
+        }
+
+        let assigned: u32 = entries.iter().map(|&(_, pct)| pct).sum();
+        if assigned > 100 {
+            return Err(anyhow::anyhow!("Operation mix adds up to {}%: {}", assigned, s));
+        }
+        if !unweighted.is_empty() {
+            // Spread the remainder evenly, giving any rounding leftover to the first entry
+            let share = (100 - assigned) / unweighted.len() as u32;
+            let extra = (100 - assigned) % unweighted.len() as u32;
+            for (i, op) in unweighted.into_iter().enumerate() {
+                entries.push((op, share + if i == 0 { extra } else { 0 }));
+            }
+        } else if assigned != 100 {
+            return Err(anyhow::anyhow!("Operation mix adds up to {}%, not 100%: {}", assigned, s));
+        }
+        entries.retain(|&(_, pct)| pct > 0);
+        if entries.is_empty() {
+            return Err(anyhow::anyhow!("Empty operation mix: {}", s));
+        }
+        Ok(OpMix(entries))
+    }
+}
+
+impl std::fmt::Display for OpMix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let entries: Vec<String> = self
+            .0
+            .iter()
+            .map(|(op, pct)| format!("{}/{}", op, pct))
+            .collect();
+        write!(f, "{}", entries.join(":"))
+    }
+}
+
+/// Spread of random offsets over the device, named as in fio's random_distribution
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum AccessDistribution {
+    #[default]
+    Uniform,
+    Zipf(f64),   // Zipf with exponent theta; the hottest blocks are at the start
+    Pareto(f64), // Pareto with shape h (0 < h < 1); smaller h is more skewed
+    Normal(f64), // Gaussian around the middle, stddev as a percentage of the device
+}
+
+impl std::str::FromStr for AccessDistribution {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        let (name, param) = match s.split_once(':') {
+            Some((name, param)) => (name, Some(param)),
+            None => (s.as_str(), None),
+        };
+        let parameter = |what: &str| -> anyhow::Result<f64> {
+            let value = param
+                .ok_or_else(|| anyhow::anyhow!("Distribution {} needs a parameter ({}:{})", name, name, what))?;
+            value
+                .parse::<f64>()
+                .ok()
+                .filter(|v| v.is_finite() && *v > 0.0)
+                .ok_or_else(|| anyhow::anyhow!("Invalid {} parameter: {}", name, value))
+        };
+        match name {
+            "random" | "uniform" => Ok(AccessDistribution::Uniform),
+            "zipf" => Ok(AccessDistribution::Zipf(parameter("theta")?)),
+            "pareto" => {
+                let h = parameter("h")?;
+                if h >= 1.0 {
+                    return Err(anyhow::anyhow!("Pareto parameter must be below 1: {}", h));
+                }
+                Ok(AccessDistribution::Pareto(h))
+            }
+            "normal" | "gauss" => Ok(AccessDistribution::Normal(parameter("stddev")?)),
+            _ => Err(anyhow::anyhow!(
+                "Invalid distribution: {}. Valid options: random, zipf:THETA, pareto:H, normal:STDDEV",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for AccessDistribution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccessDistribution::Uniform => write!(f, "random"),
+            AccessDistribution::Zipf(theta) => write!(f, "zipf:{}", theta),
+            AccessDistribution::Pareto(h) => write!(f, "pareto:{}", h),
+            AccessDistribution::Normal(stddev) => write!(f, "normal:{}", stddev),
+        }
+    }
+}
+
+/// Format a block size the way parse_block_size reads it ("4k", "1m", "512")
+pub fn format_block_size(bytes: usize) -> String {
+    if bytes >= 1024 * 1024 && bytes.is_multiple_of(1024 * 1024) {
+        format!("{}m", bytes / (1024 * 1024))
+    } else if bytes >= 1024 && bytes.is_multiple_of(1024) {
+        format!("{}k", bytes / 1024)
+    } else {
+        bytes.to_string()
+    }
+}
+
+/// Parse a 0-100 percentage
+pub fn parse_percent(s: &str) -> anyhow::Result<u8> {
+    s.trim()
+        .trim_end_matches('%')
+        .parse::<u8>()
+        .ok()
+        .filter(|p| *p <= 100)
+        .ok_or_else(|| anyhow::anyhow!("Invalid percentage: {}", s))
+}
+
+/// Expand device arguments into paths. Each argument may be a comma-separated
+/// list and may contain glob patterns (e.g., "/dev/nvme*n1").
+pub fn expand_devices(args: &[String]) -> anyhow::Result<Vec<PathBuf>> {
+    let mut devices = Vec::new();
+    for pattern in args.iter().flat_map(|a| a.split(',')).map(str::trim) {
+        if pattern.is_empty() {
+            continue;
+        }
+        if !pattern.contains(['*', '?', '[']) {
+            devices.push(PathBuf::from(pattern));
+            continue;
+        }
+
+        let mut matches: Vec<PathBuf> = glob::glob(pattern)
+            .map_err(|e| anyhow::anyhow!("Invalid device pattern {}: {}", pattern, e))?
+            .filter_map(Result::ok)
+            .collect();
+        if matches.is_empty() {
+            return Err(anyhow::anyhow!("Device pattern matched nothing: {}", pattern));
+        }
+        matches.sort();
+        devices.extend(matches);
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    devices.retain(|d| seen.insert(d.clone()));
+    if devices.is_empty() {
+        return Err(anyhow::anyhow!("No devices given"));
+    }
+    Ok(devices)
+}
+
+/// Parse a human-readable duration ("60", "30s", "5m", "1h", "500ms"); bare numbers are seconds
+pub fn parse_duration(s: &str) -> anyhow::Result<Duration> {
+    let s = s.trim().to_lowercase();
+    let (num_str, scale) = if let Some(n) = s.strip_suffix("ms") {
+        (n, 0.001)
+    } else if let Some(n) = s.strip_suffix('s') {
+        (n, 1.0)
+    } else if let Some(n) = s.strip_suffix('m') {
+        (n, 60.0)
+    } else if let Some(n) = s.strip_suffix('h') {
+        (n, 3600.0)
+    } else {
+        (s.as_str(), 1.0)
+    };
+    let num: f64 = num_str
+        .trim()
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid duration: {}", s))?;
+    if !num.is_finite() || num < 0.0 {
+        return Err(anyhow::anyhow!("Invalid duration: {}", s));
+    }
+    Ok(Duration::from_secs_f64(num * scale))
+}
+
+/// Parse a sweep list such as "1,2,4..256" or "4k,16k..1m" into ascending values.
+/// A range `a..b` doubles from a up to b (inclusive), since storage parameters
+/// scale in powers of two.
+pub fn parse_sweep_list(
+    s: &str,
+    parse: fn(&str) -> anyhow::Result<usize>,
+) -> anyhow::Result<Vec<usize>> {
+    let mut values = Vec::new();
+    for item in s.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+        match item.split_once("..") {
+            Some((start, end)) => {
+                let (start, end) = (parse(start)?, parse(end)?);
+                if start == 0 || start > end {
+                    return Err(anyhow::anyhow!("Invalid range: {}", item));
+                }
+                let mut value = start;
+                while value <= end {
+                    values.push(value);
+                    value *= 2;
+                }
+            }
+            None => values.push(parse(item)?),
+        }
+    }
+    values.sort_unstable();
+    values.dedup();
+    if values.is_empty() {
+        return Err(anyhow::anyhow!("Empty sweep list: {:?}", s));
+    }
+    if values.contains(&0) {
+        return Err(anyhow::anyhow!("Sweep values must be positive: {}", s));
+    }
+    Ok(values)
 }
 
 /// Parse human-readable block size (e.g., "4k", "64k", "1m", "2m")
@@ -49,22 +738,76 @@ pub enum Workload {
     RandWrite,  // Random write (100% write)
     Seq,        // Sequential mixed (50% read, 50% write)
     Rand,       // Random mixed (50% read, 50% write)
+    Trim,       // Random discards
+    WriteZeroes, // Random write-zeroes
+    SecureErase, // Random secure discards
+    Wal,        // Write-ahead log: sequential appends with group commits
     All,        // Run all workloads
 }
 
 impl Workload {
     pub fn is_sequential(&self) -> bool {
-        matches!(self, Workload::SeqRead | Workload::SeqWrite | Workload::Seq)
+        matches!(
+            self,
+            Workload::SeqRead | Workload::SeqWrite | Workload::Seq | Workload::Wal
+        )
     }
 
     pub fn is_random(&self) -> bool {
-        matches!(self, Workload::RandRead | Workload::RandWrite | Workload::Rand)
+        matches!(
+            self,
+            Workload::RandRead
+                | Workload::RandWrite
+                | Workload::Rand
+                | Workload::Trim
+                | Workload::WriteZeroes
+                | Workload::SecureErase
+        )
+    }
+
+    /// The single operation of the trim workloads
+    pub fn op(&self) -> Option<OpKind> {
+        match self {
+            Workload::Trim => Some(OpKind::Discard),
+            Workload::WriteZeroes => Some(OpKind::WriteZeroes),
+            Workload::SecureErase => Some(OpKind::SecureErase),
+            _ => None,
+        }
+    }
+
+    /// Default block size: 128k for sequential workloads, 4k for random workloads
+    /// and log records
+    pub fn default_block_size(&self) -> usize {
+        if self.is_sequential() && *self != Workload::Wal {
+            128 * 1024
+        } else {
+            4 * 1024
+        }
+    }
+
+    /// Canonical CLI name of the workload
+    pub fn name(&self) -> &'static str {
+        match self {
+            Workload::SeqRead => "seqread",
+            Workload::SeqWrite => "seqwrite",
+            Workload::RandRead => "randread",
+            Workload::RandWrite => "randwrite",
+            Workload::Seq => "seq",
+            Workload::Rand => "rand",
+            Workload::Trim => "trim",
+            Workload::WriteZeroes => "write-zeroes",
+            Workload::SecureErase => "secure-erase",
+            Workload::Wal => "wal",
+            Workload::All => "all",
+        }
     }
 
     pub fn read_percent(&self) -> u8 {
         match self {
             Workload::SeqRead | Workload::RandRead => 100,
             Workload::SeqWrite | Workload::RandWrite => 0,
+            Workload::Trim | Workload::WriteZeroes | Workload::SecureErase => 0,
+            Workload::Wal => 0,
             Workload::Seq | Workload::Rand => 50,
             Workload::All => 50, // Default for "all"
         }
@@ -82,8 +825,354 @@ impl std::str::FromStr for Workload {
             "randwrite" | "rand-write" | "random-write" => Ok(Workload::RandWrite),
             "seq" | "sequential" => Ok(Workload::Seq),
             "rand" | "random" => Ok(Workload::Rand),
+            "trim" | "randtrim" | "discard" => Ok(Workload::Trim),
+            "write-zeroes" | "writezeroes" | "zeroout" => Ok(Workload::WriteZeroes),
+            "secure-erase" | "secure-discard" => Ok(Workload::SecureErase),
+            "wal" | "log" => Ok(Workload::Wal),
             "all" => Ok(Workload::All),
-            _ => Err(anyhow::anyhow!("Invalid workload: {}. Valid options: seqread, seqwrite, randread, randwrite, seq, rand, all", s)),
+            _ => Err(anyhow::anyhow!("Invalid workload: {}. Valid options: seqread, seqwrite, randread, randwrite, seq, rand, trim, write-zeroes, secure-erase, wal, all", s)),
+        }
+    }
+}
+
+/// NUMA placement policy for worker threads and their buffers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NumaPolicy {
+    Auto,        // Pin to the device's home node
+    Node(usize), // Pin to an explicit node
+    Interleave,  // Spread workers across nodes, interleave memory
+}
+
+impl std::str::FromStr for NumaPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        match s.as_str() {
+            "auto" => Ok(NumaPolicy::Auto),
+            "interleave" => Ok(NumaPolicy::Interleave),
+            _ => match s.strip_prefix("node:") {
+                Some(node) => node
+                    .parse()
+                    .map(NumaPolicy::Node)
+                    .map_err(|_| anyhow::anyhow!("Invalid NUMA node: {}", node)),
+                None => Err(anyhow::anyhow!(
+                    "Invalid NUMA policy: {}. Valid options: auto, node:N, interleave",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for NumaPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NumaPolicy::Auto => write!(f, "auto"),
+            NumaPolicy::Node(node) => write!(f, "node:{}", node),
+            NumaPolicy::Interleave => write!(f, "interleave"),
+        }
+    }
+}
+
+/// How worker threads are mapped onto an explicit CPU list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CpuMapping {
+    #[default]
+    RoundRobin, // Worker i runs on cpus[i % len], CPUs may be shared
+    OneToOne,   // Worker i runs on cpus[i], every worker gets its own CPU
+}
+
+impl std::str::FromStr for CpuMapping {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "round-robin" | "roundrobin" | "rr" => Ok(CpuMapping::RoundRobin),
+            "one-to-one" | "1:1" => Ok(CpuMapping::OneToOne),
+            _ => Err(anyhow::anyhow!(
+                "Invalid CPU mapping: {}. Valid options: round-robin, one-to-one",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for CpuMapping {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CpuMapping::RoundRobin => write!(f, "round-robin"),
+            CpuMapping::OneToOne => write!(f, "one-to-one"),
+        }
+    }
+}
+
+/// Memory backing for I/O buffers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum MemoryMode {
+    #[default]
+    Anon,     // Regular anonymous pages
+    Thp,      // Anonymous memory with madvise(MADV_HUGEPAGE)
+    HugePage, // Reserved hugetlbfs pages (MAP_HUGETLB)
+}
+
+impl std::str::FromStr for MemoryMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "anon" | "anonymous" => Ok(MemoryMode::Anon),
+            "thp" => Ok(MemoryMode::Thp),
+            "hugepage" | "hugetlb" => Ok(MemoryMode::HugePage),
+            _ => Err(anyhow::anyhow!(
+                "Invalid memory mode: {}. Valid options: hugepage, thp, anon",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for MemoryMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MemoryMode::Anon => write!(f, "anon"),
+            MemoryMode::Thp => write!(f, "thp"),
+            MemoryMode::HugePage => write!(f, "hugepage"),
+        }
+    }
+}
+
+/// Write synchronization requested when opening targets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SyncMode {
+    #[default]
+    None,
+    Sync,  // O_SYNC: data and metadata reach stable storage before a write completes
+    Dsync, // O_DSYNC: data (and metadata needed to read it back) reach stable storage
+}
+
+impl std::str::FromStr for SyncMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "none" | "0" => Ok(SyncMode::None),
+            "sync" | "o_sync" | "1" => Ok(SyncMode::Sync),
+            "dsync" | "o_dsync" => Ok(SyncMode::Dsync),
+            _ => Err(anyhow::anyhow!(
+                "Invalid sync mode: {}. Valid options: none, sync, dsync",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for SyncMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyncMode::None => write!(f, "none"),
+            SyncMode::Sync => write!(f, "O_SYNC"),
+            SyncMode::Dsync => write!(f, "O_DSYNC"),
+        }
+    }
+}
+
+/// Flush operation issued between writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FlushKind {
+    Fsync,     // data and metadata
+    Fdatasync, // data, plus only the metadata needed to read it back
+}
+
+impl std::fmt::Display for FlushKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FlushKind::Fsync => write!(f, "fsync"),
+            FlushKind::Fdatasync => write!(f, "fdatasync"),
+        }
+    }
+}
+
+/// A flush issued by each worker after every `writes` writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlushPolicy {
+    pub kind: FlushKind,
+    pub writes: u32,
+}
+
+impl FlushPolicy {
+    /// A flush every `writes` writes; 0 turns flushing off, as in fio
+    pub fn new(kind: FlushKind, writes: u32) -> Option<Self> {
+        (writes > 0).then_some(FlushPolicy { kind, writes })
+    }
+
+    /// Parse a write count as fio's fsync=N does
+    pub fn every(kind: FlushKind, writes: &str) -> anyhow::Result<Option<Self>> {
+        let writes = writes
+            .trim()
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid {} interval: {}", kind, writes))?;
+        Ok(Self::new(kind, writes))
+    }
+}
+
+impl std::fmt::Display for FlushPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.writes {
+            1 => write!(f, "{} after every write", self.kind),
+            n => write!(f, "{} every {} writes", self.kind, n),
+        }
+    }
+}
+
+/// Group commit settings of the wal workload
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalSettings {
+    /// Commit (fdatasync) once the oldest uncommitted record is this old...
+    pub commit_interval: Duration,
+    /// ...or once this many bytes are waiting, whichever comes first
+    pub commit_bytes: u64,
+    /// Threads per target reading the log back sequentially, as a checkpoint does
+    pub checkpoint_threads: usize,
+}
+
+impl Default for WalSettings {
+    fn default() -> Self {
+        WalSettings {
+            commit_interval: Duration::from_millis(1),
+            commit_bytes: 64 * 1024,
+            checkpoint_threads: 0,
+        }
+    }
+}
+
+impl std::fmt::Display for WalSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "commit every {:?} or {}",
+            self.commit_interval,
+            format_block_size(self.commit_bytes as usize)
+        )?;
+        if self.checkpoint_threads > 0 {
+            write!(f, ", {} checkpoint reader(s)", self.checkpoint_threads)?;
+        }
+        Ok(())
+    }
+}
+
+/// Block queue attributes to set, written "scheduler=none,nr_requests=1023"
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueSettings(Vec<(String, String)>);
+
+impl QueueSettings {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// (attribute, value) pairs; the scheduler comes first because switching it
+    /// resets nr_requests
+    pub fn entries(&self) -> Vec<(&str, &str)> {
+        let mut entries: Vec<(&str, &str)> =
+            self.0.iter().map(|(a, v)| (a.as_str(), v.as_str())).collect();
+        entries.sort_by_key(|(attribute, _)| *attribute != "scheduler");
+        entries
+    }
+
+    pub fn get(&self, attribute: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(a, _)| a == attribute)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Set an attribute, replacing an earlier value
+    pub fn set(&mut self, attribute: &str, value: impl ToString) {
+        match self.0.iter_mut().find(|(a, _)| a == attribute) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.0.push((attribute.to_string(), value.to_string())),
+        }
+    }
+}
+
+impl std::str::FromStr for QueueSettings {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut settings = QueueSettings::default();
+        for item in s.split(',').map(str::trim).filter(|i| !i.is_empty()) {
+            let (attribute, value) = item
+                .split_once('=')
+                .map(|(a, v)| (a.trim(), v.trim()))
+                .filter(|(a, v)| !a.is_empty() && !v.is_empty())
+                .ok_or_else(|| anyhow::anyhow!("Invalid queue setting: {} (expected name=value)", item))?;
+            // Plain attribute names only, so nothing outside queue/ is written
+            if !attribute
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+            {
+                return Err(anyhow::anyhow!("Invalid queue attribute: {}", attribute));
+            }
+            settings.set(attribute, value);
+        }
+        if settings.is_empty() {
+            return Err(anyhow::anyhow!("No queue settings given"));
+        }
+        Ok(settings)
+    }
+}
+
+impl std::fmt::Display for QueueSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let entries: Vec<String> = self.0.iter().map(|(a, v)| format!("{}={}", a, v)).collect();
+        write!(f, "{}", entries.join(","))
+    }
+}
+
+/// How regular-file targets are allocated when they are created or extended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum FileLayout {
+    /// Write them out when the workload reads, so reads hit real blocks instead
+    /// of unwritten extents; fallocate them otherwise
+    #[default]
+    Auto,
+    Fallocate,
+    Write,
+}
+
+impl FileLayout {
+    /// The layout to use for a workload with the given read share
+    pub fn resolve(&self, read_percent: u8) -> FileLayout {
+        match self {
+            FileLayout::Auto if read_percent > 0 => FileLayout::Write,
+            FileLayout::Auto => FileLayout::Fallocate,
+            layout => *layout,
+        }
+    }
+}
+
+impl std::str::FromStr for FileLayout {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(FileLayout::Auto),
+            "fallocate" | "prealloc" => Ok(FileLayout::Fallocate),
+            "write" => Ok(FileLayout::Write),
+            _ => Err(anyhow::anyhow!(
+                "Invalid file layout: {}. Valid options: auto, fallocate, write",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for FileLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileLayout::Auto => write!(f, "auto"),
+            FileLayout::Fallocate => write!(f, "fallocate"),
+            FileLayout::Write => write!(f, "write"),
         }
     }
 }
@@ -100,13 +1189,15 @@ impl From<Workload> for IoMode {
     fn from(workload: Workload) -> Self {
         match workload {
             Workload::SeqRead | Workload::SeqWrite | Workload::Seq => IoMode::Sequential,
+            Workload::Wal => IoMode::Sequential,
             Workload::RandRead | Workload::RandWrite | Workload::Rand => IoMode::Random,
+            Workload::Trim | Workload::WriteZeroes | Workload::SecureErase => IoMode::Random,
             Workload::All => IoMode::Sequential, // Default
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct TestParams {
     pub queue_depth: usize,
     pub block_size: usize,
@@ -114,6 +1205,45 @@ pub struct TestParams {
     pub io_pattern: IoMode,
     pub read_percent: u8, // 0-100
     pub num_jobs: usize,
+    pub numa: Option<NumaPolicy>,
+    /// Block queue attributes to run with, on top of the base configuration's
+    pub queue: QueueSettings,
+}
+
+impl TestParams {
+    /// The base configuration with these parameters applied. The base's own jobs
+    /// are kept; when more jobs are asked for, copies of them (or identical jobs of
+    /// `num_threads` workers each, without any) make up the difference
+    pub fn apply(&self, base: &Config) -> Config {
+        let mut jobs = base.jobs.clone();
+        if self.num_jobs > jobs.len().max(1) {
+            for i in jobs.len()..self.num_jobs {
+                jobs.push(match base.jobs.get(i % base.jobs.len().max(1)) {
+                    Some(job) => JobSpec {
+                        name: format!("{}-{}", job.name, i),
+                        ..job.clone()
+                    },
+                    None => JobSpec {
+                        name: format!("job{}", i),
+                        ..Default::default()
+                    },
+                });
+            }
+        }
+        let mut set_queue = base.set_queue.clone();
+        for (attribute, value) in self.queue.entries() {
+            set_queue.set(attribute, value);
+        }
+        Config {
+            block_size: self.block_size,
+            queue_depth: self.queue_depth,
+            threads: self.num_threads,
+            jobs,
+            numa: self.numa.or(base.numa),
+            set_queue,
+            ..base.clone()
+        }
+    }
 }
 
 impl Default for TestParams {
@@ -125,7 +1255,62 @@ impl Default for TestParams {
             io_pattern: IoMode::Sequential,
             read_percent: 100,
             num_jobs: 1,
+            numa: None,
+            queue: QueueSettings::default(),
         }
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(num_jobs: usize) -> TestParams {
+        TestParams {
+            queue_depth: 8,
+            block_size: 16384,
+            num_threads: 2,
+            num_jobs,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn apply_keeps_the_base_jobs() {
+        let base = Config {
+            jobs: vec![
+                "name=reader,bs=8k".parse().unwrap(),
+                "name=writer".parse().unwrap(),
+            ],
+            ..Default::default()
+        };
+        let config = params(1).apply(&base);
+        let names: Vec<&str> = config.jobs.iter().map(|j| j.name.as_str()).collect();
+        assert_eq!(names, ["reader", "writer"]);
+        assert_eq!(config.jobs[0].block_size, Some(8192));
+        assert_eq!(
+            (config.queue_depth, config.block_size, config.threads),
+            (8, 16384, 2)
+        );
+
+        let names: Vec<String> = params(3)
+            .apply(&base)
+            .jobs
+            .into_iter()
+            .map(|j| j.name)
+            .collect();
+        assert_eq!(names, ["reader", "writer", "reader-2"]);
+    }
+
+    #[test]
+    fn apply_adds_identical_jobs_without_base_jobs() {
+        assert!(params(1).apply(&Config::default()).jobs.is_empty());
+        let names: Vec<String> = params(2)
+            .apply(&Config::default())
+            .jobs
+            .into_iter()
+            .map(|j| j.name)
+            .collect();
+        assert_eq!(names, ["job0", "job1"]);
+    }
+}
@@ -0,0 +1,788 @@
+use crate::config::{
+    self, AccessDistribution, BlockSizeSplit, Config, CpuMapping, FlushKind, FlushPolicy, JobSpec,
+    MemoryMode, RateLimit, SyncMode, Workload,
+};
+use crate::io::file;
+use crate::jobfile::JobGroup;
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// fio runs until `size` is done when no runtime is given; we are always time based
+const DEFAULT_RUNTIME: Duration = Duration::from_secs(60);
+/// fio's default per-job file name, with $filenum fixed at 0 (nrfiles=1)
+const FIO_FILENAME_FORMAT: &str = "$jobname.$jobnum.0";
+
+/// A fio job file (.fio) translated into storage-bench job groups.
+///
+/// Options are applied the way fio applies them: [global] sections set defaults for
+/// the jobs that follow, jobs run concurrently, and `stonewall` starts a new group
+/// that waits for the previous one. Options that change what I/O is issued but have
+/// no equivalent here are errors; options that only affect fio itself are warnings.
+#[derive(Debug)]
+pub struct FioJobFile {
+    sections: Vec<FioSection>,
+}
+
+#[derive(Debug)]
+struct FioSection {
+    name: String,
+    options: Vec<FioOption>,
+}
+
+#[derive(Debug, Clone)]
+struct FioOption {
+    key: String,
+    value: Option<String>,
+    line: usize,
+}
+
+/// One fio job resolved into storage-bench terms
+#[derive(Debug)]
+struct FioJob {
+    spec: JobSpec,
+    devices: Option<Vec<PathBuf>>,
+    runtime: Option<Duration>,
+    ramp_time: Option<Duration>,
+    cpus: Option<Vec<usize>>,
+    cpu_mapping: Option<CpuMapping>,
+    memory_mode: Option<MemoryMode>,
+    buffered: Option<bool>,
+    sync: Option<SyncMode>,
+    size: Option<u64>,
+    directories: Option<Vec<PathBuf>>,
+    filename_format: Option<String>,
+    trim_percent: Option<u8>,
+    stonewall: bool,
+}
+
+impl FioJobFile {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read fio job file {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("Invalid fio job file {}", path.display()))
+    }
+
+    /// Parse the INI structure; ${VAR} references are expanded from the environment
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut sections: Vec<FioSection> = Vec::new();
+        for (i, raw) in content.lines().enumerate() {
+            let line_number = i + 1;
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[') {
+                let name = name.strip_suffix(']').ok_or_else(|| {
+                    anyhow::anyhow!("line {}: unterminated section header", line_number)
+                })?;
+                sections.push(FioSection {
+                    name: name.trim().to_string(),
+                    options: Vec::new(),
+                });
+                continue;
+            }
+            if line.starts_with("include ") {
+                return Err(anyhow::anyhow!(
+                    "line {}: include directives are not supported",
+                    line_number
+                ));
+            }
+
+            let section = sections.last_mut().ok_or_else(|| {
+                anyhow::anyhow!("line {}: option outside of any section", line_number)
+            })?;
+            let line = expand_env(line)
+                .with_context(|| format!("line {}: cannot expand variable", line_number))?;
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), Some(value.trim().to_string())),
+                None => (line.as_str(), None),
+            };
+            section.options.push(FioOption {
+                key: key.to_lowercase(),
+                value,
+                line: line_number,
+            });
+        }
+        Ok(Self { sections })
+    }
+
+    /// Translate every job, grouping them at stonewalls.
+    /// `devices` from the command line, when given, replaces each job's filename.
+    pub fn into_groups(self, devices: Option<Vec<PathBuf>>) -> Result<Vec<JobGroup>> {
+        let mut warnings = Warnings::default();
+        let mut globals: Vec<(String, FioOption)> = Vec::new();
+        let mut jobs: Vec<FioJob> = Vec::new();
+
+        for section in &self.sections {
+            if section.name == "global" {
+                globals.extend(
+                    section
+                        .options
+                        .iter()
+                        .map(|o| ("[global]".to_string(), o.clone())),
+                );
+                continue;
+            }
+            let context = format!("job '{}'", section.name);
+            let options = globals
+                .iter()
+                .cloned()
+                .chain(section.options.iter().map(|o| (context.clone(), o.clone())));
+            let mut job = FioJob::new(section.name.clone());
+            for (origin, option) in options {
+                job.apply(&option, &origin, &mut warnings)?;
+            }
+            job.resolve_files();
+            job.resolve_trims(&context)?;
+            if let Some(devices) = &devices {
+                job.devices = Some(devices.clone());
+                if !devices.iter().all(|d| file::is_directory(d)) {
+                    job.filename_format = None;
+                }
+            }
+            jobs.push(job);
+        }
+        if jobs.is_empty() {
+            return Err(anyhow::anyhow!("fio job file defines no jobs"));
+        }
+
+        // A stonewall job waits for everything before it, so it opens a new group
+        let mut groups: Vec<Vec<FioJob>> = Vec::new();
+        for job in jobs {
+            match groups.last_mut() {
+                Some(group) if !job.stonewall => group.push(job),
+                _ => groups.push(vec![job]),
+            }
+        }
+
+        groups
+            .into_iter()
+            .map(|group| Self::build_group(group, &mut warnings))
+            .collect()
+    }
+
+    fn build_group(mut jobs: Vec<FioJob>, warnings: &mut Warnings) -> Result<JobGroup> {
+        let first = &jobs[0];
+        let name = if jobs.len() == 1 {
+            first.spec.name.clone()
+        } else {
+            format!("{} (+{} jobs)", first.spec.name, jobs.len() - 1)
+        };
+        let context = format!("group '{}'", name);
+
+        let devices = first.devices.clone().ok_or_else(|| {
+            anyhow::anyhow!(
+                "job '{}': no filename given (use filename= or --device)",
+                first.spec.name
+            )
+        })?;
+        for job in &jobs[1..] {
+            if job.devices.as_ref() != Some(&devices)
+                || job.filename_format != first.filename_format
+            {
+                return Err(anyhow::anyhow!(
+                    "jobs '{}' and '{}' run together but use different filenames; \
+                     separate them with stonewall",
+                    first.spec.name,
+                    job.spec.name
+                ));
+            }
+            if job.cpus != first.cpus || job.cpu_mapping != first.cpu_mapping {
+                return Err(anyhow::anyhow!(
+                    "jobs '{}' and '{}' run together but use different cpus_allowed settings",
+                    first.spec.name,
+                    job.spec.name
+                ));
+            }
+        }
+
+        // Run-wide timing: every job in the group shares one runtime and ramp time
+        let runtime = longest(&jobs, |j| j.runtime, "runtime", &context, warnings);
+        let ramp_time = longest(&jobs, |j| j.ramp_time, "ramp_time", &context, warnings);
+        let size = longest(&jobs, |j| j.size, "size", &context, warnings);
+        if runtime.is_none() {
+            warnings.warn(format!(
+                "{}: no runtime given, running for {}s instead of until size is reached",
+                context,
+                DEFAULT_RUNTIME.as_secs()
+            ));
+        }
+        let memory_mode = first.memory_mode.unwrap_or_default();
+        if jobs
+            .iter()
+            .any(|j| j.memory_mode.unwrap_or_default() != memory_mode)
+        {
+            warnings.warn(format!(
+                "{}: jobs use different mem settings, using {} for all",
+                context, memory_mode
+            ));
+        }
+
+        let buffered = first.buffered.unwrap_or(false);
+        if jobs.iter().any(|j| j.buffered.unwrap_or(false) != buffered) {
+            warnings.warn(format!(
+                "{}: jobs mix direct and buffered I/O, using {} for all",
+                context,
+                if buffered { "buffered" } else { "direct" }
+            ));
+        }
+        let sync = first.sync.unwrap_or_default();
+        if jobs.iter().any(|j| j.sync.unwrap_or_default() != sync) {
+            warnings.warn(format!(
+                "{}: jobs use different sync settings, using {} for all",
+                context, sync
+            ));
+        }
+
+        // fio allows repeated job names, job_configs() does not
+        let mut seen = HashSet::new();
+        for job in jobs.iter_mut() {
+            let base = job.spec.name.clone();
+            let mut n = 1;
+            while !seen.insert(job.spec.name.clone()) {
+                n += 1;
+                job.spec.name = format!("{}-{}", base, n);
+            }
+        }
+
+        let first = &jobs[0];
+        let config = Config {
+            devices,
+            size,
+            filename_format: first.filename_format.clone(),
+            workload: first.spec.workload.unwrap_or(Workload::SeqRead),
+            block_size: first.spec.block_size.unwrap_or(4096),
+            queue_depth: first.spec.queue_depth.unwrap_or(1),
+            threads: first.spec.threads.unwrap_or(1),
+            duration: runtime.unwrap_or(DEFAULT_RUNTIME),
+            cpus: first.cpus.clone(),
+            cpu_mapping: first.cpu_mapping.unwrap_or_default(),
+            memory_mode,
+            buffered,
+            sync,
+            ramp_time: ramp_time.unwrap_or(Duration::ZERO),
+            jobs: jobs.into_iter().map(|j| j.spec).collect(),
+            ..Default::default()
+        };
+        config
+            .job_configs()
+            .with_context(|| format!("{}: invalid job set", context))?;
+        Ok(JobGroup { name, config })
+    }
+}
+
+impl FioJob {
+    /// A job with fio's defaults: rw=read, bs=4k, iodepth=1, numjobs=1
+    fn new(name: String) -> Self {
+        Self {
+            spec: JobSpec {
+                name,
+                workload: Some(Workload::SeqRead),
+                block_size: Some(4096),
+                queue_depth: Some(1),
+                threads: Some(1),
+                ..Default::default()
+            },
+            devices: None,
+            runtime: None,
+            ramp_time: None,
+            cpus: None,
+            cpu_mapping: None,
+            memory_mode: None,
+            buffered: None,
+            sync: None,
+            size: None,
+            directories: None,
+            filename_format: None,
+            trim_percent: None,
+            stonewall: false,
+        }
+    }
+
+    /// Apply one option; later options override earlier ones as in fio
+    fn apply(&mut self, option: &FioOption, origin: &str, warnings: &mut Warnings) -> Result<()> {
+        let key = option.key.as_str();
+        let context = format!("{} (line {})", origin, option.line);
+        let value = || {
+            option
+                .value
+                .as_deref()
+                .filter(|v| !v.is_empty())
+                .ok_or_else(|| anyhow::anyhow!("{}: option '{}' needs a value", context, key))
+        };
+        let invalid = || format!("{}: invalid value for '{}'", context, key);
+
+        match key {
+            // Reporting and fio-internal behavior with no effect on the I/O issued here
+            "name"
+            | "description"
+            | "group_reporting"
+            | "new_group"
+            | "thread"
+            | "time_based"
+            | "randrepeat"
+            | "allrandrepeat"
+            | "norandommap"
+            | "random_generator"
+            | "refill_buffers"
+            | "invalidate"
+            | "gtod_reduce"
+            | "clat_percentiles"
+            | "lat_percentiles"
+            | "percentile_list"
+            | "exitall"
+            | "unified_rw_reporting" => {}
+
+            "filename" => {
+                let names: Vec<String> = value()?.split(':').map(str::to_string).collect();
+                self.devices = Some(config::expand_devices(&names).with_context(invalid)?);
+            }
+            "rw" | "readwrite" => {
+                self.spec.workload = Some(parse_rw(value()?).with_context(invalid)?)
+            }
+            "rwmixread" => {
+                self.spec.read_percent =
+                    Some(config::parse_percent(value()?).with_context(invalid)?)
+            }
+            "rwmixwrite" => {
+                let write = config::parse_percent(value()?).with_context(invalid)?;
+                self.spec.read_percent = Some(100 - write);
+            }
+            "bs" | "blocksize" => {
+                let bs = read_write_value(value()?, key, &context, warnings);
+                self.spec.block_size = Some(parse_size(bs).with_context(invalid)?);
+            }
+            "bssplit" => {
+                let split = read_write_value(value()?, key, &context, warnings);
+                let split: BlockSizeSplit = normalize_sizes(split).parse().with_context(invalid)?;
+                self.spec.block_size = Some(split.min_block_size());
+                self.spec.block_size_split = Some(split);
+            }
+            "iodepth" => self.spec.queue_depth = Some(parse_count(value()?).with_context(invalid)?),
+            "numjobs" => self.spec.threads = Some(parse_count(value()?).with_context(invalid)?),
+            "runtime" => self.runtime = Some(parse_time(value()?).with_context(invalid)?),
+            "ramp_time" => self.ramp_time = Some(parse_time(value()?).with_context(invalid)?),
+            "rate" => {
+                let rate = read_write_value(value()?, key, &context, warnings);
+                self.spec.rate = Some(RateLimit::BytesPerSec(
+                    parse_size(rate).with_context(invalid)? as u64,
+                ));
+            }
+            "rate_iops" => {
+                let rate = read_write_value(value()?, key, &context, warnings);
+                self.spec.rate = Some(RateLimit::Iops(
+                    rate.trim()
+                        .parse()
+                        .map_err(|_| anyhow::anyhow!("{}", invalid()))?,
+                ));
+            }
+            "fsync" | "fdatasync" => {
+                let kind = if key == "fsync" {
+                    FlushKind::Fsync
+                } else {
+                    FlushKind::Fdatasync
+                };
+                let policy = FlushPolicy::every(kind, value()?).with_context(invalid)?;
+                match (self.spec.flush, policy) {
+                    // Workers issue one kind of flush, so both cannot be honored
+                    (Some(current), Some(_)) if current.kind != kind => {
+                        return Err(anyhow::anyhow!(
+                            "{}: fsync and fdatasync cannot both be set",
+                            context
+                        ))
+                    }
+                    (Some(current), None) if current.kind != kind => {}
+                    _ => self.spec.flush = policy,
+                }
+            }
+            "random_distribution" => {
+                let distribution: AccessDistribution = value()?.parse().with_context(invalid)?;
+                self.spec.distribution = Some(distribution);
+            }
+            "cpus_allowed" => {
+                self.cpus =
+                    Some(crate::monitor::numa::parse_cpu_list(value()?).with_context(invalid)?)
+            }
+            "cpus_allowed_policy" => {
+                self.cpu_mapping = Some(match value()? {
+                    "shared" => CpuMapping::RoundRobin,
+                    "split" => CpuMapping::OneToOne,
+                    _ => return Err(anyhow::anyhow!("{}", invalid())),
+                })
+            }
+            "mem" | "iomem" => {
+                let mode = value()?;
+                self.memory_mode = Some(if mode.starts_with("mmaphuge") || mode == "shmhuge" {
+                    MemoryMode::HugePage
+                } else {
+                    MemoryMode::Anon
+                });
+            }
+            "stonewall" | "wait_for_previous" => self.stonewall = flag(option),
+            "ioengine" => {
+                if !matches!(value()?, "io_uring" | "libaio") {
+                    warnings.warn(format!(
+                        "{}: ioengine={} is not available, running on io_uring",
+                        context,
+                        value()?
+                    ));
+                }
+            }
+            "direct" => self.buffered = Some(!flag(option)),
+            "buffered" => self.buffered = Some(flag(option)),
+            "sync" => self.sync = Some(value()?.parse().with_context(invalid)?),
+            "trim_percentage" => {
+                self.trim_percent = Some(config::parse_percent(value()?).with_context(invalid)?)
+            }
+            "size" | "filesize" => {
+                self.size = Some(parse_size(value()?).with_context(invalid)? as u64)
+            }
+            "directory" => {
+                let names: Vec<String> = value()?.split(':').map(str::to_string).collect();
+                self.directories = Some(config::expand_devices(&names).with_context(invalid)?);
+            }
+            "filename_format" => self.filename_format = Some(value()?.replace("$filenum", "0")),
+            "nrfiles" => {
+                if parse_count(value()?).with_context(invalid)? != 1 {
+                    return Err(anyhow::anyhow!(
+                        "{}: nrfiles above 1 is not supported (each worker uses one file)",
+                        context
+                    ));
+                }
+            }
+            "io_size" | "io_limit" | "offset" => warnings.warn(format!(
+                "{}: option '{}' is not supported, ignored (jobs span the whole target)",
+                context, key
+            )),
+
+            // These change what is written or when it is flushed; running without
+            // them would produce results that are not comparable
+            "verify" | "do_verify" | "end_fsync" | "fsync_on_close" | "bsrange"
+            | "blocksize_range" | "zonemode" | "zonesize" | "zonerange" | "zoneskip"
+            | "percentage_random" => {
+                return Err(anyhow::anyhow!(
+                    "{}: option '{}' is not supported",
+                    context,
+                    key
+                ))
+            }
+
+            _ => warnings.warn(format!("{}: unknown option '{}' ignored", context, key)),
+        }
+        Ok(())
+    }
+
+    /// Turn trim_percentage into an operation mix: that share of operations are
+    /// discards, the rest split by the job's read/write mix
+    fn resolve_trims(&mut self, context: &str) -> Result<()> {
+        let Some(trim) = self.trim_percent.filter(|&pct| pct > 0) else {
+            return Ok(());
+        };
+        let workload = self.spec.workload.unwrap_or(Workload::SeqRead);
+        if workload == Workload::Trim {
+            return Err(anyhow::anyhow!(
+                "{}: trim_percentage needs a read or write rw mode, not trim",
+                context
+            ));
+        }
+        let read = self.spec.read_percent.unwrap_or(workload.read_percent()) as u32;
+        let rest = 100 - trim as u32;
+        let reads = rest * read / 100;
+        let mix = format!("read/{}:write/{}:discard/{}", reads, rest - reads, trim);
+        self.spec.op_mix = Some(
+            mix.parse()
+                .with_context(|| format!("{}: invalid value for 'trim_percentage'", context))?,
+        );
+        Ok(())
+    }
+
+    /// Resolve filename, directory and filename_format the way fio combines them:
+    /// filenames are relative to the directories, and without a filename each
+    /// worker gets its own file named by the format
+    fn resolve_files(&mut self) {
+        match (self.devices.take(), self.directories.take()) {
+            (Some(files), Some(directories)) => {
+                self.devices = Some(
+                    directories
+                        .iter()
+                        .flat_map(|d| files.iter().map(move |f| d.join(f)))
+                        .collect(),
+                );
+                self.filename_format = None;
+            }
+            (Some(files), None) => {
+                self.devices = Some(files);
+                self.filename_format = None;
+            }
+            (None, Some(directories)) => {
+                self.devices = Some(directories);
+                self.filename_format
+                    .get_or_insert_with(|| FIO_FILENAME_FORMAT.to_string());
+            }
+            (None, None) => {
+                if self.filename_format.is_some() {
+                    self.devices = Some(vec![PathBuf::from(".")]);
+                }
+            }
+        }
+    }
+}
+
+/// Warnings printed once each, so a [global] option does not warn for every job
+#[derive(Default)]
+struct Warnings {
+    seen: HashSet<String>,
+}
+
+impl Warnings {
+    fn warn(&mut self, message: String) {
+        if self.seen.insert(message.clone()) {
+            eprintln!("Warning: fio {}", message);
+        }
+    }
+}
+
+/// Longest value among the jobs, warning when they disagree
+fn longest<T: Ord + Copy + Default + std::fmt::Debug>(
+    jobs: &[FioJob],
+    get: impl Fn(&FioJob) -> Option<T>,
+    key: &str,
+    context: &str,
+    warnings: &mut Warnings,
+) -> Option<T> {
+    let values: Vec<T> = jobs.iter().filter_map(&get).collect();
+    let longest = values.iter().max().copied();
+    if values.iter().any(|v| Some(*v) != longest) {
+        warnings.warn(format!(
+            "{}: jobs use different {} values, using the longest ({:?}) for all",
+            context,
+            key,
+            longest.unwrap_or_default()
+        ));
+    }
+    longest
+}
+
+fn parse_rw(value: &str) -> Result<Workload> {
+    if value.contains(':') {
+        return Err(anyhow::anyhow!(
+            "rw offset modifiers are not supported: {}",
+            value
+        ));
+    }
+    match value {
+        "read" => Ok(Workload::SeqRead),
+        "write" => Ok(Workload::SeqWrite),
+        "randread" => Ok(Workload::RandRead),
+        "randwrite" => Ok(Workload::RandWrite),
+        "rw" | "readwrite" => Ok(Workload::Seq),
+        "randrw" => Ok(Workload::Rand),
+        "trim" | "randtrim" => Ok(Workload::Trim),
+        _ => Err(anyhow::anyhow!("unsupported rw mode: {}", value)),
+    }
+}
+
+/// fio options like bs and rate take "read,write[,trim]"; only one value applies here
+fn read_write_value<'a>(
+    value: &'a str,
+    key: &str,
+    context: &str,
+    warnings: &mut Warnings,
+) -> &'a str {
+    let mut parts = value.split(',').map(str::trim);
+    let first = parts.next().unwrap_or("");
+    let rest: Vec<&str> = parts.filter(|p| !p.is_empty()).collect();
+    if rest.iter().any(|p| *p != first) {
+        warnings.warn(format!(
+            "{}: separate read/write values for '{}' are not supported, using {}",
+            context, key, first
+        ));
+    }
+    first
+}
+
+/// fio sizes accept "4k", "4K", "4KiB" and "4kb", all base 1024
+fn parse_size(value: &str) -> Result<usize> {
+    config::parse_block_size(&normalize_sizes(value))
+}
+
+fn normalize_sizes(value: &str) -> String {
+    value
+        .to_lowercase()
+        .replace("ib", "")
+        .replace("kb", "k")
+        .replace("mb", "m")
+        .replace("gb", "g")
+}
+
+fn parse_count(value: &str) -> Result<usize> {
+    value
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .filter(|n| *n > 0)
+        .ok_or_else(|| anyhow::anyhow!("expected a positive number, got {}", value))
+}
+
+/// fio times default to seconds and also accept "us"
+fn parse_time(value: &str) -> Result<Duration> {
+    match value.trim().to_lowercase().strip_suffix("us") {
+        Some(us) => us
+            .trim()
+            .parse()
+            .map(Duration::from_micros)
+            .map_err(|_| anyhow::anyhow!("Invalid duration: {}", value)),
+        None => config::parse_duration(value),
+    }
+}
+
+/// Boolean options are either bare ("stonewall") or take 0/1
+fn flag(option: &FioOption) -> bool {
+    option.value.as_deref().is_none_or(|v| v != "0")
+}
+
+/// Replace ${NAME} with the environment variable's value
+fn expand_env(line: &str) -> Result<String> {
+    let mut expanded = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find("${") {
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| anyhow::anyhow!("unterminated ${{...}} in {}", line))?;
+        let name = &rest[start + 2..start + end];
+        let value = std::env::var(name)
+            .map_err(|_| anyhow::anyhow!("environment variable {} is not set", name))?;
+        expanded.push_str(&rest[..start]);
+        expanded.push_str(&value);
+        rest = &rest[start + end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translate(content: &str) -> Result<Vec<JobGroup>> {
+        FioJobFile::parse(content)?.into_groups(None)
+    }
+
+    #[test]
+    fn jobs_inherit_global_bssplit_and_rwmix() {
+        let groups = translate(
+            "[global]\n\
+             filename=/dev/fixture\n\
+             rw=randrw\n\
+             rwmixread=70\n\
+             bssplit=4k/50:64k/50\n\
+             iodepth=8\n\
+             [a]\n\
+             [b]\n\
+             rwmixwrite=10\n\
+             bssplit=8k/100\n",
+        )
+        .unwrap();
+        assert_eq!(groups.len(), 1);
+        let config = &groups[0].config;
+        assert_eq!(config.devices, vec![PathBuf::from("/dev/fixture")]);
+        assert_eq!(config.workload, Workload::Rand);
+        assert_eq!(config.queue_depth, 8);
+
+        let (a, b) = (&config.jobs[0], &config.jobs[1]);
+        assert_eq!(a.read_percent, Some(70));
+        assert_eq!(
+            a.block_size_split.as_ref().unwrap().entries(),
+            &[(4096, 50), (65536, 50)]
+        );
+        assert_eq!(a.block_size, Some(4096));
+        assert_eq!(b.read_percent, Some(90));
+        assert_eq!(
+            b.block_size_split.as_ref().unwrap().entries(),
+            &[(8192, 100)]
+        );
+        assert_eq!(b.queue_depth, Some(8));
+    }
+
+    #[test]
+    fn stonewall_starts_a_new_group() {
+        let groups =
+            translate("[global]\nfilename=/dev/fixture\n[a]\n[b]\n[c]\nstonewall\nrw=write\n")
+                .unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].config.jobs.len(), 2);
+        assert_eq!(groups[1].name, "c");
+        assert_eq!(groups[1].config.workload, Workload::SeqWrite);
+    }
+
+    #[test]
+    fn fsync_and_fdatasync_conflict() {
+        let err =
+            translate("[global]\nfilename=/dev/fixture\nfsync=4\n[a]\nfdatasync=2\n").unwrap_err();
+        assert!(
+            format!("{:#}", err).contains("cannot both be set"),
+            "{:#}",
+            err
+        );
+
+        // Turning one off first leaves room for the other
+        let groups = translate(
+            "[global]\nfilename=/dev/fixture\nrw=write\nfsync=4\n[a]\nfsync=0\nfdatasync=2\n",
+        )
+        .unwrap();
+        assert_eq!(
+            groups[0].config.jobs[0].flush,
+            FlushPolicy::new(FlushKind::Fdatasync, 2)
+        );
+    }
+
+    #[test]
+    fn zero_interval_of_the_other_kind_keeps_the_flush() {
+        let groups =
+            translate("[a]\nfilename=/dev/fixture\nrw=write\nfsync=4\nfdatasync=0\n").unwrap();
+        assert_eq!(
+            groups[0].config.jobs[0].flush,
+            FlushPolicy::new(FlushKind::Fsync, 4)
+        );
+    }
+
+    #[test]
+    fn maps_direct_sync_and_files() {
+        let groups =
+            translate("[a]\ndirectory=/srv/bench\nsize=64m\ndirect=0\nsync=dsync\nnrfiles=1\n")
+                .unwrap();
+        let config = &groups[0].config;
+        assert!(config.buffered);
+        assert_eq!(config.sync, SyncMode::Dsync);
+        assert_eq!(config.devices, vec![PathBuf::from("/srv/bench")]);
+        assert_eq!(config.size, Some(64 << 20));
+        assert_eq!(config.filename_format.as_deref(), Some(FIO_FILENAME_FORMAT));
+
+        let groups = translate("[a]\ndirectory=/srv\nfilename=data\n").unwrap();
+        assert_eq!(groups[0].config.devices, vec![PathBuf::from("/srv/data")]);
+        assert_eq!(groups[0].config.filename_format, None);
+    }
+
+    #[test]
+    fn trim_percentage_becomes_an_op_mix() {
+        let groups =
+            translate("[a]\nfilename=/dev/fixture\nrw=randrw\nrwmixread=50\ntrim_percentage=20\n")
+                .unwrap();
+        let mix = groups[0].config.jobs[0].op_mix.clone().unwrap();
+        assert_eq!(mix, "read/40:write/40:discard/20".parse().unwrap());
+    }
+
+    #[test]
+    fn rejects_unsupported_and_malformed_input() {
+        let err = translate("[a]\nfilename=/dev/fixture\nverify=crc32c\n").unwrap_err();
+        assert!(format!("{:#}", err).contains("'verify' is not supported"));
+        assert!(translate("[a]\nfilename=/dev/fixture\nnrfiles=4\n").is_err());
+        assert!(translate("[a]\nfilename=/dev/fixture\nbs=huge\n").is_err());
+        assert!(FioJobFile::parse("[a\nrw=read\n").is_err());
+        assert!(translate("[global]\nrw=read\n").is_err());
+        assert!(translate("[a]\nrw=read\n").is_err());
+    }
+}
@@ -0,0 +1,376 @@
+use crate::io::device::{read_string, DeviceInfo};
+use crate::io::safety;
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What a block device is in the storage stack
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlockKind {
+    Disk,
+    Part,
+    Loop,
+    Md,
+    Lvm,
+    Crypt,
+    Dm,
+    Ram,
+}
+
+impl std::str::FromStr for BlockKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "disk" => Ok(BlockKind::Disk),
+            "part" | "partition" => Ok(BlockKind::Part),
+            "loop" => Ok(BlockKind::Loop),
+            "md" | "raid" => Ok(BlockKind::Md),
+            "lvm" => Ok(BlockKind::Lvm),
+            "crypt" => Ok(BlockKind::Crypt),
+            "dm" => Ok(BlockKind::Dm),
+            "ram" | "zram" => Ok(BlockKind::Ram),
+            _ => Err(anyhow::anyhow!(
+                "Invalid device type: {}. Valid options: disk, part, loop, md, lvm, crypt, dm, ram",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for BlockKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            BlockKind::Disk => "disk",
+            BlockKind::Part => "part",
+            BlockKind::Loop => "loop",
+            BlockKind::Md => "md",
+            BlockKind::Lvm => "lvm",
+            BlockKind::Crypt => "crypt",
+            BlockKind::Dm => "dm",
+            BlockKind::Ram => "ram",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A block device with its partitions and the devices stacked on it
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockDevice {
+    /// Kernel name, e.g. nvme0n1p1 or dm-0
+    pub name: String,
+    pub path: PathBuf,
+    pub kind: BlockKind,
+    pub size: u64,
+    pub model: Option<String>,
+    pub serial: Option<String>,
+    pub firmware: Option<String>,
+    /// nvme, nvme-tcp (or another fabric), sata, sas, usb, virtio, iscsi or
+    /// mmc; disks only
+    pub transport: Option<String>,
+    pub rotational: Option<bool>,
+    /// dm or LVM name, md RAID level, or a loop device's backing file
+    pub label: Option<String>,
+    /// Mount point, or "[SWAP]" for active swap
+    pub mount_point: Option<String>,
+    /// Devices this one is built from (md members, dm/LVM physical volumes)
+    pub slaves: Vec<String>,
+    /// Devices built on this one
+    pub holders: Vec<String>,
+    /// Mounted, swapped on or held by another device, itself or through a child
+    pub in_use: bool,
+    pub children: Vec<BlockDevice>,
+}
+
+/// Which devices `list` shows. A device matching the filters is shown with
+/// all its children; one that doesn't is shown only to hold a matching child.
+#[derive(Debug, Clone, Default)]
+pub struct ListFilter {
+    /// Include empty devices, RAM disks and unattached loop devices
+    pub all: bool,
+    pub kinds: Vec<BlockKind>,
+    pub transport: Option<String>,
+    pub name: Option<glob::Pattern>,
+    /// Only devices that are not in use
+    pub unused: bool,
+}
+
+impl ListFilter {
+    pub fn apply(&self, devices: Vec<BlockDevice>) -> Vec<BlockDevice> {
+        devices
+            .into_iter()
+            .filter(|device| self.all || !hidden(device))
+            .filter_map(|mut device| {
+                if self.matches(&device) {
+                    if !self.all {
+                        device.children = self.visible(device.children);
+                    }
+                    return Some(device);
+                }
+                device.children = self.apply(device.children);
+                (!device.children.is_empty()).then_some(device)
+            })
+            .collect()
+    }
+
+    fn matches(&self, device: &BlockDevice) -> bool {
+        (self.kinds.is_empty() || self.kinds.contains(&device.kind))
+            && self.transport.as_ref().is_none_or(|t| {
+                device
+                    .transport
+                    .as_ref()
+                    .is_some_and(|own| own.eq_ignore_ascii_case(t) || own.starts_with(&format!("{}-", t)))
+            })
+            && self.name.as_ref().is_none_or(|p| p.matches(&device.name))
+            && (!self.unused || !device.in_use)
+    }
+
+    fn visible(&self, devices: Vec<BlockDevice>) -> Vec<BlockDevice> {
+        devices
+            .into_iter()
+            .filter(|device| !hidden(device))
+            .map(|mut device| {
+                device.children = self.visible(device.children);
+                device
+            })
+            .collect()
+    }
+}
+
+/// Devices `list` leaves out unless asked for all of them
+fn hidden(device: &BlockDevice) -> bool {
+    device.size == 0
+        || device.kind == BlockKind::Ram
+        || (device.kind == BlockKind::Loop && device.label.is_none())
+}
+
+/// Every block device in a sysfs tree (normally /sys), as a forest: whole
+/// disks and loop/RAM devices at the top, partitions and stacked devices
+/// (md, dm, LVM) below what they are built on. A device built on several
+/// others appears under each of them.
+pub fn list(sysfs: &Path) -> Result<Vec<BlockDevice>> {
+    let mounts = safety::mounts().unwrap_or_default();
+    let swaps = swaps();
+    let mut devices = HashMap::new();
+    let mut parents = HashMap::new();
+    for entry in fs::read_dir(sysfs.join("class/block"))? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let Ok(dir) = fs::canonicalize(entry.path()) else {
+            continue;
+        };
+        let parent = dir.join("partition").exists().then(|| {
+            dir.parent()
+                .and_then(Path::file_name)
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        });
+        devices.insert(
+            name.clone(),
+            read_device(sysfs, &name, &dir, parent.as_deref(), &mounts, &swaps),
+        );
+        if let Some(parent) = parent {
+            parents.insert(name, parent);
+        }
+    }
+
+    let mut roots: Vec<&String> = devices
+        .iter()
+        .filter(|(name, device)| !parents.contains_key(*name) && device.slaves.is_empty())
+        .map(|(name, _)| name)
+        .collect();
+    roots.sort_by_key(|name| natural_key(name));
+    Ok(roots
+        .into_iter()
+        .map(|name| build(name, &devices, &parents, 0))
+        .collect())
+}
+
+/// A device with its partitions and holders attached, depth-limited in case
+/// sysfs ever reports a loop
+fn build(
+    name: &str,
+    devices: &HashMap<String, BlockDevice>,
+    parents: &HashMap<String, String>,
+    depth: usize,
+) -> BlockDevice {
+    let mut device = devices[name].clone();
+    if depth < 16 {
+        let mut partitions: Vec<&String> = parents
+            .iter()
+            .filter(|(_, parent)| *parent == name)
+            .map(|(partition, _)| partition)
+            .collect();
+        partitions.sort_by_key(|name| natural_key(name));
+        let mut holders: Vec<&String> = device
+            .holders
+            .iter()
+            .filter(|holder| devices.contains_key(*holder))
+            .collect();
+        holders.sort_by_key(|name| natural_key(name));
+        device.children = partitions
+            .into_iter()
+            .chain(holders)
+            .map(|child| build(child, devices, parents, depth + 1))
+            .collect();
+    }
+    device.in_use = device.mount_point.is_some()
+        || !device.holders.is_empty()
+        || device.children.iter().any(|child| child.in_use);
+    device
+}
+
+fn read_device(
+    sysfs: &Path,
+    name: &str,
+    dir: &Path,
+    parent: Option<&str>,
+    mounts: &[(String, String)],
+    swaps: &[String],
+) -> BlockDevice {
+    let path = PathBuf::from("/dev").join(name);
+    let size = read_string(&dir.join("size"))
+        .and_then(|s| s.parse::<u64>().ok())
+        .map(|sectors| sectors * 512)
+        .unwrap_or(0);
+    let dm_uuid = read_string(&dir.join("dm/uuid")).unwrap_or_default();
+    let kind = if parent.is_some() {
+        BlockKind::Part
+    } else if name.starts_with("loop") {
+        BlockKind::Loop
+    } else if name.starts_with("ram") || name.starts_with("zram") {
+        BlockKind::Ram
+    } else if dir.join("md").is_dir() {
+        BlockKind::Md
+    } else if dir.join("dm").is_dir() {
+        if dm_uuid.starts_with("LVM-") {
+            BlockKind::Lvm
+        } else if dm_uuid.starts_with("CRYPT-") {
+            BlockKind::Crypt
+        } else {
+            BlockKind::Dm
+        }
+    } else {
+        BlockKind::Disk
+    };
+    let label = match kind {
+        BlockKind::Loop => read_string(&dir.join("loop/backing_file")),
+        BlockKind::Md => read_string(&dir.join("md/level")),
+        BlockKind::Lvm | BlockKind::Crypt | BlockKind::Dm => read_string(&dir.join("dm/name")),
+        _ => None,
+    };
+
+    let (model, firmware, serial, transport) = if kind == BlockKind::Disk {
+        let info = DeviceInfo::from_sysfs(sysfs, name, path.clone(), size);
+        (info.model, info.firmware, serial(dir), transport(dir))
+    } else {
+        (None, None, None, None)
+    };
+    // Partitions share their disk's queue
+    let queue_dir = match parent {
+        Some(_) => dir.parent().unwrap_or(dir).join("queue"),
+        None => dir.join("queue"),
+    };
+    let rotational = read_string(&queue_dir.join("rotational")).map(|r| r == "1");
+
+    let mount_point = if swaps.iter().any(|swap| swap == name) {
+        Some("[SWAP]".to_string())
+    } else {
+        safety::mount_point(mounts, dir).map(str::to_string)
+    };
+
+    BlockDevice {
+        name: name.to_string(),
+        path,
+        kind,
+        size,
+        model: model.filter(|m| !m.is_empty()),
+        serial,
+        firmware: firmware.filter(|f| !f.is_empty()),
+        transport,
+        rotational,
+        label,
+        mount_point,
+        slaves: entry_names(&dir.join("slaves")),
+        holders: entry_names(&dir.join("holders")),
+        in_use: false,
+        children: Vec::new(),
+    }
+}
+
+/// Serial number: NVMe and virtio report it as text, SCSI in VPD page 0x80
+fn serial(dir: &Path) -> Option<String> {
+    read_string(&dir.join("device/serial"))
+        .or_else(|| read_string(&dir.join("serial")))
+        .or_else(|| {
+            let page = fs::read(dir.join("device/vpd_pg80")).ok()?;
+            let serial = String::from_utf8_lossy(page.get(4..)?).trim().to_string();
+            Some(serial)
+        })
+        .filter(|s| !s.is_empty())
+}
+
+/// How a disk is attached, from the NVMe controller's transport or the
+/// device's position in the sysfs hierarchy
+fn transport(dir: &Path) -> Option<String> {
+    if let Some(transport) = read_string(&dir.join("device/transport")) {
+        return Some(match transport.as_str() {
+            "pcie" => "nvme".to_string(),
+            fabric => format!("nvme-{}", fabric),
+        });
+    }
+    let path = dir.to_string_lossy();
+    let transport = if path.contains("/usb") {
+        "usb"
+    } else if path.contains("/ata") {
+        "sata"
+    } else if path.contains("/end_device-") {
+        "sas"
+    } else if path.contains("/session") {
+        "iscsi"
+    } else if path.contains("/virtio") {
+        "virtio"
+    } else if path.contains("/mmc_host") {
+        "mmc"
+    } else {
+        return None;
+    };
+    Some(transport.to_string())
+}
+
+/// Kernel names of the devices in active swap
+fn swaps() -> Vec<String> {
+    let Ok(content) = fs::read_to_string("/proc/swaps") else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().next())
+        .filter_map(|path| fs::canonicalize(path).ok())
+        .filter_map(|path| path.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .collect()
+}
+
+fn entry_names(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.file_name().to_string_lossy().into_owned())
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort_by_key(|name| natural_key(name));
+    names
+}
+
+/// Sort key putting sda2 before sda10
+fn natural_key(name: &str) -> (String, usize, String) {
+    let digits = name.len() - name.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    let (prefix, number) = name.split_at(name.len() - digits);
+    (prefix.to_string(), number.len(), number.to_string())
+}
@@ -0,0 +1,269 @@
+use crate::config::MemoryMode;
+use anyhow::Result;
+use std::fs;
+
+// Buffers are page aligned, which also satisfies O_DIRECT's sector alignment
+const PAGE_SIZE: usize = 4096;
+// Used when /proc/meminfo does not report Hugepagesize
+const DEFAULT_HUGE_PAGE_SIZE: usize = 2 * 1024 * 1024;
+// Synchronous THP collapse (Linux 6.1+); not exported by libc yet
+const MADV_COLLAPSE: libc::c_int = 25;
+
+/// One contiguous mapping carved into equally sized, page-aligned I/O buffers
+/// (one per queue slot so each in-flight operation owns its buffer)
+pub struct BufferPool {
+    ptr: *mut u8,
+    mapping_len: usize,
+    slot_size: usize,
+    buffer_len: usize,
+    count: usize,
+    mode: MemoryMode,
+}
+
+// The pool owns its mapping exclusively; it is only moved between threads, never shared
+unsafe impl Send for BufferPool {}
+
+impl BufferPool {
+    /// Allocate `count` buffers of `buffer_len` bytes, falling back from
+    /// hugepage -> thp -> anon when the requested mode is unavailable
+    pub fn new(count: usize, buffer_len: usize, requested: MemoryMode) -> Result<Self> {
+        let slot_size = buffer_len.div_ceil(PAGE_SIZE) * PAGE_SIZE;
+        let total = slot_size * count.max(1);
+
+        let mut mode = requested;
+        loop {
+            let mapped = match mode {
+                MemoryMode::HugePage => Self::map_hugetlb(total),
+                MemoryMode::Thp => Self::map_thp(total),
+                MemoryMode::Anon => Self::map_anon(total, true),
+            };
+
+            match mapped {
+                Some((ptr, mapping_len)) => {
+                    // The kernel may still have handed out small pages
+                    if mode == MemoryMode::Thp && !thp_backed(ptr) {
+                        mode = MemoryMode::Anon;
+                    }
+                    if mode != requested {
+                        eprintln!(
+                            "Warning: {} buffers unavailable, using {} memory",
+                            requested, mode
+                        );
+                    }
+                    return Ok(Self {
+                        ptr,
+                        mapping_len,
+                        slot_size,
+                        buffer_len,
+                        count,
+                        mode,
+                    });
+                }
+                None => {
+                    mode = match mode {
+                        MemoryMode::HugePage => MemoryMode::Thp,
+                        MemoryMode::Thp => MemoryMode::Anon,
+                        MemoryMode::Anon => {
+                            return Err(anyhow::anyhow!(
+                                "Failed to allocate {} bytes of I/O buffers: {}",
+                                total,
+                                std::io::Error::last_os_error()
+                            ))
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn map_anon(len: usize, populate: bool) -> Option<(*mut u8, usize)> {
+        let flags = libc::MAP_PRIVATE
+            | libc::MAP_ANONYMOUS
+            | if populate { libc::MAP_POPULATE } else { 0 };
+        Self::mmap(len, flags)
+    }
+
+    fn map_hugetlb(len: usize) -> Option<(*mut u8, usize)> {
+        let huge_page_size = huge_page_size();
+        let len = len.div_ceil(huge_page_size) * huge_page_size;
+        Self::mmap(
+            len,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_HUGETLB | libc::MAP_POPULATE,
+        )
+    }
+
+    fn map_thp(len: usize) -> Option<(*mut u8, usize)> {
+        if !thp_available() {
+            return None;
+        }
+        let huge_page_size = huge_page_size();
+        let len = len.div_ceil(huge_page_size) * huge_page_size;
+
+        // mmap only aligns to pages, but huge pages can only back aligned extents:
+        // over-allocate by one huge page and trim the slack on both sides
+        let (raw, raw_len) = Self::map_anon(len + huge_page_size, false)?;
+        let head = (raw as usize).next_multiple_of(huge_page_size) - raw as usize;
+        let tail = raw_len - head - len;
+        let ptr = unsafe { raw.add(head) };
+        unsafe {
+            if head > 0 {
+                libc::munmap(raw as *mut libc::c_void, head);
+            }
+            if tail > 0 {
+                libc::munmap(ptr.add(len) as *mut libc::c_void, tail);
+            }
+        }
+
+        // Advise before the first touch so the fault path can hand out huge pages
+        let result = unsafe { libc::madvise(ptr as *mut libc::c_void, len, libc::MADV_HUGEPAGE) };
+        if result != 0 {
+            unsafe { libc::munmap(ptr as *mut libc::c_void, len) };
+            return None;
+        }
+        unsafe { std::ptr::write_bytes(ptr, 0, len) };
+        if !thp_backed(ptr) {
+            // Best effort: fragmented memory may still collapse on request
+            unsafe { libc::madvise(ptr as *mut libc::c_void, len, MADV_COLLAPSE) };
+        }
+        Some((ptr, len))
+    }
+
+    fn mmap(len: usize, flags: libc::c_int) -> Option<(*mut u8, usize)> {
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                flags,
+                -1,
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            None
+        } else {
+            Some((ptr as *mut u8, len))
+        }
+    }
+
+    /// Memory mode actually backing the buffers
+    pub fn mode(&self) -> MemoryMode {
+        self.mode
+    }
+
+    /// Number of buffers in the pool
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Usable length of each buffer
+    pub fn buffer_len(&self) -> usize {
+        self.buffer_len
+    }
+
+    /// Pointer to the start of buffer `index`
+    pub fn ptr(&self, index: usize) -> *mut u8 {
+        debug_assert!(index < self.count);
+        unsafe { self.ptr.add(index * self.slot_size) }
+    }
+
+    /// iovecs for registering every buffer with io_uring
+    pub fn iovecs(&self) -> Vec<libc::iovec> {
+        (0..self.count)
+            .map(|i| libc::iovec {
+                iov_base: self.ptr(i) as *mut libc::c_void,
+                iov_len: self.buffer_len,
+            })
+            .collect()
+    }
+}
+
+impl Drop for BufferPool {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.ptr as *mut libc::c_void, self.mapping_len);
+        }
+    }
+}
+
+/// Default huge page size from /proc/meminfo
+fn huge_page_size() -> usize {
+    fs::read_to_string("/proc/meminfo")
+        .ok()
+        .and_then(|content| {
+            content
+                .lines()
+                .find(|line| line.starts_with("Hugepagesize:"))
+                .and_then(|line| line.split_whitespace().nth(1))
+                .and_then(|kb| kb.parse::<usize>().ok())
+        })
+        .map(|kb| kb * 1024)
+        .unwrap_or(DEFAULT_HUGE_PAGE_SIZE)
+}
+
+/// Whether the mapping containing `ptr` has any anonymous huge pages, per /proc/self/smaps
+fn thp_backed(ptr: *mut u8) -> bool {
+    let Ok(smaps) = fs::read_to_string("/proc/self/smaps") else {
+        return false;
+    };
+    let addr = ptr as usize;
+    let mut in_mapping = false;
+    for line in smaps.lines() {
+        // Mapping headers start with "start-end", field lines with "Name:"
+        let range = line
+            .split_whitespace()
+            .next()
+            .and_then(|field| field.split_once('-'))
+            .and_then(|(start, end)| {
+                Some((
+                    usize::from_str_radix(start, 16).ok()?,
+                    usize::from_str_radix(end, 16).ok()?,
+                ))
+            });
+        if let Some((start, end)) = range {
+            in_mapping = (start..end).contains(&addr);
+        } else if in_mapping {
+            if let Some(kb) = line.strip_prefix("AnonHugePages:") {
+                return kb
+                    .trim()
+                    .trim_end_matches("kB")
+                    .trim()
+                    .parse::<u64>()
+                    .is_ok_and(|kb| kb > 0);
+            }
+        }
+    }
+    false
+}
+
+/// Transparent huge pages are usable unless the system has them set to "never"
+fn thp_available() -> bool {
+    match fs::read_to_string("/sys/kernel/mm/transparent_hugepage/enabled") {
+        Ok(content) => !content.contains("[never]"),
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thp_pool_is_huge_page_aligned_and_verified() {
+        let pool = BufferPool::new(4, 64 * 1024, MemoryMode::Thp).unwrap();
+        match pool.mode() {
+            MemoryMode::Thp => {
+                assert_eq!(pool.ptr(0) as usize % huge_page_size(), 0);
+                assert!(thp_backed(pool.ptr(0)));
+            }
+            // THP disabled or no huge page available on this machine
+            mode => assert_eq!(mode, MemoryMode::Anon),
+        }
+    }
+
+    #[test]
+    fn small_anon_mapping_is_not_thp() {
+        let pool = BufferPool::new(1, PAGE_SIZE, MemoryMode::Anon).unwrap();
+        assert!(!thp_backed(pool.ptr(0)));
+    }
+}
@@ -1,42 +1,174 @@
+use crate::config::{Config, OpKind, SyncMode};
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
 use std::fs;
 use std::fs::File;
-use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::fs::{FileTypeExt, OpenOptionsExt};
 use std::os::unix::io::{AsRawFd, RawFd};
 use std::path::{Path, PathBuf};
 
 // BLKGETSIZE64 ioctl constant (from linux/fs.h)
 const BLKGETSIZE64: libc::c_ulong = 0x80081272;
+// Range ioctls taking a {start, length} pair of u64 (from linux/fs.h)
+const BLKDISCARD: libc::c_ulong = 0x1277;
+const BLKSECDISCARD: libc::c_ulong = 0x127d;
+const BLKZEROOUT: libc::c_ulong = 0x127f;
+
+/// Where sysfs is mounted
+pub const SYSFS_ROOT: &str = "/sys";
+
+/// Access a target is opened with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpenMode {
+    pub write: bool,
+    pub direct: bool,
+    pub sync: SyncMode,
+}
+
+impl OpenMode {
+    /// Read-only direct I/O, enough to inspect a device
+    pub fn read_only() -> Self {
+        OpenMode {
+            write: false,
+            direct: true,
+            sync: SyncMode::None,
+        }
+    }
+
+    /// Access needed to run a configuration; O_RDONLY when it issues no writes
+    pub fn for_config(config: &Config) -> Result<Self> {
+        Ok(OpenMode {
+            write: config.writes()? || config.trim_before,
+            direct: !config.buffered,
+            sync: config.sync,
+        })
+    }
+
+    fn flags(&self) -> libc::c_int {
+        let mut flags = if self.write {
+            libc::O_RDWR
+        } else {
+            libc::O_RDONLY
+        };
+        if self.direct {
+            flags |= libc::O_DIRECT;
+        }
+        flags |= match self.sync {
+            SyncMode::None => 0,
+            SyncMode::Sync => libc::O_SYNC,
+            SyncMode::Dsync => libc::O_DSYNC,
+        };
+        flags
+    }
+}
+
+impl std::fmt::Display for OpenMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", if self.write { "O_RDWR" } else { "O_RDONLY" })?;
+        if self.direct {
+            write!(f, "|O_DIRECT")?;
+        }
+        if self.sync != SyncMode::None {
+            write!(f, "|{}", self.sync)?;
+        }
+        Ok(())
+    }
+}
 
 /// Abstraction for storage device access
 pub struct Device {
     file: File,
     path: std::path::PathBuf,
     size: u64,
+    block_device: bool,
 }
 
 impl Device {
-    /// Open a storage device with O_DIRECT for direct I/O
-    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+    /// Open a storage device; writes need `mode.write`
+    pub fn open<P: AsRef<Path>>(path: P, mode: OpenMode) -> Result<Self> {
         use std::fs::OpenOptions;
 
         let path_buf = path.as_ref().to_path_buf();
         let file = OpenOptions::new()
             .read(true)
-            .write(true)
-            .custom_flags(libc::O_DIRECT | libc::O_RDWR)
+            .write(mode.write)
+            .custom_flags(mode.flags())
             .open(&path_buf)?;
 
         let metadata = file.metadata()?;
-        let size = metadata.len();
+        let block_device = metadata.file_type().is_block_device();
+        let size = if block_device {
+            let mut size: u64 = 0;
+            let ret = unsafe { libc::ioctl(file.as_raw_fd(), BLKGETSIZE64, &mut size) };
+            if ret != 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            size
+        } else {
+            metadata.len()
+        };
 
         Ok(Device {
             file,
             path: path_buf,
             size,
+            block_device,
         })
     }
 
+    /// Open a directory whose files the workers use, so it can stand in as a target
+    pub fn open_directory<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path_buf = path.as_ref().to_path_buf();
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_DIRECTORY)
+            .open(&path_buf)?;
+        Ok(Device {
+            file,
+            path: path_buf,
+            size: 0,
+            block_device: false,
+        })
+    }
+
+    /// Use only the first `size` bytes
+    pub fn limit_size(&mut self, size: u64) {
+        self.size = self.size.min(size);
+    }
+
+    pub fn is_block_device(&self) -> bool {
+        self.block_device
+    }
+
+    /// Discard, zero or securely erase a range, waiting for it to finish.
+    /// Block devices use the BLK* ioctls, regular files fallocate.
+    pub fn trim(&self, op: OpKind, offset: u64, len: u64) -> std::io::Result<()> {
+        let ret = if self.block_device {
+            let request = match op {
+                OpKind::Discard => BLKDISCARD,
+                OpKind::WriteZeroes => BLKZEROOUT,
+                OpKind::SecureErase => BLKSECDISCARD,
+                OpKind::Read | OpKind::Write => return Err(std::io::ErrorKind::InvalidInput.into()),
+            };
+            let range: [u64; 2] = [offset, len];
+            unsafe { libc::ioctl(self.file.as_raw_fd(), request, &range) }
+        } else {
+            let mode = fallocate_mode(op).ok_or(std::io::ErrorKind::Unsupported)?;
+            unsafe {
+                libc::fallocate(
+                    self.file.as_raw_fd(),
+                    mode,
+                    offset as libc::off_t,
+                    len as libc::off_t,
+                )
+            }
+        };
+        if ret != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
     /// Get the raw file descriptor
     pub fn as_raw_fd(&self) -> RawFd {
         self.file.as_raw_fd()
@@ -52,173 +184,174 @@ impl Device {
         self.size
     }
 
-    /// Get device information
+    /// Get device information. Symlinks, partitions and files on a filesystem
+    /// report the whole disk they live on.
     pub fn info(&self) -> DeviceInfo {
-        let name = self.path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-        let info = Self::get_device_info(name, &self.path);
-        DeviceInfo {
-            path: self.path.clone(),
-            size: self.size,
-            model: info.0,
-            device_type: info.1,
-            link_speed: info.2,
-            link_status: info.3,
-        }
+        self.info_at(Path::new(SYSFS_ROOT))
     }
 
-    // List all available storage devices
-    /// List all available storage devices
-    pub fn list_devices() -> Result<Vec<DeviceInfo>> {
-        let mut devices = Vec::new();
-
-        // List block devices from /sys/block
-        let sys_block = Path::new("/sys/block");
-        if sys_block.exists() {
-            for entry in fs::read_dir(sys_block)? {
-                let entry = entry?;
-                let name = entry.file_name();
-                let name_str = name.to_string_lossy();
-
-                // Skip loop devices, ram devices, etc.
-                if name_str.starts_with("loop")
-                    || name_str.starts_with("ram")
-                    || name_str.starts_with("dm-")
-                {
-                    continue;
-                }
-
-                let device_path = PathBuf::from("/dev").join(name_str.as_ref());
-
-                // Try to get device size and info
-                if let Ok(size) = Self::get_device_size(&device_path) {
-                    if size > 0 {
-                        let info = Self::get_device_info(&name_str, &device_path);
-                        devices.push(DeviceInfo {
-                            path: device_path,
-                            size,
-                            model: info.0,
-                            device_type: info.1,
-                            link_speed: info.2,
-                            link_status: info.3,
-                        });
-                    }
-                }
-            }
-        }
+    /// Device information read from the sysfs tree at `sysfs`
+    pub fn info_at(&self, sysfs: &Path) -> DeviceInfo {
+        let name = Self::disk_name(sysfs, &self.path).unwrap_or_else(|| {
+            self.path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("")
+                .to_string()
+        });
+        DeviceInfo::from_sysfs(sysfs, &name, self.path.clone(), self.size)
+    }
 
-        // Also check for NVMe namespaces (skip controllers and fabrics)
-        let nvme_path = Path::new("/dev");
-        if nvme_path.exists() {
-            for entry in fs::read_dir(nvme_path)? {
-                let entry = entry?;
-                let name = entry.file_name();
-                let name_str = name.to_string_lossy();
-
-                // Only include NVMe namespaces (nvmeXnY format) and partitions (nvmeXnYpZ)
-                // Skip controllers (nvmeX without 'n'), fabrics (nvme-fabrics), and other non-block devices
-                // A namespace has format nvmeXnY where X is controller and Y is namespace
-                if name_str.starts_with("nvme")
-                    && name_str.contains('n')
-                    && !name_str.ends_with("-fabrics")
-                    && name_str.len() > 5
-                {
-                    // At least "nvme0n1"
-                    let device_path = PathBuf::from("/dev").join(name_str.as_ref());
-                    if let Ok(size) = Self::get_device_size(&device_path) {
-                        // Only include devices with valid size > 0, and avoid duplicates
-                        if size > 0 && !devices.iter().any(|d| d.path == device_path) {
-                            let info = Self::get_device_info(&name_str, &device_path);
-                            devices.push(DeviceInfo {
-                                path: device_path,
-                                size,
-                                model: info.0,
-                                device_type: info.1,
-                                link_speed: info.2,
-                                link_status: info.3,
-                            });
-                        }
-                    }
-                }
-            }
-        }
+    /// Kernel name of the whole disk behind a path, via /sys/dev/block/MAJ:MIN
+    pub fn disk_name(sysfs: &Path, path: &Path) -> Option<String> {
+        use std::os::unix::fs::MetadataExt;
+
+        let metadata = fs::metadata(path).ok()?;
+        let dev = if metadata.file_type().is_block_device() {
+            metadata.rdev()
+        } else {
+            metadata.dev()
+        };
+        let sys = fs::canonicalize(sysfs.join(format!(
+            "dev/block/{}:{}",
+            libc::major(dev),
+            libc::minor(dev)
+        )))
+        .ok()?;
+        // Partitions sit below their disk
+        let disk = if sys.join("partition").exists() {
+            sys.parent()?.to_path_buf()
+        } else {
+            sys
+        };
+        disk.file_name()?.to_str().map(str::to_string)
+    }
+}
 
-        // Filter out devices with zero size from the /sys/block list as well
-        devices.retain(|d| d.size > 0);
+/// Block layer settings of a device, from /sys/block/<dev>/queue and friends
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QueueTopology {
+    pub logical_block_size: Option<u64>,
+    pub physical_block_size: Option<u64>,
+    pub minimum_io_size: Option<u64>,
+    pub optimal_io_size: Option<u64>,
+    pub max_sectors_kb: Option<u64>,
+    pub max_hw_sectors_kb: Option<u64>,
+    pub nr_requests: Option<u64>,
+    /// Active I/O scheduler (the bracketed entry of queue/scheduler)
+    pub scheduler: Option<String>,
+    pub rotational: Option<bool>,
+    /// "write back" or "write through"
+    pub write_cache: Option<String>,
+    pub discard_granularity: Option<u64>,
+    /// none, host-aware or host-managed
+    pub zoned: Option<String>,
+    /// blk-mq hardware queues (entries of /sys/block/<dev>/mq)
+    pub hw_queues: Option<usize>,
+    pub numa_node: Option<usize>,
+}
 
-        devices.sort_by(|a, b| a.path.cmp(&b.path));
-        Ok(devices)
+impl QueueTopology {
+    /// Read the settings below a block device's sysfs directory
+    pub fn from_sysfs(dev_dir: &Path) -> Self {
+        let queue = dev_dir.join("queue");
+        let number = |name: &str| read_string(&queue.join(name)).and_then(|v| v.parse().ok());
+        let scheduler = read_string(&queue.join("scheduler")).map(|s| {
+            // "mq-deadline kyber [none]" -> "none"
+            s.split_once('[')
+                .and_then(|(_, rest)| rest.split_once(']'))
+                .map(|(active, _)| active.to_string())
+                .unwrap_or(s)
+        });
+        let hw_queues = fs::read_dir(dev_dir.join("mq"))
+            .ok()
+            .map(|entries| entries.flatten().count());
+        // NVMe namespaces: device/ is the controller, device/device/ the PCI function
+        let numa_node = ["device/numa_node", "device/device/numa_node"]
+            .iter()
+            .find_map(|p| read_string(&dev_dir.join(p)))
+            .and_then(|n| n.parse::<i64>().ok())
+            .and_then(|n| usize::try_from(n).ok());
+
+        QueueTopology {
+            logical_block_size: number("logical_block_size"),
+            physical_block_size: number("physical_block_size"),
+            minimum_io_size: number("minimum_io_size"),
+            optimal_io_size: number("optimal_io_size"),
+            max_sectors_kb: number("max_sectors_kb"),
+            max_hw_sectors_kb: number("max_hw_sectors_kb"),
+            nr_requests: number("nr_requests"),
+            scheduler,
+            rotational: read_string(&queue.join("rotational")).map(|r| r == "1"),
+            write_cache: read_string(&queue.join("write_cache")),
+            discard_granularity: number("discard_granularity"),
+            zoned: read_string(&queue.join("zoned")),
+            hw_queues,
+            numa_node,
+        }
     }
+}
 
-    fn get_device_size<P: AsRef<Path>>(path: P) -> Result<u64> {
-        let path_ref = path.as_ref();
-        let name = path_ref
-            .file_name()
-            .and_then(|n| n.to_str())
-            .ok_or_else(|| anyhow::anyhow!("Invalid device path"))?;
-
-        // Try reading from /sys/class/block first (works for all block devices including partitions)
-        let class_size_path = Path::new("/sys/class/block").join(name).join("size");
-        if class_size_path.exists() {
-            if let Ok(content) = fs::read_to_string(&class_size_path) {
-                if let Ok(sectors) = content.trim().parse::<u64>() {
-                    if sectors > 0 {
-                        return Ok(sectors * 512); // Convert sectors to bytes
-                    }
-                }
+impl std::fmt::Display for QueueTopology {
+    /// The settings that are known, as "key=value" pairs
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut fields: Vec<String> = Vec::new();
+        let numbers = [
+            ("lbs", self.logical_block_size),
+            ("pbs", self.physical_block_size),
+            ("io_min", self.minimum_io_size),
+            ("io_opt", self.optimal_io_size),
+            ("max_sectors_kb", self.max_sectors_kb),
+            ("max_hw_sectors_kb", self.max_hw_sectors_kb),
+            ("nr_requests", self.nr_requests),
+            ("discard_granularity", self.discard_granularity),
+        ];
+        for (name, value) in numbers {
+            if let Some(value) = value {
+                fields.push(format!("{}={}", name, value));
             }
         }
-
-        // Try /sys/block (for whole devices, not partitions)
-        let size_path = Path::new("/sys/block").join(name).join("size");
-        if size_path.exists() {
-            if let Ok(content) = fs::read_to_string(&size_path) {
-                if let Ok(sectors) = content.trim().parse::<u64>() {
-                    if sectors > 0 {
-                        return Ok(sectors * 512);
-                    }
-                }
-            }
+        if let Some(scheduler) = &self.scheduler {
+            fields.push(format!("scheduler={}", scheduler));
         }
-
-        // Fallback: Try to open the device (requires root for block devices)
-        // Use BLKGETSIZE64 ioctl for accurate size
-        match fs::File::open(path_ref) {
-            Ok(file) => {
-                // Try using ioctl to get block device size
-                unsafe {
-                    use std::os::unix::io::AsRawFd;
-                    let fd = file.as_raw_fd();
-                    let mut size: u64 = 0;
-                    let result = libc::ioctl(fd, BLKGETSIZE64, &mut size);
-                    if result == 0 && size > 0 {
-                        return Ok(size);
-                    }
-                }
-                // Fallback to metadata (may not work for block devices)
-                if let Ok(metadata) = file.metadata() {
-                    let len = metadata.len();
-                    if len > 0 {
-                        return Ok(len);
-                    }
-                }
-            }
-            Err(_) => {}
+        if let Some(rotational) = self.rotational {
+            fields.push(format!("rotational={}", if rotational { "yes" } else { "no" }));
         }
-
-        Ok(0)
+        if let Some(cache) = &self.write_cache {
+            fields.push(format!("write_cache={}", cache.replace(' ', "-")));
+        }
+        if let Some(zoned) = &self.zoned {
+            fields.push(format!("zoned={}", zoned));
+        }
+        if let Some(queues) = self.hw_queues {
+            fields.push(format!("hw_queues={}", queues));
+        }
+        if let Some(node) = self.numa_node {
+            fields.push(format!("numa_node={}", node));
+        }
+        write!(f, "{}", fields.join(" "))
     }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceInfo {
+    pub path: std::path::PathBuf,
+    pub size: u64,
+    pub model: Option<String>,
+    pub firmware: Option<String>,
+    pub device_type: Option<String>,
+    pub link_speed: Option<String>,
+    pub link_status: Option<String>,
+    #[serde(default)]
+    pub queue: QueueTopology,
+}
 
-    /// Get device information (model, type, link speed, link status)
-    fn get_device_info(
-        device_name: &str,
-        device_path: &Path,
-    ) -> (
-        Option<String>,
-        Option<String>,
-        Option<String>,
-        Option<String>,
-    ) {
+impl DeviceInfo {
+    /// Read a block device's identity and queue settings from a sysfs tree
+    /// (normally /sys; tests can point it at a fixture tree instead)
+    pub fn from_sysfs(sysfs: &Path, device_name: &str, path: PathBuf, size: u64) -> Self {
         let mut model = None;
+        let mut firmware = None;
         let mut device_type = None;
         let mut link_speed = None;
         let mut link_status = None;
@@ -233,15 +366,15 @@ impl Device {
 
                 // Model from /sys/class/block/nvme0n1/device/model or /sys/block/nvme0n1/device/model
                 let model_paths = [
-                    Path::new("/sys/class/block")
+                    sysfs.join("class/block")
                         .join(device_name)
                         .join("device")
                         .join("model"),
-                    Path::new("/sys/block")
+                    sysfs.join("block")
                         .join(device_name)
                         .join("device")
                         .join("model"),
-                    Path::new("/sys/class/block")
+                    sysfs.join("class/block")
                         .join(device_name)
                         .join("device")
                         .join("..")
@@ -257,6 +390,15 @@ impl Device {
                     }
                 }
 
+                // Firmware revision lives next to the model in the controller's directory
+                for model_path in &model_paths {
+                    let firmware_path = model_path.with_file_name("firmware_rev");
+                    if let Ok(content) = fs::read_to_string(&firmware_path) {
+                        firmware = Some(content.trim().to_string());
+                        break;
+                    }
+                }
+
                 // Link speed from /sys/class/nvme/nvme0/subsysnqn or /sys/block/nvme0n1/queue/optimal_io_size
                 // Actually, link speed is in /sys/class/nvme/nvme0/device/subsystem/nvme-subsys0/subsysnqn
                 // Better: /sys/class/nvme/nvme0/device/subsystem/nvme-subsys0/device/nvme0/firmware_rev
@@ -264,7 +406,7 @@ impl Device {
                 // Try: /sys/block/nvme0n1/queue/optimal_io_size or check PCIe info
 
                 // For NVMe, try to get PCIe link info
-                let pci_path = Path::new("/sys/class/block")
+                let pci_path = sysfs.join("class/block")
                     .join(device_name)
                     .join("device")
                     .join("..")
@@ -295,7 +437,7 @@ impl Device {
             device_type = Some("SATA/SAS".to_string());
 
             // Try to get model from /sys/block/sda/device/model
-            let model_path = Path::new("/sys/block")
+            let model_path = sysfs.join("block")
                 .join(device_name)
                 .join("device")
                 .join("model");
@@ -304,20 +446,64 @@ impl Device {
                     model = Some(content.trim().to_string());
                 }
             }
+
+            // SCSI devices report their firmware revision as "rev"
+            let rev_path = model_path.with_file_name("rev");
+            if let Ok(content) = fs::read_to_string(&rev_path) {
+                firmware = Some(content.trim().to_string());
+            }
         }
 
-        (model, device_type, link_speed, link_status)
+        // Controller state ("live" for NVMe, "running" for SCSI), flagging PCIe
+        // links that trained below what the device supports
+        let device_dir = sysfs.join("class/block").join(device_name).join("device");
+        if let Some(state) = read_string(&device_dir.join("state")) {
+            link_status = Some(state);
+        }
+        let pci_dir = device_dir.join("device");
+        let current = read_string(&pci_dir.join("current_link_speed"))
+            .zip(read_string(&pci_dir.join("current_link_width")));
+        let max = read_string(&pci_dir.join("max_link_speed"))
+            .zip(read_string(&pci_dir.join("max_link_width")));
+        if let (Some(current), Some(max)) = (current, max) {
+            if current != max {
+                let degraded = format!(
+                    "degraded: {} x{} of {} x{}",
+                    current.0, current.1, max.0, max.1
+                );
+                link_status = Some(match link_status {
+                    Some(state) => format!("{} ({})", state, degraded),
+                    None => degraded,
+                });
+            }
+        }
+
+        DeviceInfo {
+            path,
+            size,
+            model,
+            firmware,
+            device_type,
+            link_speed,
+            link_status,
+            queue: QueueTopology::from_sysfs(&sysfs.join("class/block").join(device_name)),
+        }
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct DeviceInfo {
-    pub path: std::path::PathBuf,
-    pub size: u64,
-    pub model: Option<String>,
-    pub device_type: Option<String>,
-    pub link_speed: Option<String>,
-    pub link_status: Option<String>,
+
+/// fallocate mode that trims a range of a regular file; files have no secure erase
+pub fn fallocate_mode(op: OpKind) -> Option<libc::c_int> {
+    match op {
+        OpKind::Discard => Some(libc::FALLOC_FL_PUNCH_HOLE | libc::FALLOC_FL_KEEP_SIZE),
+        OpKind::WriteZeroes => Some(libc::FALLOC_FL_ZERO_RANGE | libc::FALLOC_FL_KEEP_SIZE),
+        OpKind::Read | OpKind::Write | OpKind::SecureErase => None,
+    }
+}
+
+/// Trimmed contents of a sysfs attribute
+pub fn read_string(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
 }
 
 impl AsRawFd for Device {
@@ -325,3 +511,136 @@ impl AsRawFd for Device {
         self.file.as_raw_fd()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::{symlink, MetadataExt};
+
+    const NAMESPACE: &str = "devices/pci0000:00/0000:00:1d.0/nvme/nvme0/nvme0n1";
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    /// A sysfs tree with one NVMe namespace and its first partition, where the
+    /// device number of `target` resolves to `node` (relative to the tree)
+    fn sysfs_fixture(test: &str, node: &str) -> (PathBuf, Device) {
+        let root = std::env::temp_dir().join(format!(
+            "storage-bench-sysfs-{}-{}",
+            test,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&root);
+        let namespace = root.join(NAMESPACE);
+        for (name, value) in [
+            ("logical_block_size", "512"),
+            ("physical_block_size", "4096"),
+            ("minimum_io_size", "4096"),
+            ("optimal_io_size", "131072"),
+            ("max_sectors_kb", "1280"),
+            ("max_hw_sectors_kb", "2048"),
+            ("nr_requests", "1023"),
+            ("scheduler", "[mq-deadline] none\n"),
+            ("rotational", "0\n"),
+            ("write_cache", "write back\n"),
+            ("discard_granularity", "512"),
+            ("zoned", "none\n"),
+        ] {
+            write(&namespace.join("queue").join(name), value);
+        }
+        for queue in ["0", "1", "2", "3"] {
+            fs::create_dir_all(namespace.join("mq").join(queue)).unwrap();
+        }
+        write(&namespace.join("nvme0n1p1/partition"), "1\n");
+
+        // The namespace's device/ is its controller, and the controller's device/
+        // is the PCI function, which alone knows the NUMA node
+        let controller = root.join("devices/pci0000:00/0000:00:1d.0/nvme/nvme0");
+        write(&controller.join("model"), "Fixture NVMe SSD    \n");
+        write(&controller.join("firmware_rev"), "1.2.3\n");
+        write(&controller.join("state"), "live\n");
+        write(&root.join("devices/pci0000:00/0000:00:1d.0/numa_node"), "1\n");
+        symlink("../../../0000:00:1d.0", controller.join("device")).unwrap();
+        symlink("..", namespace.join("device")).unwrap();
+
+        fs::create_dir_all(root.join("class/block")).unwrap();
+        symlink(
+            Path::new("../..").join(NAMESPACE),
+            root.join("class/block/nvme0n1"),
+        )
+        .unwrap();
+
+        // The target is a regular file, whose device number is its filesystem's
+        let target = root.join("target");
+        write(&target, "data");
+        let dev = fs::metadata(&target).unwrap().dev();
+        fs::create_dir_all(root.join("dev/block")).unwrap();
+        symlink(
+            Path::new("../..").join(node),
+            root.join("dev/block").join(format!(
+                "{}:{}",
+                libc::major(dev),
+                libc::minor(dev)
+            )),
+        )
+        .unwrap();
+
+        let mode = OpenMode {
+            write: false,
+            direct: false,
+            sync: SyncMode::None,
+        };
+        let device = Device::open(&target, mode).unwrap();
+        (root, device)
+    }
+
+    #[test]
+    fn reads_nvme_namespace_from_sysfs() {
+        let (root, device) = sysfs_fixture("namespace", NAMESPACE);
+        let info = device.info_at(&root);
+
+        assert_eq!(info.device_type.as_deref(), Some("NVMe"));
+        assert_eq!(info.model.as_deref(), Some("Fixture NVMe SSD"));
+        assert_eq!(info.firmware.as_deref(), Some("1.2.3"));
+        assert_eq!(info.link_status.as_deref(), Some("live"));
+        assert_eq!(
+            info.queue,
+            QueueTopology {
+                logical_block_size: Some(512),
+                physical_block_size: Some(4096),
+                minimum_io_size: Some(4096),
+                optimal_io_size: Some(131072),
+                max_sectors_kb: Some(1280),
+                max_hw_sectors_kb: Some(2048),
+                nr_requests: Some(1023),
+                scheduler: Some("mq-deadline".to_string()),
+                rotational: Some(false),
+                write_cache: Some("write back".to_string()),
+                discard_granularity: Some(512),
+                zoned: Some("none".to_string()),
+                hw_queues: Some(4),
+                numa_node: Some(1),
+            }
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn partition_resolves_to_its_disk() {
+        let partition = format!("{}/nvme0n1p1", NAMESPACE);
+        let (root, device) = sysfs_fixture("partition", &partition);
+        assert_eq!(
+            Device::disk_name(&root, &root.join("target")).as_deref(),
+            Some("nvme0n1")
+        );
+
+        let info = device.info_at(&root);
+        assert_eq!(info.model.as_deref(), Some("Fixture NVMe SSD"));
+        assert_eq!(info.queue.scheduler.as_deref(), Some("mq-deadline"));
+        assert_eq!(info.queue.hw_queues, Some(4));
+        assert_eq!(info.queue.numa_node, Some(1));
+        fs::remove_dir_all(root).unwrap();
+    }
+}
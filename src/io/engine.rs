@@ -1,22 +1,66 @@
-use crate::config::{Config, IoMode, Workload};
-use crate::io::{Device, IoWorker};
-use anyhow::Result;
+use crate::config::{Config, IoMode, MemoryMode, NumaPolicy, OpKind, Workload};
+use crate::io::histogram::LatencySnapshot;
+use crate::io::worker::WorkerStats;
+use crate::io::device::{DeviceInfo, OpenMode, SYSFS_ROOT};
+use crate::io::queue::QueueChange;
+use crate::io::{file, safety, Device, IoWorker};
+use crate::monitor::cpu::process_cpu_time;
+use crate::monitor::health::{DeviceHealth, HealthMonitor, HealthSnapshot};
+use crate::monitor::io_stats::{DiskUtil, IoStats};
+use crate::monitor::numa::{self, NumaReport, WorkerPlacement};
+use crate::monitor::{IoStatsMonitor, NumaMonitor};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Barrier};
 use std::thread;
 use std::time::Duration;
 
+// A target is flagged as an outlier when its IOPS is this far from the median
+const OUTLIER_TOLERANCE: f64 = 0.10;
+
 /// Main I/O engine that coordinates workers
 pub struct IoEngine {
-    device: Arc<Device>,
+    devices: Vec<Arc<Device>>,
+    /// Identity and queue settings of each target, recorded with the results
+    infos: Vec<DeviceInfo>,
     config: Config,
+    open_mode: OpenMode,
+}
+
+/// Results for a single target in a multi-target run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TargetResults {
+    pub path: PathBuf,
+    pub total_bytes_read: u64,
+    pub total_bytes_written: u64,
+    pub total_ops: u64,
+    pub failed_ops: u64,
+    pub throughput_read_mbps: f64,
+    pub throughput_write_mbps: f64,
+    pub iops: f64,
+    pub avg_latency_us: f64,
+    pub min_latency_us: f64,
+    pub max_latency_us: f64,
+    /// IOPS deviates from the median target by more than OUTLIER_TOLERANCE
+    pub outlier: bool,
+    /// The device behind the target and its queue settings during the run
+    #[serde(default)]
+    pub device: Option<DeviceInfo>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct BenchmarkResults {
+    /// Job name (the workload name for single-job runs)
+    pub name: String,
+    /// Configuration this job ran with
+    pub config: Config,
     pub total_bytes_read: u64,
     pub total_bytes_written: u64,
     pub total_ops: u64,
+    pub read_ops: u64,
+    pub write_ops: u64,
     pub failed_ops: u64,
     pub duration: Duration,
     pub throughput_read_mbps: f64,
@@ -25,40 +69,327 @@ pub struct BenchmarkResults {
     pub avg_latency_us: f64,
     pub min_latency_us: f64,
     pub max_latency_us: f64,
+    pub p50_latency_us: f64,
+    pub p90_latency_us: f64,
+    pub p99_latency_us: f64,
+    pub p999_latency_us: f64,
+    /// Sampled completion latency distributions
+    pub read_latency: LatencySnapshot,
+    pub write_latency: LatencySnapshot,
+    pub numa: Option<NumaReport>,
+    /// Buffer memory mode that was requested
+    pub memory_mode_requested: MemoryMode,
+    /// Buffer memory mode each worker actually got
+    pub memory_modes: Vec<MemoryMode>,
+    /// Per-target breakdown (one entry per device)
+    pub targets: Vec<TargetResults>,
+    /// Per-job breakdown for runs with several concurrent jobs
+    pub jobs: Vec<BenchmarkResults>,
+    /// Activity of the disks behind the targets while the run was measured
+    pub disk_util: Vec<DiskUtil>,
+    /// CPU cores the process kept busy on average while the run was measured
+    #[serde(default)]
+    pub cpu_cores: f64,
+    /// Flags the targets were opened with (e.g., O_RDONLY|O_DIRECT)
+    #[serde(default)]
+    pub open_flags: String,
+    /// Block queue attributes set for the run, with their original and restored values
+    #[serde(default)]
+    pub queue_changes: Vec<QueueChange>,
+    /// Health of the disks behind the targets before and after the run
+    #[serde(default)]
+    pub health: Vec<DeviceHealth>,
+    /// Discards, write-zeroes and secure erases; also counted in total_ops
+    #[serde(default)]
+    pub trim_ops: u64,
+    #[serde(default)]
+    pub total_bytes_trimmed: u64,
+    #[serde(default)]
+    pub throughput_trim_mbps: f64,
+    #[serde(default)]
+    pub trim_latency: LatencySnapshot,
+    /// fsyncs and fdatasyncs issued between writes; not counted in total_ops
+    #[serde(default)]
+    pub flush_ops: u64,
+    #[serde(default)]
+    pub flush_latency: LatencySnapshot,
+    /// wal workload: oldest record of each group to its fdatasync completing;
+    /// the count is the number of commits
+    #[serde(default)]
+    pub commit_latency: LatencySnapshot,
+}
+
+/// Which job and target a worker belongs to
+#[derive(Debug, Clone, Copy)]
+struct WorkerSlot {
+    job: usize,
+    target: usize,
+}
+
+/// What a worker reports back once it finishes
+struct WorkerOutcome {
+    cpus: Vec<usize>,
+    node: Option<usize>,
+    device_node: Option<usize>,
+    memory_mode: MemoryMode,
+}
+
+/// Raw counters summed over a group of workers
+#[derive(Default)]
+struct StatsTotals {
+    bytes_read: u64,
+    bytes_written: u64,
+    ops: u64,
+    read_ops: u64,
+    trim_ops: u64,
+    bytes_trimmed: u64,
+    failed_ops: u64,
+    latency_ns: u64,
+    min_latency_ns: u64,
+    max_latency_ns: u64,
+    read_latency: LatencySnapshot,
+    write_latency: LatencySnapshot,
+    trim_latency: LatencySnapshot,
+    flush_ops: u64,
+    flush_latency: LatencySnapshot,
+    commit_latency: LatencySnapshot,
+}
+
+impl StatsTotals {
+    fn sum<'a>(stats: impl IntoIterator<Item = &'a Arc<WorkerStats>>) -> Self {
+        let mut totals = StatsTotals {
+            min_latency_ns: u64::MAX,
+            ..Default::default()
+        };
+        for stats in stats {
+            totals.bytes_read += stats.bytes_read.load(Ordering::Relaxed);
+            totals.bytes_written += stats.bytes_written.load(Ordering::Relaxed);
+            totals.ops += stats.ops_completed.load(Ordering::Relaxed);
+            totals.read_ops += stats.read_ops_completed.load(Ordering::Relaxed);
+            totals.trim_ops += stats.trim_ops_completed.load(Ordering::Relaxed);
+            totals.bytes_trimmed += stats.bytes_trimmed.load(Ordering::Relaxed);
+            totals.failed_ops += stats.ops_failed.load(Ordering::Relaxed);
+            totals.latency_ns += stats.total_latency_ns.load(Ordering::Relaxed);
+            totals.min_latency_ns = totals
+                .min_latency_ns
+                .min(stats.min_latency_ns.load(Ordering::Relaxed));
+            totals.max_latency_ns = totals
+                .max_latency_ns
+                .max(stats.max_latency_ns.load(Ordering::Relaxed));
+            totals.read_latency.merge(&stats.read_latency.snapshot());
+            totals.write_latency.merge(&stats.write_latency.snapshot());
+            totals.trim_latency.merge(&stats.trim_latency.snapshot());
+            totals.flush_ops += stats.flush_ops_completed.load(Ordering::Relaxed);
+            totals.flush_latency.merge(&stats.flush_latency.snapshot());
+            totals.commit_latency.merge(&stats.commit_latency.snapshot());
+        }
+        totals
+    }
+
+    fn throughput_mbps(bytes: u64, duration: Duration) -> f64 {
+        (bytes as f64 / duration.as_secs_f64()) / (1024.0 * 1024.0)
+    }
+
+    /// Latency is sampled, so average over the sampled operations only
+    fn avg_latency_us(&self) -> f64 {
+        let samples =
+            self.read_latency.count + self.write_latency.count + self.trim_latency.count;
+        self.latency_ns
+            .checked_div(samples)
+            .map_or(0.0, |ns| ns as f64 / 1000.0)
+    }
+
+    fn min_latency_us(&self) -> f64 {
+        if self.min_latency_ns == u64::MAX {
+            0.0
+        } else {
+            self.min_latency_ns as f64 / 1000.0
+        }
+    }
+
+    fn combined_latency(&self) -> LatencySnapshot {
+        let mut combined = self.read_latency.clone();
+        combined.merge(&self.write_latency);
+        combined.merge(&self.trim_latency);
+        combined
+    }
+}
+
+impl TargetResults {
+    /// Flag targets whose IOPS strays too far from the median target
+    fn mark_outliers(targets: &mut [TargetResults]) {
+        if targets.len() < 3 {
+            return;
+        }
+        let mut iops: Vec<f64> = targets.iter().map(|t| t.iops).collect();
+        iops.sort_by(|a, b| a.total_cmp(b));
+        let median = iops[iops.len() / 2];
+        if median <= 0.0 {
+            return;
+        }
+        for target in targets.iter_mut() {
+            target.outlier = ((target.iops - median) / median).abs() > OUTLIER_TOLERANCE;
+        }
+    }
 }
 
 impl IoEngine {
     pub fn new(config: Config) -> Result<Self> {
-        let device = Arc::new(Device::open(&config.device)?);
-        Ok(Self { device, config })
+        if config.devices.is_empty() {
+            return Err(anyhow::anyhow!("No target devices given"));
+        }
+        let open_mode = OpenMode::for_config(&config)?;
+        if open_mode.write && !config.allow_destructive {
+            for path in &config.devices {
+                safety::check_writable(path)?;
+            }
+        }
+        let layout = config.file_layout.resolve(max_read_percent(&config)?);
+        let devices = config
+            .devices
+            .iter()
+            .map(|path| {
+                if config.filename_format.is_some() {
+                    if !file::is_directory(path) {
+                        return Err(anyhow::anyhow!(
+                            "--filename-format needs directory targets: {}",
+                            path.display()
+                        ));
+                    }
+                    return Device::open_directory(path).map(Arc::new);
+                }
+                if file::is_directory(path) {
+                    return Err(anyhow::anyhow!(
+                        "{} is a directory; use --filename-format for one file per worker",
+                        path.display()
+                    ));
+                }
+                file::prepare(path, config.size, layout)?;
+                let mut device = Device::open(path, open_mode)?;
+                if let Some(size) = config.size {
+                    device.limit_size(size);
+                }
+                Ok(Arc::new(device))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let infos = devices.iter().map(|d| d.info()).collect();
+        Ok(Self {
+            devices,
+            infos,
+            config,
+            open_mode,
+        })
     }
 
     /// Run benchmark
     pub fn run(&self) -> Result<BenchmarkResults> {
+        let health: Vec<(HealthMonitor, HealthSnapshot)> = self
+            .health_monitors()
+            .into_iter()
+            .map(|monitor| {
+                let snapshot = monitor.snapshot();
+                (monitor, snapshot)
+            })
+            .collect();
+
         // Handle "all" workload by running all workloads sequentially
-        if self.config.workload == Workload::All {
-            return self.run_all_workloads();
-        }
+        let mut results = if self.config.workload == Workload::All && self.config.jobs.is_empty() {
+            // Once for the whole sequence, so later workloads see what earlier ones wrote
+            if self.config.trim_before {
+                Self::trim_targets(&self.devices)?;
+            }
+            self.run_all_workloads()?
+        } else {
+            let jobs = self.config.job_configs()?;
+            self.run_jobs(&jobs)?
+        };
+        results.health = health
+            .into_iter()
+            .map(|(monitor, before)| monitor.report(before))
+            .collect();
+        Ok(results)
+    }
 
+    /// Run every job concurrently against all targets
+    fn run_jobs(&self, jobs: &[(String, Config)]) -> Result<BenchmarkResults> {
         let stop_flag = Arc::new(AtomicBool::new(false));
 
         // Create shared stats collection
         let workers_stats = Arc::new(std::sync::Mutex::new(Vec::new()));
         let mut workers_final = Vec::new();
 
+        // Each job gets `threads` workers on every target
+        let mut slots = Vec::new();
+        for (job, (_, config)) in jobs.iter().enumerate() {
+            for target in 0..self.devices.len() {
+                slots.extend((0..config.threads).map(|_| WorkerSlot { job, target }));
+            }
+        }
+        let total_workers = slots.len();
+        if total_workers == 0 {
+            return Err(anyhow::anyhow!("No workers to run (thread count is 0)"));
+        }
+
+        let slot_devices = self.slot_devices(jobs, &slots)?;
+        if self.config.trim_before {
+            Self::trim_targets(&slot_devices)?;
+        }
+
         // Pre-create stats for all workers
-        let read_percent = self.config.workload.read_percent();
-        for _ in 0..self.config.threads {
-            let stats = Arc::new(crate::io::worker::WorkerStats::new());
+        for _ in 0..total_workers {
+            let stats = Arc::new(WorkerStats::new());
             workers_stats.lock().unwrap().push(Arc::clone(&stats));
             workers_final.push(stats);
         }
 
+        // Resolve NUMA placement before any worker allocates its buffers
+        let numa_monitor = NumaMonitor::default();
+        let target_nodes: Vec<Option<usize>> = self
+            .devices
+            .iter()
+            .map(|d| numa::device_numa_node(d.path()))
+            .collect();
+        let mut placements = match self.config.numa {
+            Some(NumaPolicy::Interleave) => {
+                numa_monitor.plan_placement(NumaPolicy::Interleave, None, total_workers)?
+            }
+            Some(policy) => {
+                // Slots are grouped by job, then target, so plan each group in order
+                let mut placements = Vec::with_capacity(total_workers);
+                for (_, config) in jobs {
+                    for node in &target_nodes {
+                        placements.extend(numa_monitor.plan_placement(
+                            policy,
+                            *node,
+                            config.threads,
+                        )?);
+                    }
+                }
+                placements
+            }
+            None => vec![WorkerPlacement::default(); total_workers],
+        };
+
+        // An explicit CPU list overrides the NUMA node's CPU set but keeps its memory policy
+        if let Some(cpus) = &self.config.cpus {
+            let mapping =
+                numa_monitor.plan_cpu_affinity(cpus, self.config.cpu_mapping, total_workers)?;
+            for (placement, cpus) in placements.iter_mut().zip(mapping) {
+                if !placement.cpus.is_empty() && !cpus.iter().all(|c| placement.cpus.contains(c)) {
+                    eprintln!(
+                        "Warning: CPUs {:?} are outside the NUMA node selected by --numa",
+                        cpus
+                    );
+                }
+                placement.cpus = cpus;
+            }
+        }
+
         // Start monitoring thread if enabled
         let monitor_handle = if self.config.monitor {
             let stats_for_monitor = Arc::clone(&workers_stats);
             let stop_monitor = Arc::clone(&stop_flag);
-            let duration = self.config.duration;
+            let duration = self.config.ramp_time + self.config.duration;
 
             Some(thread::spawn(move || {
                 use std::io::{self, Write};
@@ -128,102 +459,377 @@ impl IoEngine {
             None
         };
 
-        // Spawn worker threads - each worker will use its pre-allocated stats
+        // Spawn worker threads - each worker will use its pre-allocated stats.
+        // All workers start issuing I/O together once every ring is set up; this
+        // thread waits too, so the ramp-up is timed from the same moment.
+        let start_barrier = Arc::new(Barrier::new(total_workers + 1));
         let mut worker_handles = Vec::new();
-        for i in 0..self.config.threads {
-            let device_clone = Arc::clone(&self.device);
-            let workload_mode: IoMode = self.config.workload.into();
-            let block_size = self.config.block_size;
-            let queue_depth = self.config.queue_depth;
-            let read_percent = self.config.workload.read_percent();
+        for (i, slot) in slots.iter().enumerate() {
+            let config = &jobs[slot.job].1;
+            let device_clone = Arc::clone(&slot_devices[i]);
+            let workload_mode: IoMode = config.workload.into();
+            let block_size = config.block_size;
+            let queue_depth = config.queue_depth;
+            let read_percent = config.read_percent();
+            let rate = config.rate;
+            let block_size_split = config.block_size_split();
+            let distribution = config.distribution;
+            let op_mix = config.op_mix();
+            let flush = config.flush;
+            let link_flush = config.link_flush;
+            let wal = (config.workload == Workload::Wal).then_some(config.wal);
+            let ramp_time = self.config.ramp_time;
             let duration = self.config.duration;
+            let memory_mode = config.memory_mode;
             let worker_stats = Arc::clone(&workers_final[i]);
+            let placement = placements[i].clone();
+            let start_barrier = Arc::clone(&start_barrier);
+            let stop_flag = Arc::clone(&stop_flag);
 
-            let handle = thread::spawn(move || {
-                let mut worker = IoWorker::new_with_read_percent(
+            let handle = thread::spawn(move || -> Result<(Option<usize>, MemoryMode)> {
+                if let Err(e) = placement.apply() {
+                    eprintln!("Warning: worker {}: {}", i, e);
+                }
+                let worker = IoWorker::new_with_memory_mode(
                     device_clone,
                     workload_mode,
                     block_size,
                     queue_depth,
                     read_percent,
-                );
+                    memory_mode,
+                )
+                .and_then(|mut worker| {
+                    if let Some(split) = &block_size_split {
+                        worker.set_block_size_split(split)?;
+                    }
+                    if let Some(mix) = &op_mix {
+                        worker.set_op_mix(mix)?;
+                    }
+                    Ok(worker)
+                });
+                let mut worker = match worker {
+                    Ok(worker) => worker,
+                    Err(e) => {
+                        // Release the other workers, then stop them early
+                        start_barrier.wait();
+                        stop_flag.store(true, Ordering::Relaxed);
+                        return Err(e);
+                    }
+                };
+                worker.set_start_barrier(start_barrier);
+                worker.set_stop_flag(Arc::clone(&stop_flag));
+                worker.set_rate_limit(rate);
+                worker.set_distribution(distribution);
+                worker.set_ramp_time(ramp_time);
+                if wal.is_some() {
+                    worker.set_wal(wal);
+                } else {
+                    worker.set_flush(flush, link_flush);
+                }
                 // Replace worker's internal stats with shared stats
                 worker.set_stats(worker_stats);
-                worker.run(duration).unwrap();
+                if let Err(e) = worker.run(duration) {
+                    stop_flag.store(true, Ordering::Relaxed);
+                    return Err(e);
+                }
+                Ok((numa::current_cpu(), worker.memory_mode()))
             });
 
             worker_handles.push(handle);
         }
 
-        // Wait for all workers to complete
-        for handle in worker_handles {
-            handle.join().unwrap();
+        // Disk counters cover the measured period only, so skip the ramp-up
+        start_barrier.wait();
+        thread::sleep(self.config.ramp_time);
+        let disks = self.disk_monitors();
+        let disk_start = std::time::Instant::now();
+        let disk_before: Vec<Option<IoStats>> =
+            disks.iter().map(|(_, m)| m.collect().ok()).collect();
+        let cpu_before = process_cpu_time();
+
+        // Wait for all workers to complete, noting where each one ended up
+        let mut outcomes = Vec::with_capacity(total_workers);
+        let mut failure = None;
+        for (i, ((handle, slot), placement)) in
+            worker_handles.into_iter().zip(&slots).zip(placements).enumerate()
+        {
+            let (cpu, memory_mode) = match handle.join() {
+                Ok(Ok(outcome)) => outcome,
+                Ok(Err(e)) => {
+                    failure.get_or_insert(e.context(format!("worker {}", i)));
+                    continue;
+                }
+                Err(_) => {
+                    failure.get_or_insert(anyhow::anyhow!("worker {} panicked", i));
+                    continue;
+                }
+            };
+            outcomes.push(WorkerOutcome {
+                cpus: placement.cpus,
+                node: cpu.and_then(|c| numa_monitor.get_numa_node_for_cpu(c)),
+                device_node: target_nodes[slot.target],
+                memory_mode,
+            });
         }
 
+        let disk_elapsed = disk_start.elapsed();
+        let cpu_used = process_cpu_time().saturating_sub(cpu_before);
+        let disk_util: Vec<DiskUtil> = disks
+            .into_iter()
+            .zip(disk_before)
+            .filter_map(|((name, monitor), before)| {
+                let after = monitor.collect().ok()?;
+                Some(DiskUtil::new(name, after.since(&before?), disk_elapsed))
+            })
+            .collect();
+
         stop_flag.store(true, Ordering::Relaxed);
         if let Some(handle) = monitor_handle {
-            handle.join().unwrap();
+            handle
+                .join()
+                .map_err(|_| anyhow::anyhow!("progress monitor panicked"))?;
+        }
+        if let Some(e) = failure {
+            return Err(e);
         }
 
-        // Aggregate statistics
-        let mut total_bytes_read = 0u64;
-        let mut total_bytes_written = 0u64;
-        let mut total_ops = 0u64;
-        let mut total_latency_ns = 0u64;
-        let mut min_latency_ns = u64::MAX;
-        let mut max_latency_ns = 0u64;
-
-        for stats in workers_final {
-            total_bytes_read += stats.bytes_read.load(std::sync::atomic::Ordering::Relaxed);
-            total_bytes_written += stats
-                .bytes_written
-                .load(std::sync::atomic::Ordering::Relaxed);
-            total_ops += stats
-                .ops_completed
-                .load(std::sync::atomic::Ordering::Relaxed);
-            total_latency_ns += stats
-                .total_latency_ns
-                .load(std::sync::atomic::Ordering::Relaxed);
-
-            let min = stats
-                .min_latency_ns
-                .load(std::sync::atomic::Ordering::Relaxed);
-            if min < min_latency_ns {
-                min_latency_ns = min;
+        let num_nodes = numa_monitor.nodes().len();
+        let all: Vec<usize> = (0..total_workers).collect();
+        let mut results = self.summarize(
+            jobs[0].0.clone(),
+            &jobs[0].1,
+            &all,
+            &slots,
+            &workers_final,
+            &outcomes,
+            num_nodes,
+        );
+        results.disk_util = disk_util;
+        if !disk_elapsed.is_zero() {
+            results.cpu_cores = cpu_used.as_secs_f64() / disk_elapsed.as_secs_f64();
+        }
+        if let Some(numa) = results.numa.as_ref().filter(|n| n.cross_node) {
+            eprintln!(
+                "Warning: cross-node placement detected - device nodes {:?}, worker nodes {:?}",
+                numa.device_nodes, numa.worker_nodes
+            );
+        }
+
+        if jobs.len() > 1 {
+            results.name = "all".to_string();
+            results.config = self.config.clone();
+            results.jobs = jobs
+                .iter()
+                .enumerate()
+                .map(|(j, (name, config))| {
+                    let workers: Vec<usize> = (0..total_workers)
+                        .filter(|&i| slots[i].job == j)
+                        .collect();
+                    self.summarize(
+                        name.clone(),
+                        config,
+                        &workers,
+                        &slots,
+                        &workers_final,
+                        &outcomes,
+                        num_nodes,
+                    )
+                })
+                .collect();
+        }
+
+        Ok(results)
+    }
+
+    /// Device each worker issues I/O to: its target, or with a filename format
+    /// its own file inside the target directory
+    fn slot_devices(
+        &self,
+        jobs: &[(String, Config)],
+        slots: &[WorkerSlot],
+    ) -> Result<Vec<Arc<Device>>> {
+        let Some(format) = &self.config.filename_format else {
+            return Ok(slots
+                .iter()
+                .map(|slot| Arc::clone(&self.devices[slot.target]))
+                .collect());
+        };
+        let mut devices = Vec::with_capacity(slots.len());
+        for (i, slot) in slots.iter().enumerate() {
+            let (name, config) = &jobs[slot.job];
+            let job_num = slots[..i]
+                .iter()
+                .filter(|s| s.job == slot.job && s.target == slot.target)
+                .count();
+            let path = file::worker_file(self.devices[slot.target].path(), format, name, job_num);
+            let layout = self.config.file_layout.resolve(config.read_percent());
+            file::prepare(&path, self.config.size, layout)?;
+            let mut device = Device::open(&path, self.open_mode)?;
+            if let Some(size) = self.config.size {
+                device.limit_size(size);
             }
+            devices.push(Arc::new(device));
+        }
+        Ok(devices)
+    }
 
-            let max = stats
-                .max_latency_ns
-                .load(std::sync::atomic::Ordering::Relaxed);
-            if max > max_latency_ns {
-                max_latency_ns = max;
+    /// Discard the whole range of every distinct target, so an SSD starts the
+    /// run from a clean state
+    fn trim_targets(devices: &[Arc<Device>]) -> Result<()> {
+        let mut trimmed: Vec<&Arc<Device>> = Vec::new();
+        for device in devices {
+            if trimmed.iter().any(|d| Arc::ptr_eq(d, device)) || device.size() == 0 {
+                continue;
             }
+            eprintln!(
+                "Discarding {} ({} bytes)",
+                device.path().display(),
+                device.size()
+            );
+            device
+                .trim(OpKind::Discard, 0, device.size())
+                .with_context(|| format!("Failed to discard {}", device.path().display()))?;
+            trimmed.push(device);
         }
+        Ok(())
+    }
 
-        let duration_secs = self.config.duration.as_secs_f64();
-        let throughput_read_mbps = (total_bytes_read as f64 / duration_secs) / (1024.0 * 1024.0);
-        let throughput_write_mbps =
-            (total_bytes_written as f64 / duration_secs) / (1024.0 * 1024.0);
-        let iops = total_ops as f64 / duration_secs;
-        let avg_latency_us = if total_ops > 0 {
-            (total_latency_ns / total_ops) as f64 / 1000.0
-        } else {
-            0.0
-        };
+    /// One diskstats monitor per distinct disk behind the targets
+    fn disk_monitors(&self) -> Vec<(String, IoStatsMonitor)> {
+        let mut disks: Vec<(String, IoStatsMonitor)> = Vec::new();
+        for device in &self.devices {
+            let monitor = IoStatsMonitor::new(device.path().to_path_buf());
+            if let Some(name) = monitor.disk_name() {
+                if !disks.iter().any(|(n, _)| *n == name) {
+                    disks.push((name, monitor));
+                }
+            }
+        }
+        disks
+    }
 
-        Ok(BenchmarkResults {
-            total_bytes_read,
-            total_bytes_written,
-            total_ops,
-            failed_ops: 0,
-            duration: self.config.duration,
-            throughput_read_mbps,
-            throughput_write_mbps,
-            iops,
-            avg_latency_us,
-            min_latency_us: min_latency_ns as f64 / 1000.0,
-            max_latency_us: max_latency_ns as f64 / 1000.0,
-        })
+    /// One health monitor per distinct whole disk behind the targets
+    fn health_monitors(&self) -> Vec<HealthMonitor> {
+        let sysfs = Path::new(SYSFS_ROOT);
+        let mut disks: Vec<String> = Vec::new();
+        for device in &self.devices {
+            if let Some(disk) = Device::disk_name(sysfs, device.path()) {
+                if !disks.contains(&disk) {
+                    disks.push(disk);
+                }
+            }
+        }
+        disks
+            .iter()
+            .map(|disk| HealthMonitor::new(sysfs, disk))
+            .collect()
+    }
+
+    /// Build results for a subset of workers
+    #[allow(clippy::too_many_arguments)]
+    fn summarize(
+        &self,
+        name: String,
+        config: &Config,
+        workers: &[usize],
+        slots: &[WorkerSlot],
+        stats: &[Arc<WorkerStats>],
+        outcomes: &[WorkerOutcome],
+        num_nodes: usize,
+    ) -> BenchmarkResults {
+        let duration = self.config.duration;
+
+        let mut targets: Vec<TargetResults> = self
+            .devices
+            .iter()
+            .enumerate()
+            .map(|(t, device)| {
+                let totals = StatsTotals::sum(
+                    workers
+                        .iter()
+                        .filter(|&&i| slots[i].target == t)
+                        .map(|&i| &stats[i]),
+                );
+                TargetResults {
+                    path: device.path().to_path_buf(),
+                    total_bytes_read: totals.bytes_read,
+                    total_bytes_written: totals.bytes_written,
+                    total_ops: totals.ops,
+                    failed_ops: totals.failed_ops,
+                    throughput_read_mbps: StatsTotals::throughput_mbps(totals.bytes_read, duration),
+                    throughput_write_mbps: StatsTotals::throughput_mbps(
+                        totals.bytes_written,
+                        duration,
+                    ),
+                    iops: totals.ops as f64 / duration.as_secs_f64(),
+                    avg_latency_us: totals.avg_latency_us(),
+                    min_latency_us: totals.min_latency_us(),
+                    max_latency_us: totals.max_latency_ns as f64 / 1000.0,
+                    outlier: false,
+                    device: self.infos.get(t).cloned(),
+                }
+            })
+            .collect();
+        TargetResults::mark_outliers(&mut targets);
+
+        let totals = StatsTotals::sum(workers.iter().map(|&i| &stats[i]));
+        let latency = totals.combined_latency();
+
+        let worker_nodes: Vec<Option<usize>> = workers.iter().map(|&i| outcomes[i].node).collect();
+        let device_nodes: Vec<Option<usize>> =
+            workers.iter().map(|&i| outcomes[i].device_node).collect();
+        let cross_node = worker_nodes
+            .iter()
+            .zip(device_nodes.iter())
+            .any(|(worker, device)| matches!((worker, device), (Some(w), Some(d)) if w != d));
+
+        BenchmarkResults {
+            name,
+            config: config.clone(),
+            total_bytes_read: totals.bytes_read,
+            total_bytes_written: totals.bytes_written,
+            total_ops: totals.ops,
+            read_ops: totals.read_ops,
+            write_ops: totals.ops - totals.read_ops - totals.trim_ops,
+            failed_ops: totals.failed_ops,
+            duration,
+            throughput_read_mbps: StatsTotals::throughput_mbps(totals.bytes_read, duration),
+            throughput_write_mbps: StatsTotals::throughput_mbps(totals.bytes_written, duration),
+            iops: totals.ops as f64 / duration.as_secs_f64(),
+            avg_latency_us: totals.avg_latency_us(),
+            min_latency_us: totals.min_latency_us(),
+            max_latency_us: totals.max_latency_ns as f64 / 1000.0,
+            p50_latency_us: latency.percentile_us(50.0),
+            p90_latency_us: latency.percentile_us(90.0),
+            p99_latency_us: latency.percentile_us(99.0),
+            p999_latency_us: latency.percentile_us(99.9),
+            read_latency: totals.read_latency,
+            write_latency: totals.write_latency,
+            numa: Some(NumaReport {
+                policy: config.numa,
+                num_nodes,
+                device_nodes,
+                worker_cpus: workers.iter().map(|&i| outcomes[i].cpus.clone()).collect(),
+                worker_nodes,
+                cross_node,
+            }),
+            memory_mode_requested: config.memory_mode,
+            memory_modes: workers.iter().map(|&i| outcomes[i].memory_mode).collect(),
+            targets,
+            jobs: Vec::new(),
+            disk_util: Vec::new(),
+            cpu_cores: 0.0,
+            open_flags: self.open_mode.to_string(),
+            queue_changes: Vec::new(),
+            health: Vec::new(),
+            trim_ops: totals.trim_ops,
+            total_bytes_trimmed: totals.bytes_trimmed,
+            throughput_trim_mbps: StatsTotals::throughput_mbps(totals.bytes_trimmed, duration),
+            trim_latency: totals.trim_latency,
+            flush_ops: totals.flush_ops,
+            flush_latency: totals.flush_latency,
+            commit_latency: totals.commit_latency,
+        }
     }
 
     /// Run all workloads sequentially
@@ -238,9 +844,13 @@ impl IoEngine {
         ];
 
         let mut combined_results = BenchmarkResults {
+            name: Workload::All.name().to_string(),
+            config: self.config.clone(),
             total_bytes_read: 0,
             total_bytes_written: 0,
             total_ops: 0,
+            read_ops: 0,
+            write_ops: 0,
             failed_ops: 0,
             duration: Duration::ZERO,
             throughput_read_mbps: 0.0,
@@ -249,12 +859,36 @@ impl IoEngine {
             avg_latency_us: 0.0,
             min_latency_us: f64::MAX,
             max_latency_us: 0.0,
+            p50_latency_us: 0.0,
+            p90_latency_us: 0.0,
+            p99_latency_us: 0.0,
+            p999_latency_us: 0.0,
+            read_latency: LatencySnapshot::default(),
+            write_latency: LatencySnapshot::default(),
+            numa: None,
+            memory_mode_requested: self.config.memory_mode,
+            memory_modes: Vec::new(),
+            targets: Vec::new(),
+            jobs: Vec::new(),
+            disk_util: Vec::new(),
+            cpu_cores: 0.0,
+            open_flags: self.open_mode.to_string(),
+            queue_changes: Vec::new(),
+            health: Vec::new(),
+            trim_ops: 0,
+            total_bytes_trimmed: 0,
+            throughput_trim_mbps: 0.0,
+            trim_latency: LatencySnapshot::default(),
+            flush_ops: 0,
+            flush_latency: LatencySnapshot::default(),
+            commit_latency: LatencySnapshot::default(),
         };
 
         for workload in workloads.iter() {
-            println!("\n=== Running workload: {:?} ===", workload);
+            eprintln!("\n=== Running workload: {:?} ===", workload);
             let mut config = self.config.clone();
             config.workload = *workload;
+            config.trim_before = false;
 
             let engine = IoEngine::new(config)?;
             let results = engine.run()?;
@@ -262,8 +896,12 @@ impl IoEngine {
             combined_results.total_bytes_read += results.total_bytes_read;
             combined_results.total_bytes_written += results.total_bytes_written;
             combined_results.total_ops += results.total_ops;
+            combined_results.read_ops += results.read_ops;
+            combined_results.write_ops += results.write_ops;
             combined_results.failed_ops += results.failed_ops;
             combined_results.duration += results.duration;
+            // Summed as core-seconds here, averaged over the total duration below
+            combined_results.cpu_cores += results.cpu_cores * results.duration.as_secs_f64();
 
             if results.min_latency_us < combined_results.min_latency_us {
                 combined_results.min_latency_us = results.min_latency_us;
@@ -271,6 +909,27 @@ impl IoEngine {
             if results.max_latency_us > combined_results.max_latency_us {
                 combined_results.max_latency_us = results.max_latency_us;
             }
+            combined_results.read_latency.merge(&results.read_latency);
+            combined_results.write_latency.merge(&results.write_latency);
+            combined_results.trim_ops += results.trim_ops;
+            combined_results.total_bytes_trimmed += results.total_bytes_trimmed;
+            combined_results.trim_latency.merge(&results.trim_latency);
+            combined_results.flush_ops += results.flush_ops;
+            combined_results.flush_latency.merge(&results.flush_latency);
+            combined_results.commit_latency.merge(&results.commit_latency);
+            combined_results.numa = results.numa.clone();
+            combined_results.memory_modes = results.memory_modes.clone();
+            for disk in &results.disk_util {
+                match combined_results
+                    .disk_util
+                    .iter_mut()
+                    .find(|d| d.name == disk.name)
+                {
+                    Some(existing) => existing.merge(disk),
+                    None => combined_results.disk_util.push(disk.clone()),
+                }
+            }
+            combined_results.jobs.push(results);
         }
 
         let duration_secs = combined_results.duration.as_secs_f64();
@@ -278,14 +937,45 @@ impl IoEngine {
             (combined_results.total_bytes_read as f64 / duration_secs) / (1024.0 * 1024.0);
         combined_results.throughput_write_mbps =
             (combined_results.total_bytes_written as f64 / duration_secs) / (1024.0 * 1024.0);
+        combined_results.throughput_trim_mbps = StatsTotals::throughput_mbps(
+            combined_results.total_bytes_trimmed,
+            combined_results.duration,
+        );
         combined_results.iops = combined_results.total_ops as f64 / duration_secs;
-        combined_results.avg_latency_us = if combined_results.total_ops > 0 {
-            // Approximate average latency
-            (combined_results.total_ops as f64 / duration_secs) / 1000.0
+        combined_results.cpu_cores /= duration_secs;
+        let mut latency = combined_results.read_latency.clone();
+        latency.merge(&combined_results.write_latency);
+        latency.merge(&combined_results.trim_latency);
+        let samples_of = |r: &BenchmarkResults| {
+            r.read_latency.count + r.write_latency.count + r.trim_latency.count
+        };
+        let samples: u64 = combined_results.jobs.iter().map(samples_of).sum();
+        combined_results.avg_latency_us = if samples > 0 {
+            // Sample-weighted average of the per-workload averages
+            combined_results
+                .jobs
+                .iter()
+                .map(|r| r.avg_latency_us * samples_of(r) as f64)
+                .sum::<f64>()
+                / samples as f64
         } else {
             0.0
         };
+        combined_results.p50_latency_us = latency.percentile_us(50.0);
+        combined_results.p90_latency_us = latency.percentile_us(90.0);
+        combined_results.p99_latency_us = latency.percentile_us(99.0);
+        combined_results.p999_latency_us = latency.percentile_us(99.9);
 
         Ok(combined_results)
     }
 }
+
+/// Largest read share of any job, which decides how new files are laid out
+fn max_read_percent(config: &Config) -> Result<u8> {
+    Ok(config
+        .job_configs()?
+        .iter()
+        .map(|(_, c)| c.read_percent())
+        .max()
+        .unwrap_or(100))
+}
@@ -0,0 +1,92 @@
+use crate::config::FileLayout;
+use anyhow::{Context, Result};
+use rand::RngCore;
+use std::fs::{self, OpenOptions};
+use std::io::{Seek, SeekFrom, Write};
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+
+// Chunk written at a time when laying files out by writing
+const LAYOUT_CHUNK: usize = 1024 * 1024;
+
+/// Make sure a regular-file target exists and holds at least `size` bytes,
+/// creating or extending it as needed. Block devices and other special files
+/// are left alone. Returns true when the file had to be laid out.
+pub fn prepare(path: &Path, size: Option<u64>, layout: FileLayout) -> Result<bool> {
+    let existing = match fs::metadata(path) {
+        Ok(metadata) if !metadata.is_file() => return Ok(false),
+        Ok(metadata) => metadata.len(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => 0,
+        Err(e) => return Err(e).with_context(|| format!("Failed to stat {}", path.display())),
+    };
+    let Some(size) = size else {
+        if existing == 0 {
+            return Err(anyhow::anyhow!(
+                "{} is missing or empty; pass --size to create it",
+                path.display()
+            ));
+        }
+        return Ok(false);
+    };
+    if existing >= size {
+        return Ok(false);
+    }
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    let fallocated = layout == FileLayout::Fallocate && {
+        let ret = unsafe {
+            libc::fallocate(
+                file.as_raw_fd(),
+                0,
+                existing as libc::off_t,
+                (size - existing) as libc::off_t,
+            )
+        };
+        if ret != 0 {
+            let err = std::io::Error::last_os_error();
+            if err.raw_os_error() != Some(libc::EOPNOTSUPP) {
+                return Err(err)
+                    .with_context(|| format!("Failed to preallocate {}", path.display()));
+            }
+            eprintln!(
+                "Warning: {} does not support fallocate, laying it out by writing",
+                path.display()
+            );
+        }
+        ret == 0
+    };
+    if !fallocated {
+        // Random data, so compressing or deduplicating storage sees real blocks
+        let mut chunk = vec![0u8; LAYOUT_CHUNK];
+        rand::thread_rng().fill_bytes(&mut chunk);
+        file.seek(SeekFrom::Start(existing))?;
+        let mut written = existing;
+        while written < size {
+            let len = (size - written).min(LAYOUT_CHUNK as u64) as usize;
+            file.write_all(&chunk[..len])
+                .with_context(|| format!("Failed to lay out {}", path.display()))?;
+            written += len as u64;
+        }
+    }
+    file.sync_all()?;
+    Ok(true)
+}
+
+/// Whether a target path names a directory that per-worker files go into
+pub fn is_directory(path: &Path) -> bool {
+    fs::metadata(path).map(|m| m.is_dir()).unwrap_or(false)
+}
+
+/// File a worker uses inside a target directory. Like fio, the format may use
+/// $jobname and $jobnum (the worker's number within its job on that target).
+pub fn worker_file(dir: &Path, format: &str, job_name: &str, job_num: usize) -> PathBuf {
+    let name = format
+        .replace("$jobname", job_name)
+        .replace("$jobnum", &job_num.to_string());
+    dir.join(name)
+}
@@ -0,0 +1,271 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Log-linear buckets: 32 linear sub-buckets per power of two (~3% relative error)
+const SUB_BUCKET_BITS: u32 = 5;
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+// Highest tracked value is 2^40 ns (~18 minutes); larger values land in the last bucket
+const MAX_VALUE_BITS: u32 = 40;
+const NUM_BUCKETS: usize = (MAX_VALUE_BITS - SUB_BUCKET_BITS + 1) as usize * SUB_BUCKETS + SUB_BUCKETS;
+
+/// Lock-free latency histogram shared between a worker and the monitor
+pub struct LatencyHistogram {
+    buckets: Vec<AtomicU64>,
+}
+
+/// Point-in-time copy of a histogram, mergeable across workers
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LatencySnapshot {
+    /// Sparse (bucket index, count) pairs
+    pub buckets: Vec<(u32, u64)>,
+    pub count: u64,
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        Self {
+            buckets: (0..NUM_BUCKETS).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+    pub fn record(&self, latency_ns: u64) {
+        self.buckets[bucket_index(latency_ns)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn reset(&self) {
+        for bucket in &self.buckets {
+            bucket.store(0, Ordering::Relaxed);
+        }
+    }
+
+    pub fn snapshot(&self) -> LatencySnapshot {
+        let mut snapshot = LatencySnapshot::default();
+        for (i, bucket) in self.buckets.iter().enumerate() {
+            let count = bucket.load(Ordering::Relaxed);
+            if count > 0 {
+                snapshot.buckets.push((i as u32, count));
+                snapshot.count += count;
+            }
+        }
+        snapshot
+    }
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for LatencyHistogram {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LatencyHistogram")
+            .field("count", &self.snapshot().count)
+            .finish()
+    }
+}
+
+impl LatencySnapshot {
+    /// Add another snapshot's samples into this one
+    pub fn merge(&mut self, other: &LatencySnapshot) {
+        for &(index, count) in &other.buckets {
+            match self.buckets.binary_search_by_key(&index, |&(i, _)| i) {
+                Ok(pos) => self.buckets[pos].1 += count,
+                Err(pos) => self.buckets.insert(pos, (index, count)),
+            }
+        }
+        self.count += other.count;
+    }
+
+    /// Latency in nanoseconds at the given percentile (0-100)
+    pub fn percentile_ns(&self, percentile: f64) -> u64 {
+        if self.count == 0 {
+            return 0;
+        }
+        let target = ((percentile / 100.0) * self.count as f64).ceil().max(1.0) as u64;
+        let mut seen = 0u64;
+        for &(index, count) in &self.buckets {
+            seen += count;
+            if seen >= target {
+                return bucket_value(index as usize);
+            }
+        }
+        self.buckets
+            .last()
+            .map(|&(index, _)| bucket_value(index as usize))
+            .unwrap_or(0)
+    }
+
+    /// Smallest recorded latency in nanoseconds (bucket resolution)
+    pub fn min_ns(&self) -> u64 {
+        self.buckets
+            .first()
+            .map_or(0, |&(index, _)| bucket_value(index as usize))
+    }
+
+    /// Largest recorded latency in nanoseconds (bucket resolution)
+    pub fn max_ns(&self) -> u64 {
+        self.buckets
+            .last()
+            .map_or(0, |&(index, _)| bucket_value(index as usize))
+    }
+
+    /// Mean latency in nanoseconds (bucket resolution)
+    pub fn mean_ns(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        let sum: f64 = self
+            .buckets
+            .iter()
+            .map(|&(index, count)| bucket_value(index as usize) as f64 * count as f64)
+            .sum();
+        sum / self.count as f64
+    }
+
+    /// Standard deviation in nanoseconds (bucket resolution)
+    pub fn stddev_ns(&self) -> f64 {
+        if self.count < 2 {
+            return 0.0;
+        }
+        let mean = self.mean_ns();
+        let sum_sq: f64 = self
+            .buckets
+            .iter()
+            .map(|&(index, count)| (bucket_value(index as usize) as f64 - mean).powi(2) * count as f64)
+            .sum();
+        (sum_sq / (self.count - 1) as f64).sqrt()
+    }
+
+    /// Latency in microseconds at the given percentile (0-100)
+    pub fn percentile_us(&self, percentile: f64) -> f64 {
+        self.percentile_ns(percentile) as f64 / 1000.0
+    }
+}
+
+fn bucket_index(value: u64) -> usize {
+    if value < SUB_BUCKETS as u64 {
+        return value as usize;
+    }
+    let msb = 63 - value.leading_zeros();
+    if msb >= MAX_VALUE_BITS {
+        return NUM_BUCKETS - 1;
+    }
+    let shift = msb - SUB_BUCKET_BITS;
+    let sub = ((value >> shift) as usize) - SUB_BUCKETS;
+    (shift as usize + 1) * SUB_BUCKETS + sub
+}
+
+/// Representative (midpoint) value of a bucket
+fn bucket_value(index: usize) -> u64 {
+    if index < SUB_BUCKETS {
+        return index as u64;
+    }
+    let shift = (index / SUB_BUCKETS - 1) as u32;
+    let sub = (index % SUB_BUCKETS) as u64;
+    let lower = (SUB_BUCKETS as u64 + sub) << shift;
+    lower + ((1u64 << shift) >> 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Largest relative error of a bucket's representative value
+    const MAX_ERROR: f64 = 1.0 / SUB_BUCKETS as f64;
+
+    fn relative_error(value: u64) -> f64 {
+        (bucket_value(bucket_index(value)) as f64 - value as f64).abs() / value as f64
+    }
+
+    #[test]
+    fn small_values_are_exact() {
+        for value in 0..2 * SUB_BUCKETS as u64 {
+            assert_eq!(bucket_value(bucket_index(value)), value);
+        }
+    }
+
+    #[test]
+    fn buckets_split_at_powers_of_two_up_to_2_40() {
+        let mut previous = bucket_index(SUB_BUCKETS as u64 - 1);
+        for bits in SUB_BUCKET_BITS..MAX_VALUE_BITS {
+            let power = 1u64 << bits;
+            assert_eq!(bucket_index(power - 1), previous, "2^{} - 1", bits);
+            let index = bucket_index(power);
+            assert_eq!(index, previous + 1, "2^{}", bits);
+            assert!(relative_error(power) <= MAX_ERROR);
+            assert!(relative_error(power - 1) <= MAX_ERROR);
+            assert!(relative_error(power + power / 2) <= MAX_ERROR);
+            previous = bucket_index((power << 1) - 1);
+        }
+        assert!(previous < NUM_BUCKETS - 1);
+    }
+
+    #[test]
+    fn every_bucket_holds_its_representative_value() {
+        let last_tracked = bucket_index((1u64 << MAX_VALUE_BITS) - 1);
+        for index in 0..=last_tracked {
+            assert_eq!(bucket_index(bucket_value(index)), index);
+        }
+    }
+
+    #[test]
+    fn values_from_2_40_land_in_the_last_bucket() {
+        assert_eq!(bucket_index(1 << MAX_VALUE_BITS), NUM_BUCKETS - 1);
+        assert_eq!(bucket_index(u64::MAX), NUM_BUCKETS - 1);
+        assert!(bucket_value(NUM_BUCKETS - 1) >= 1 << MAX_VALUE_BITS);
+    }
+
+    #[test]
+    fn percentiles_follow_the_recorded_distribution() {
+        let histogram = LatencyHistogram::new();
+        for us in 1..=1000u64 {
+            histogram.record(us * 1000);
+        }
+        let snapshot = histogram.snapshot();
+        assert_eq!(snapshot.count, 1000);
+        for (percentile, expected_ns) in [(50.0, 500_000.0), (90.0, 900_000.0), (99.0, 990_000.0)] {
+            let actual = snapshot.percentile_ns(percentile) as f64;
+            assert!(
+                (actual - expected_ns).abs() / expected_ns <= MAX_ERROR,
+                "p{}: {}",
+                percentile,
+                actual
+            );
+        }
+        assert_eq!(snapshot.percentile_ns(0.0), snapshot.min_ns());
+        assert_eq!(snapshot.percentile_ns(100.0), snapshot.max_ns());
+        assert_eq!(LatencySnapshot::default().percentile_ns(99.0), 0);
+    }
+
+    #[test]
+    fn percentiles_reach_2_40_ns() {
+        let histogram = LatencyHistogram::new();
+        histogram.record(50);
+        histogram.record((1 << MAX_VALUE_BITS) - 1);
+        let snapshot = histogram.snapshot();
+        assert_eq!(snapshot.percentile_ns(50.0), 50);
+        let max = snapshot.percentile_ns(100.0) as f64;
+        let expected = (1u64 << MAX_VALUE_BITS) as f64;
+        assert!((max - expected).abs() / expected <= MAX_ERROR, "{}", max);
+    }
+
+    #[test]
+    fn merged_snapshots_keep_every_sample() {
+        let a = LatencyHistogram::new();
+        let b = LatencyHistogram::new();
+        for ns in [10, 1_000, 1_000_000] {
+            a.record(ns);
+        }
+        for ns in [1_000, 5_000_000] {
+            b.record(ns);
+        }
+        let mut merged = a.snapshot();
+        merged.merge(&b.snapshot());
+        assert_eq!(merged.count, 5);
+        assert!(merged.buckets.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(merged.min_ns(), 10);
+        assert_eq!(merged.percentile_ns(40.0), merged.percentile_ns(60.0));
+        assert!((merged.max_ns() as f64 - 5e6).abs() / 5e6 <= MAX_ERROR);
+    }
+}
@@ -1,6 +1,12 @@
+pub mod blockdev;
+pub mod buffer;
 pub mod device;
 pub mod engine;
+pub mod file;
+pub mod histogram;
 pub mod patterns;
+pub mod queue;
+pub mod safety;
 pub mod worker;
 
 pub use device::Device;
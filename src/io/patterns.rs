@@ -1,5 +1,6 @@
-use crate::config::IoMode;
+use crate::config::{AccessDistribution, BlockSizeSplit, IoMode, OpKind, OpMix};
 use rand::Rng;
+use rand_distr::Distribution;
 use std::sync::Mutex;
 
 /// I/O pattern generator
@@ -8,6 +9,11 @@ pub struct IoPattern {
     block_size: usize,
     device_size: u64,
     rng: Mutex<rand::rngs::StdRng>,
+    // (block size, cumulative percentage) when operations use a mix of sizes
+    block_sizes: Vec<(usize, u32)>,
+    // (operation, cumulative percentage) when operations go beyond reads and writes
+    ops: Vec<(OpKind, u32)>,
+    distribution: AccessDistribution,
 }
 
 impl IoPattern {
@@ -18,38 +24,158 @@ impl IoPattern {
             block_size,
             device_size,
             rng: Mutex::new(rand::rngs::StdRng::from_entropy()),
+            block_sizes: Vec::new(),
+            ops: Vec::new(),
+            distribution: AccessDistribution::Uniform,
         }
     }
 
-    /// Generate next I/O offset
-    pub fn next_offset(&self, current: u64) -> u64 {
+    /// Draw each operation's size from a weighted mix instead of the fixed block size
+    pub fn set_block_size_split(&mut self, split: &BlockSizeSplit) {
+        let mut cumulative = 0;
+        self.block_sizes = split
+            .entries()
+            .iter()
+            .map(|&(bs, pct)| {
+                cumulative += pct;
+                (bs, cumulative)
+            })
+            .collect();
+    }
+
+    /// Draw each operation's type from a weighted mix instead of read_percent
+    pub fn set_op_mix(&mut self, mix: &OpMix) {
+        let mut cumulative = 0;
+        self.ops = mix
+            .entries()
+            .iter()
+            .map(|&(op, pct)| {
+                cumulative += pct;
+                (op, cumulative)
+            })
+            .collect();
+    }
+
+    /// Spread random offsets according to the given distribution
+    pub fn set_distribution(&mut self, distribution: AccessDistribution) {
+        self.distribution = distribution;
+    }
+
+    /// Size of the next operation
+    pub fn next_block_size(&self) -> usize {
+        if self.block_sizes.is_empty() {
+            return self.block_size;
+        }
+        let roll = self.rng.lock().unwrap().gen_range(0..100);
+        self.block_sizes
+            .iter()
+            .find(|&&(_, cumulative)| roll < cumulative)
+            .or(self.block_sizes.last())
+            .map_or(self.block_size, |&(bs, _)| bs)
+    }
+
+    /// Average operation size, used to turn bandwidth limits into operation rates
+    pub fn mean_block_size(&self) -> f64 {
+        if self.block_sizes.is_empty() {
+            return self.block_size as f64;
+        }
+        let mut previous = 0;
+        self.block_sizes
+            .iter()
+            .map(|&(bs, cumulative)| {
+                let pct = cumulative - previous;
+                previous = cumulative;
+                bs as f64 * pct as f64 / 100.0
+            })
+            .sum()
+    }
+
+    /// Largest operation this pattern issues
+    pub fn max_block_size(&self) -> usize {
+        self.block_sizes
+            .iter()
+            .map(|&(bs, _)| bs)
+            .max()
+            .unwrap_or(self.block_size)
+    }
+
+    /// Generate the next I/O offset for an operation of `len` bytes
+    /// following one of `previous_len` bytes at `current`
+    pub fn next_offset(&self, current: u64, previous_len: usize, len: usize) -> u64 {
+        let sequential = || {
+            let next = current + previous_len as u64;
+            if next + len as u64 > self.device_size { 0 } else { next }
+        };
         match self.mode {
-            IoMode::Sequential => {
-                let next = current + self.block_size as u64;
-                if next >= self.device_size { 0 } else { next }
-            }
-            IoMode::Random => {
-                let max_offset = self.device_size.saturating_sub(self.block_size as u64);
-                self.rng.lock().unwrap().gen_range(0..=max_offset)
-            }
+            IoMode::Sequential => sequential(),
+            IoMode::Random => self.random_offset(&mut self.rng.lock().unwrap(), len),
             IoMode::Mixed => {
                 // 70% sequential, 30% random
                 let mut rng = self.rng.lock().unwrap();
                 if rng.gen_bool(0.7) {
-                    let next = current + self.block_size as u64;
-                    if next >= self.device_size { 0 } else { next }
+                    sequential()
                 } else {
-                    let max_offset = self.device_size.saturating_sub(self.block_size as u64);
-                    rng.gen_range(0..=max_offset)
+                    self.random_offset(&mut rng, len)
                 }
             }
         }
     }
 
+    /// Random offset aligned to the smallest block size (O_DIRECT rejects unaligned offsets)
+    fn random_offset(&self, rng: &mut rand::rngs::StdRng, len: usize) -> u64 {
+        let align = self
+            .block_sizes
+            .iter()
+            .map(|&(bs, _)| bs)
+            .min()
+            .unwrap_or(self.block_size) as u64;
+        let span = self.device_size.saturating_sub(len as u64) + align;
+        let blocks = (span / align).max(1);
+        let block = match self.distribution {
+            AccessDistribution::Uniform => rng.gen_range(0..blocks),
+            AccessDistribution::Zipf(theta) => {
+                // Rank 1 is the hottest block
+                let zipf = rand_distr::Zipf::new(blocks, theta).expect("validated zipf theta");
+                zipf.sample(rng) as u64 - 1
+            }
+            AccessDistribution::Pareto(h) => {
+                // Same mapping as fio: rank = (n - 1) * (1 - u)^(ln(h) / ln(1 - h))
+                let exponent = h.ln() / (1.0 - h).ln();
+                let u: f64 = rng.gen();
+                ((blocks - 1) as f64 * (1.0 - u).powf(exponent)) as u64
+            }
+            AccessDistribution::Normal(stddev_pct) => {
+                let center = blocks as f64 / 2.0;
+                let stddev = (blocks as f64 * stddev_pct / 100.0).max(f64::MIN_POSITIVE);
+                let normal = rand_distr::Normal::new(center, stddev).expect("validated stddev");
+                normal.sample(rng).clamp(0.0, (blocks - 1) as f64) as u64
+            }
+        };
+        block.min(blocks - 1) * align
+    }
+
     /// Check if this is a read operation (based on read_percent)
     pub fn is_read(&self, read_percent: u8) -> bool {
         self.rng.lock().unwrap().gen_range(0..100) < read_percent
     }
+
+    /// Type of the next operation: from the op mix if one is set, otherwise a
+    /// read or write according to read_percent
+    pub fn next_op(&self, read_percent: u8) -> OpKind {
+        if self.ops.is_empty() {
+            return if self.is_read(read_percent) {
+                OpKind::Read
+            } else {
+                OpKind::Write
+            };
+        }
+        let roll = self.rng.lock().unwrap().gen_range(0..100);
+        self.ops
+            .iter()
+            .find(|&&(_, cumulative)| roll < cumulative)
+            .or(self.ops.last())
+            .map_or(OpKind::Read, |&(op, _)| op)
+    }
     
     /// Get the I/O mode
     pub fn mode(&self) -> IoMode {
@@ -0,0 +1,198 @@
+use crate::config::QueueSettings;
+use crate::io::device::{Device, SYSFS_ROOT};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, Once};
+
+/// Original values of the queue attributes currently changed, so a signal
+/// handler can put them back
+static PENDING: Mutex<Vec<(PathBuf, String)>> = Mutex::new(Vec::new());
+static SIGNAL_RESTORE: Once = Once::new();
+
+/// A block queue attribute changed for a run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueChange {
+    pub disk: String,
+    pub attribute: String,
+    pub original: String,
+    /// Value read back after writing; the kernel may clamp what was asked for
+    pub applied: String,
+    /// Value read back after restoring
+    pub restored: Option<String>,
+}
+
+/// Queue attributes changed on the targets' disks. They are put back by
+/// `restore`, when the guard is dropped (including on errors), or on Ctrl-C.
+pub struct QueueTuning {
+    entries: Vec<(PathBuf, QueueChange)>,
+    restored: bool,
+}
+
+impl QueueTuning {
+    /// Write the settings to the queue of every disk behind the targets
+    pub fn apply(targets: &[PathBuf], settings: &QueueSettings) -> Result<Self> {
+        install_signal_restore();
+        let sysfs = Path::new(SYSFS_ROOT);
+        let mut disks: Vec<String> = Vec::new();
+        for target in targets {
+            let disk = Device::disk_name(sysfs, target).ok_or_else(|| {
+                anyhow::anyhow!("{}: no block device to tune", target.display())
+            })?;
+            if !disks.contains(&disk) {
+                disks.push(disk);
+            }
+        }
+
+        // Dropping a partly applied guard restores what was already changed
+        let mut tuning = QueueTuning {
+            entries: Vec::new(),
+            restored: false,
+        };
+        for disk in &disks {
+            let queue = sysfs.join("class/block").join(disk).join("queue");
+            // Read every original first: switching the scheduler resets nr_requests
+            let originals = settings
+                .entries()
+                .into_iter()
+                .map(|(attribute, _)| {
+                    read_attribute(&queue.join(attribute))
+                        .with_context(|| format!("{}: no queue attribute {}", disk, attribute))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            for ((attribute, value), original) in settings.entries().into_iter().zip(originals) {
+                let path = queue.join(attribute);
+                fs::write(&path, value).with_context(|| {
+                    format!("Failed to set {}={} on {}", attribute, value, disk)
+                })?;
+                PENDING
+                    .lock()
+                    .unwrap()
+                    .push((path.clone(), original.clone()));
+                tuning.entries.push((
+                    path.clone(),
+                    QueueChange {
+                        disk: disk.clone(),
+                        attribute: attribute.to_string(),
+                        original,
+                        applied: read_attribute(&path).unwrap_or_else(|_| value.to_string()),
+                        restored: None,
+                    },
+                ));
+            }
+        }
+        Ok(tuning)
+    }
+
+    pub fn changes(&self) -> Vec<QueueChange> {
+        self.entries.iter().map(|(_, change)| change.clone()).collect()
+    }
+
+    /// Put the original values back and report what each attribute ended up as
+    pub fn restore(mut self) -> Vec<QueueChange> {
+        self.restore_all();
+        self.changes()
+    }
+
+    fn restore_all(&mut self) {
+        if self.restored {
+            return;
+        }
+        self.restored = true;
+        // Scheduler first: switching it resets nr_requests
+        let mut order: Vec<usize> = (0..self.entries.len()).collect();
+        order.sort_by_key(|&i| self.entries[i].1.attribute != "scheduler");
+        for &i in &order {
+            let (path, change) = &self.entries[i];
+            if let Err(e) = fs::write(path, &change.original) {
+                eprintln!(
+                    "Warning: failed to restore {}={} on {}: {}",
+                    change.attribute, change.original, change.disk, e
+                );
+            }
+        }
+        let mut pending = PENDING.lock().unwrap();
+        for (path, change) in &mut self.entries {
+            change.restored = read_attribute(path).ok();
+            pending.retain(|(p, _)| p != path);
+        }
+    }
+}
+
+impl Drop for QueueTuning {
+    fn drop(&mut self) {
+        self.restore_all();
+    }
+}
+
+/// Attribute value; for the scheduler only the active (bracketed) entry
+fn read_attribute(path: &Path) -> Result<String> {
+    let value = fs::read_to_string(path)?;
+    let value = value.trim();
+    Ok(value
+        .split_once('[')
+        .and_then(|(_, rest)| rest.split_once(']'))
+        .map(|(active, _)| active)
+        .unwrap_or(value)
+        .to_string())
+}
+
+/// On Ctrl-C or SIGTERM, restore the pending queue attributes before exiting.
+/// The benchmark blocks the async runtime, so the handler gets its own thread
+/// and runtime.
+fn install_signal_restore() {
+    SIGNAL_RESTORE.call_once(|| {
+        let (ready_tx, ready) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            use tokio::signal::unix::{signal, SignalKind};
+
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(runtime) => runtime,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                    return;
+                }
+            };
+            let signals = runtime.block_on(async {
+                Ok::<_, std::io::Error>((
+                    signal(SignalKind::terminate())?,
+                    signal(SignalKind::interrupt())?,
+                ))
+            });
+            let (mut terminate, mut interrupt) = match signals {
+                Ok(signals) => signals,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                    return;
+                }
+            };
+            let _ = ready_tx.send(Ok(()));
+            runtime.block_on(async {
+                tokio::select! {
+                    _ = terminate.recv() => {}
+                    _ = interrupt.recv() => {}
+                }
+            });
+
+            let pending = PENDING.lock().unwrap();
+            // Last changed first, so the oldest original wins for repeated paths,
+            // but schedulers before everything else
+            let mut order: Vec<&(PathBuf, String)> = pending.iter().rev().collect();
+            order.sort_by_key(|(path, _)| !path.ends_with("scheduler"));
+            for (path, original) in order {
+                if fs::write(path, original).is_ok() {
+                    eprintln!("Restored {} = {}", path.display(), original);
+                }
+            }
+            std::process::exit(130);
+        });
+        // Wait for the handlers, so a signal right after apply is caught
+        if let Ok(Err(e)) = ready.recv() {
+            eprintln!("Warning: queue settings won't be restored on Ctrl-C: {}", e);
+        }
+    });
+}
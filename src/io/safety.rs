@@ -0,0 +1,196 @@
+use anyhow::Result;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::{Path, PathBuf};
+
+// How much of each end of the device to scan for on-disk signatures. The head
+// covers btrfs (64 KiB + 64 bytes), the tail md 0.90 superblocks (up to 128 KiB)
+const HEAD_BYTES: usize = 68 * 1024;
+const TAIL_BYTES: usize = 128 * 1024;
+
+/// Reasons why writing to a block device would destroy data in use. Regular
+/// files are only benchmark scratch space and never report a hazard.
+pub fn write_hazards(path: &Path) -> Result<Vec<String>> {
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        // A file that is yet to be created holds nothing
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    if !metadata.file_type().is_block_device() {
+        return Ok(Vec::new());
+    }
+    let mut hazards = Vec::new();
+    let mounts = mounts()?;
+
+    // The device itself, and for whole disks every partition on it
+    let mut members = Vec::new();
+    if let Ok(dir) = fs::canonicalize(format!(
+        "/sys/dev/block/{}:{}",
+        libc::major(metadata.rdev()),
+        libc::minor(metadata.rdev())
+    )) {
+        if !dir.join("partition").exists() {
+            if let Ok(entries) = fs::read_dir(&dir) {
+                members.extend(
+                    entries
+                        .flatten()
+                        .map(|e| e.path())
+                        .filter(|p| p.join("partition").exists()),
+                );
+            }
+        }
+        members.insert(0, dir);
+    }
+
+    for member in &members {
+        let name = block_name(member);
+        if let Some(mount_point) = mount_point(&mounts, member) {
+            hazards.push(format!("{} is mounted at {}", name, mount_point));
+        }
+        for holder in holders(member) {
+            let holder_name = block_name(&holder);
+            let label = fs::read_to_string(holder.join("dm/name"))
+                .map(|n| format!("{} ({})", holder_name, n.trim()))
+                .unwrap_or(holder_name);
+            hazards.push(format!("{} is in use by {} (md/dm/LVM)", name, label));
+            if let Some(mount_point) = mount_point(&mounts, &holder) {
+                hazards.push(format!("{} is mounted at {}", label, mount_point));
+            }
+        }
+    }
+
+    if let Some(signature) = signature(path)? {
+        hazards.push(format!("{} carries {}", path.display(), signature));
+    }
+    Ok(hazards)
+}
+
+/// Refuse to continue when writing to `path` would destroy data in use
+pub fn check_writable(path: &Path) -> Result<()> {
+    let hazards = write_hazards(path)?;
+    if hazards.is_empty() {
+        return Ok(());
+    }
+    Err(anyhow::anyhow!(
+        "Refusing to write to {}:\n  {}\nPass --allow-destructive to overwrite it anyway",
+        path.display(),
+        hazards.join("\n  ")
+    ))
+}
+
+/// Mounted devices from /proc/self/mountinfo as ("major:minor", mount point)
+pub fn mounts() -> Result<Vec<(String, String)>> {
+    let content = fs::read_to_string("/proc/self/mountinfo")?;
+    Ok(content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace().skip(2);
+            let dev = fields.next()?;
+            let mount_point = fields.nth(1)?;
+            Some((dev.to_string(), mount_point.to_string()))
+        })
+        .collect())
+}
+
+pub fn mount_point<'a>(mounts: &'a [(String, String)], sys_dir: &Path) -> Option<&'a str> {
+    let dev = fs::read_to_string(sys_dir.join("dev")).ok()?;
+    let dev = dev.trim();
+    mounts
+        .iter()
+        .find(|(d, _)| d == dev)
+        .map(|(_, mount_point)| mount_point.as_str())
+}
+
+/// Devices stacked on a block device, including holders of holders
+fn holders(sys_dir: &Path) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    let Ok(entries) = fs::read_dir(sys_dir.join("holders")) else {
+        return found;
+    };
+    for entry in entries.flatten() {
+        if let Ok(holder) = fs::canonicalize(entry.path()) {
+            found.extend(holders(&holder));
+            found.insert(0, holder);
+        }
+    }
+    found
+}
+
+fn block_name(sys_dir: &Path) -> String {
+    sys_dir
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Filesystem, partition table, swap or RAID signature at either end of the device
+fn signature(path: &Path) -> Result<Option<&'static str>> {
+    let mut file = File::open(path)?;
+    let size = file.seek(SeekFrom::End(0))?;
+
+    let mut head = vec![0u8; HEAD_BYTES.min(size as usize)];
+    file.seek(SeekFrom::Start(0))?;
+    file.read_exact(&mut head)?;
+    let at = |buf: &[u8], offset: usize, magic: &[u8]| {
+        buf.get(offset..offset + magic.len()) == Some(magic)
+    };
+
+    let head_signatures: [(usize, &[u8], &'static str); 13] = [
+        (512, b"EFI PART", "a GPT partition table"),
+        (4096, b"EFI PART", "a GPT partition table"),
+        (1080, &[0x53, 0xef], "an ext2/3/4 filesystem"),
+        (0, b"XFSB", "an XFS filesystem"),
+        (0x10040, b"_BHRfS_M", "a btrfs filesystem"),
+        (4086, b"SWAPSPACE2", "a swap signature"),
+        (4086, b"SWAP-SPACE", "a swap signature"),
+        (536, b"LVM2 001", "an LVM physical volume"),
+        (0, b"LUKS\xba\xbe", "a LUKS header"),
+        (3, b"NTFS    ", "an NTFS filesystem"),
+        (0, &[0xfc, 0x4e, 0x2b, 0xa9], "an md RAID superblock"),
+        (4096, &[0xfc, 0x4e, 0x2b, 0xa9], "an md RAID superblock"),
+        (0x8001, b"CD001", "an ISO 9660 filesystem"),
+    ];
+    if let Some((_, _, name)) = head_signatures
+        .iter()
+        .find(|(offset, magic, _)| at(&head, *offset, magic))
+    {
+        return Ok(Some(name));
+    }
+    // Checked last: FAT boot sectors end in the same marker as an MBR
+    if at(&head, 510, &[0x55, 0xaa]) {
+        return Ok(Some("an MBR partition table or FAT filesystem"));
+    }
+
+    if size as usize > HEAD_BYTES {
+        let tail_len = TAIL_BYTES.min(size as usize - HEAD_BYTES);
+        let tail_start = size - tail_len as u64;
+        let mut tail = vec![0u8; tail_len];
+        file.seek(SeekFrom::Start(tail_start))?;
+        file.read_exact(&mut tail)?;
+        let offset_of = |position: u64| position.checked_sub(tail_start).map(|o| o as usize);
+
+        // Backup GPT header in the last logical block
+        for sector in [512, 4096] {
+            if let Some(offset) = size.checked_sub(sector).and_then(offset_of) {
+                if at(&tail, offset, b"EFI PART") {
+                    return Ok(Some("a GPT partition table"));
+                }
+            }
+        }
+        // md 0.90 (64 KiB aligned, last 64 KiB) and 1.0 (8 KiB from the end, 4 KiB aligned)
+        let md_positions = [
+            (size & !0xffff).checked_sub(0x10000),
+            size.checked_sub(8192).map(|p| p & !0xfff),
+        ];
+        for position in md_positions.into_iter().flatten() {
+            if let Some(offset) = offset_of(position) {
+                if at(&tail, offset, &[0xfc, 0x4e, 0x2b, 0xa9]) {
+                    return Ok(Some("an md RAID superblock"));
+                }
+            }
+        }
+    }
+    Ok(None)
+}
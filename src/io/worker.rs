@@ -1,11 +1,17 @@
-use crate::config::IoMode;
+use crate::config::{
+    AccessDistribution, BlockSizeSplit, FlushKind, FlushPolicy, IoMode, MemoryMode, OpKind, OpMix,
+    RateLimit, WalSettings,
+};
+use crate::io::buffer::BufferPool;
+use crate::io::histogram::LatencyHistogram;
 use crate::io::patterns::IoPattern;
+use crate::io::device::fallocate_mode;
 use crate::io::Device;
 use anyhow::Result;
-use io_uring::{opcode, types, IoUring};
-use std::os::unix::io::AsRawFd;
+use io_uring::{opcode, squeue, types, IoUring};
+use std::os::unix::io::{AsRawFd, RawFd};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Barrier};
 use std::time::{Duration, Instant};
 
 /// Statistics collected by a worker
@@ -14,10 +20,23 @@ pub struct WorkerStats {
     pub bytes_read: AtomicU64,
     pub bytes_written: AtomicU64,
     pub ops_completed: AtomicU64,
+    /// Completed reads; writes are ops_completed minus these
+    pub read_ops_completed: AtomicU64,
+    /// Discards, write-zeroes and secure erases, also counted in ops_completed
+    pub trim_ops_completed: AtomicU64,
+    pub bytes_trimmed: AtomicU64,
     pub ops_failed: AtomicU64,
     pub total_latency_ns: AtomicU64,
     pub min_latency_ns: AtomicU64,
     pub max_latency_ns: AtomicU64,
+    pub read_latency: LatencyHistogram,
+    pub write_latency: LatencyHistogram,
+    pub trim_latency: LatencyHistogram,
+    /// Completed fsyncs and fdatasyncs; not counted in ops_completed
+    pub flush_ops_completed: AtomicU64,
+    pub flush_latency: LatencyHistogram,
+    /// wal workload: first record of a group appended to its fdatasync completing
+    pub commit_latency: LatencyHistogram,
 }
 
 impl WorkerStats {
@@ -28,12 +47,48 @@ impl WorkerStats {
         }
     }
 
-    pub fn record_op(&self, bytes: usize, latency_ns: u64, is_read: bool) {
-        if is_read {
-            self.bytes_read.fetch_add(bytes as u64, Ordering::Relaxed);
-        } else {
-            self.bytes_written
-                .fetch_add(bytes as u64, Ordering::Relaxed);
+    /// Discard everything recorded so far (end of the ramp-up period)
+    pub fn reset(&self) {
+        self.bytes_read.store(0, Ordering::Relaxed);
+        self.bytes_written.store(0, Ordering::Relaxed);
+        self.ops_completed.store(0, Ordering::Relaxed);
+        self.read_ops_completed.store(0, Ordering::Relaxed);
+        self.trim_ops_completed.store(0, Ordering::Relaxed);
+        self.bytes_trimmed.store(0, Ordering::Relaxed);
+        self.ops_failed.store(0, Ordering::Relaxed);
+        self.total_latency_ns.store(0, Ordering::Relaxed);
+        self.min_latency_ns.store(u64::MAX, Ordering::Relaxed);
+        self.max_latency_ns.store(0, Ordering::Relaxed);
+        self.read_latency.reset();
+        self.write_latency.reset();
+        self.trim_latency.reset();
+        self.flush_ops_completed.store(0, Ordering::Relaxed);
+        self.flush_latency.reset();
+        self.commit_latency.reset();
+    }
+
+    pub fn record_flush(&self, latency_ns: u64) {
+        self.flush_ops_completed.fetch_add(1, Ordering::Relaxed);
+        self.flush_latency.record(latency_ns);
+    }
+
+    pub fn record_op(&self, bytes: usize, latency_ns: u64, op: OpKind) {
+        match op {
+            OpKind::Read => {
+                self.bytes_read.fetch_add(bytes as u64, Ordering::Relaxed);
+                self.read_ops_completed.fetch_add(1, Ordering::Relaxed);
+                self.read_latency.record(latency_ns);
+            }
+            OpKind::Write => {
+                self.bytes_written
+                    .fetch_add(bytes as u64, Ordering::Relaxed);
+                self.write_latency.record(latency_ns);
+            }
+            OpKind::Discard | OpKind::WriteZeroes | OpKind::SecureErase => {
+                self.bytes_trimmed.fetch_add(bytes as u64, Ordering::Relaxed);
+                self.trim_ops_completed.fetch_add(1, Ordering::Relaxed);
+                self.trim_latency.record(latency_ns);
+            }
         }
 
         self.ops_completed.fetch_add(1, Ordering::Relaxed);
@@ -69,23 +124,65 @@ impl WorkerStats {
     }
 }
 
+// Check the deadline once per this many loop iterations
+const ELAPSED_CHECK_INTERVAL: u64 = 1000;
+
+/// Bookkeeping for one in-flight operation
+#[derive(Debug, Clone, Copy)]
+struct InFlight {
+    /// Submission time, only captured for latency-sampled operations
+    start: Option<Instant>,
+    op: OpKind,
+    /// Bytes covered; fallocate completions don't report it
+    len: usize,
+    /// An fsync or fdatasync rather than `op`
+    flush: bool,
+    /// Append time of the oldest record a wal commit makes durable
+    commit: Option<Instant>,
+}
+
+impl Default for InFlight {
+    fn default() -> Self {
+        InFlight {
+            start: None,
+            op: OpKind::Read,
+            len: 0,
+            flush: false,
+            commit: None,
+        }
+    }
+}
+
 /// I/O worker thread with io_uring support
 pub struct IoWorker {
     device: Arc<Device>,
-    pattern: Arc<IoPattern>,
+    pattern: IoPattern,
     stats: Arc<WorkerStats>,
     stop_flag: Arc<AtomicBool>,
     block_size: usize,
     queue_depth: usize,
     read_percent: u8,
-    // Multiple aligned buffers for O_DIRECT I/O (one per queue depth for fixed buffers)
-    // Each buffer must be aligned to filesystem block size (typically 512 bytes)
-    buffers: Vec<Vec<u8>>,
-    buffer_ptrs: Vec<*mut libc::c_void>, // Track original pointers for cleanup
+    // Aligned buffers for O_DIRECT I/O (one per queue depth for fixed buffers)
+    buffers: BufferPool,
+    // Optional barrier so workers across targets start issuing I/O together
+    start_barrier: Option<Arc<Barrier>>,
+    rate: Option<RateLimit>,
+    // Warm-up period run before `duration` starts; its statistics are discarded
+    ramp_time: Duration,
+    flush: Option<FlushPolicy>,
+    // Chain each flush to the write that triggered it with IOSQE_IO_LINK
+    link_flush: bool,
+    // Group commits of the wal workload; replaces `flush`
+    wal: Option<WalSettings>,
 }
 
 impl IoWorker {
-    pub fn new(device: Arc<Device>, mode: IoMode, block_size: usize, queue_depth: usize) -> Self {
+    pub fn new(
+        device: Arc<Device>,
+        mode: IoMode,
+        block_size: usize,
+        queue_depth: usize,
+    ) -> Result<Self> {
         Self::new_with_read_percent(device, mode, block_size, queue_depth, 100)
     }
 
@@ -95,56 +192,51 @@ impl IoWorker {
         block_size: usize,
         queue_depth: usize,
         read_percent: u8,
-    ) -> Self {
+    ) -> Result<Self> {
+        Self::new_with_memory_mode(
+            device,
+            mode,
+            block_size,
+            queue_depth,
+            read_percent,
+            MemoryMode::Anon,
+        )
+    }
+
+    pub fn new_with_memory_mode(
+        device: Arc<Device>,
+        mode: IoMode,
+        block_size: usize,
+        queue_depth: usize,
+        read_percent: u8,
+        memory_mode: MemoryMode,
+    ) -> Result<Self> {
         let device_size = device.size();
 
-        // Allocate multiple properly aligned buffers for O_DIRECT I/O
-        // O_DIRECT requires:
-        // 1. Buffer aligned to filesystem block size (typically 512 bytes)
-        // 2. Buffer size must be multiple of block size
-        // Use posix_memalign for guaranteed alignment
         // CRITICAL: Need one buffer per queue depth for fixed buffers to work correctly!
-        let alignment = 512;
-        let total_size = ((block_size + alignment - 1) / alignment) * alignment; // Round up to alignment
-
-        let mut buffers = Vec::with_capacity(queue_depth);
-        let mut buffer_ptrs = Vec::with_capacity(queue_depth);
-
-        for _ in 0..queue_depth {
-            let buffer_ptr = unsafe {
-                let mut ptr: *mut libc::c_void = std::ptr::null_mut();
-                let result = libc::posix_memalign(&mut ptr, alignment, total_size);
-                if result != 0 || ptr.is_null() {
-                    // Fallback to Vec if posix_memalign fails
-                    let mut buf = Vec::with_capacity(total_size);
-                    unsafe {
-                        buf.set_len(block_size);
-                    }
-                    buffers.push(buf);
-                    buffer_ptrs.push(std::ptr::null_mut());
-                    continue;
-                }
-                ptr
-            };
-
-            // Create Vec from aligned memory
-            let buffer =
-                unsafe { Vec::from_raw_parts(buffer_ptr as *mut u8, block_size, total_size) };
-            buffers.push(buffer);
-            buffer_ptrs.push(buffer_ptr);
-        }
+        let buffers = BufferPool::new(queue_depth, block_size, memory_mode)?;
 
-        Self {
+        Ok(Self {
             device,
-            pattern: Arc::new(IoPattern::new(mode, block_size, device_size)),
+            pattern: IoPattern::new(mode, block_size, device_size),
             stats: Arc::new(WorkerStats::new()),
             stop_flag: Arc::new(AtomicBool::new(false)),
             block_size,
             queue_depth,
             read_percent,
             buffers,
-            buffer_ptrs,
-        }
+            start_barrier: None,
+            rate: None,
+            ramp_time: Duration::ZERO,
+            flush: None,
+            link_flush: false,
+            wal: None,
+        })
+    }
+
+    /// Memory mode actually backing this worker's buffers
+    pub fn memory_mode(&self) -> MemoryMode {
+        self.buffers.mode()
     }
 
     pub fn stats(&self) -> Arc<WorkerStats> {
@@ -156,6 +248,69 @@ impl IoWorker {
         self.stats = stats;
     }
 
+    /// Wait on a shared barrier after ring setup, right before the first submission
+    pub fn set_start_barrier(&mut self, barrier: Arc<Barrier>) {
+        self.start_barrier = Some(barrier);
+    }
+
+    /// Cap the rate at which this worker issues I/O
+    pub fn set_rate_limit(&mut self, rate: Option<RateLimit>) {
+        self.rate = rate;
+    }
+
+    /// Issue operations with sizes drawn from a weighted mix, growing the buffers if needed
+    pub fn set_block_size_split(&mut self, split: &BlockSizeSplit) -> Result<()> {
+        if split.max_block_size() > self.buffers.buffer_len() {
+            self.buffers =
+                BufferPool::new(self.queue_depth, split.max_block_size(), self.buffers.mode())?;
+        }
+        self.pattern.set_block_size_split(split);
+        Ok(())
+    }
+
+    /// Spread random offsets according to the given distribution
+    pub fn set_distribution(&mut self, distribution: AccessDistribution) {
+        self.pattern.set_distribution(distribution);
+    }
+
+    /// Issue operation types drawn from a weighted mix that may include trims
+    pub fn set_op_mix(&mut self, mix: &OpMix) -> Result<()> {
+        if !self.device.is_block_device() {
+            if let Some((op, _)) = mix.entries().iter().find(|(op, _)| {
+                op.is_trim() && fallocate_mode(*op).is_none()
+            }) {
+                return Err(anyhow::anyhow!(
+                    "{} needs a block device: {}",
+                    op,
+                    self.device.path().display()
+                ));
+            }
+        }
+        self.pattern.set_op_mix(mix);
+        Ok(())
+    }
+
+    /// Issue an fsync or fdatasync after every N writes, optionally linked to the last one
+    pub fn set_flush(&mut self, flush: Option<FlushPolicy>, linked: bool) {
+        self.flush = flush;
+        self.link_flush = linked;
+    }
+
+    /// Append records as a write-ahead log, committing groups of them with fdatasync
+    pub fn set_wal(&mut self, wal: Option<WalSettings>) {
+        self.wal = wal;
+    }
+
+    /// Run for this long before `duration` starts, discarding the statistics
+    pub fn set_ramp_time(&mut self, ramp_time: Duration) {
+        self.ramp_time = ramp_time;
+    }
+
+    /// Replace the internal stop flag with one shared by every worker of a run
+    pub fn set_stop_flag(&mut self, stop_flag: Arc<AtomicBool>) {
+        self.stop_flag = stop_flag;
+    }
+
     pub fn stop_flag(&self) -> Arc<AtomicBool> {
         Arc::clone(&self.stop_flag)
     }
@@ -167,27 +322,28 @@ impl IoWorker {
     /// Run the worker with io_uring (blocking)
     pub fn run(&mut self, duration: Duration) -> Result<()> {
         let fd = self.device.as_raw_fd();
-        let mut ring = IoUring::new(self.queue_depth as u32)?;
+        let ring = IoUring::new(self.queue_depth as u32);
+        if let Some(barrier) = &self.start_barrier {
+            // Ring setup failures must not leave the other workers stuck at the barrier
+            if ring.is_err() {
+                barrier.wait();
+            }
+        }
+        let mut ring = ring?;
 
         // OPTIMIZATION: Register multiple buffers with kernel (IORING_REGISTER_BUFFERS)
         // CRITICAL FIX: Register one buffer per queue depth to eliminate DMA mapping overhead!
         // Each operation gets its own buffer, so kernel doesn't need to map/unmap per operation
-        let buffer_iovecs: Vec<libc::iovec> = self
-            .buffers
-            .iter()
-            .map(|buf| libc::iovec {
-                iov_base: buf.as_ptr() as *mut libc::c_void,
-                iov_len: buf.len(),
-            })
-            .collect();
+        let buffer_iovecs = self.buffers.iovecs();
 
         let use_fixed_buffers =
             unsafe { ring.submitter().register_buffers(&buffer_iovecs).is_ok() };
 
         if use_fixed_buffers {
             eprintln!(
-                "Successfully registered {} fixed buffers",
-                self.buffers.len()
+                "Successfully registered {} fixed buffers ({} memory)",
+                self.buffers.count(),
+                self.buffers.mode()
             );
         } else {
             eprintln!("Warning: Fixed buffers registration failed, falling back to standard I/O");
@@ -212,27 +368,39 @@ impl IoWorker {
             );
         }
 
-        // Track which buffer index to use next (round-robin assignment)
-        let mut next_buf_index = 0usize;
-
         // CRITICAL OPTIMIZATION: Fast path for sequential reads (100% reads)
         // Avoid Mutex locks and function call overhead in hot path
         let is_sequential_reads = self.read_percent == 100
-            && matches!(self.pattern.mode(), crate::config::IoMode::Sequential);
+            && matches!(self.pattern.mode(), crate::config::IoMode::Sequential)
+            && self.pattern.max_block_size() == self.block_size;
         let block_size_u64 = self.block_size as u64;
         let device_size = self.pattern.device_size();
-        let buffers_len = self.buffers.len();
+        let use_fixed = use_fixed_buffers && use_fixed_files;
+
+        // Every in-flight operation owns one queue slot: its buffer and its bookkeeping.
+        // The slot index travels in user_data so completions can arrive in any order.
+        let mut slots = vec![InFlight::default(); self.queue_depth];
+        let mut free_slots: Vec<usize> = (0..self.queue_depth).rev().collect();
+
+        if let Some(barrier) = self.start_barrier.take() {
+            barrier.wait();
+        }
 
         let start = Instant::now();
-        let deadline = start + duration; // Calculate deadline once to avoid repeated elapsed() calls
+        let deadline = start + self.ramp_time + duration; // Calculate deadline once to avoid repeated elapsed() calls
+        let mut ramping = !self.ramp_time.is_zero();
         let mut offset = 0u64;
+        let mut len = self.block_size;
         let mut pending_ops = 0usize; // Operations in-flight (submitted to kernel)
         let mut queued_ops = 0usize; // Operations queued but not yet submitted
-                                     // Use circular buffer for timestamps (pre-allocated, no reallocation)
-        let timestamp_capacity = self.queue_depth * 2;
-        let mut op_timestamps_circular: Vec<(Instant, bool)> =
-            vec![(Instant::now(), true); timestamp_capacity];
-        let mut timestamp_head = 0usize;
+        let mut writes_since_flush = 0u32;
+        let mut flush_due: Option<FlushKind> = None;
+        // wal group commit: the open group (oldest append, bytes), a group sealed until
+        // its records complete, and whether its fdatasync is in flight
+        let mut wal_group: Option<(Instant, u64)> = None;
+        let mut wal_sealed: Option<Instant> = None;
+        let mut wal_commit: Option<Instant> = None;
+        let mut committing = false;
 
         // OPTIMIZATION: Latency sampling - track only 1% of operations to reduce overhead
         let latency_sample_rate = 100; // Track 1 in 100 operations
@@ -240,351 +408,199 @@ impl IoWorker {
 
         // CRITICAL: Cache elapsed time check to avoid clock_gettime overhead (30%!)
         // Only check time every N iterations instead of every iteration
-        let mut elapsed_check_counter = 0u64;
-        const ELAPSED_CHECK_INTERVAL: u64 = 1000; // Check every 1000 iterations
-
-        // Pre-calculate power-of-2 checks to avoid repeated calls in hot path
-        let timestamp_capacity_is_pow2 = timestamp_capacity.is_power_of_two();
-        let buffers_len_is_pow2 = buffers_len.is_power_of_two();
-        let timestamp_mask = timestamp_capacity - 1;
-        let buffers_mask = buffers_len - 1;
-
-        // Initial fill: submit up to queue depth
-        let init_time = Instant::now();
-        while (pending_ops + queued_ops) < self.queue_depth && Instant::now() < deadline {
-            // Use fast path for sequential reads
-            let is_read = if is_sequential_reads {
-                true
-            } else {
-                self.pattern.is_read(self.read_percent)
-            };
-            offset = if is_sequential_reads {
-                let next = offset + block_size_u64;
-                if next >= device_size {
-                    0
-                } else {
-                    next
-                }
-            } else {
-                self.pattern.next_offset(offset)
-            };
-
-            // Store in circular buffer only if needed (for latency tracking or mixed reads/writes)
-            if !is_sequential_reads
-                || ((pending_ops + queued_ops) % latency_sample_rate as usize == 0)
-            {
-                op_timestamps_circular[pending_ops + queued_ops] = (init_time, is_read);
-            }
+        let mut elapsed_check_counter = ELAPSED_CHECK_INTERVAL;
 
-            // OPTIMIZATION: Use ReadFixed/WriteFixed with registered buffers and files
-            // Use round-robin buffer assignment - each operation gets its own buffer
-            let buf_index = if use_fixed_buffers {
-                let idx = next_buf_index % self.buffers.len();
-                next_buf_index = (next_buf_index + 1) % self.buffers.len();
-                idx as u16
-            } else {
-                0u16 // Not used if fixed buffers not available
-            };
-
-            if is_read {
-                let read_e = if use_fixed_buffers && use_fixed_files {
-                    opcode::ReadFixed::new(
-                        types::Fixed(0),
-                        self.buffers[buf_index as usize].as_mut_ptr() as *mut _,
-                        self.buffers[buf_index as usize].len() as u32,
-                        buf_index,
-                    )
-                    .offset(offset)
-                    .build()
-                } else {
-                    opcode::Read::new(
-                        types::Fd(fd),
-                        self.buffers[0].as_mut_ptr() as *mut _,
-                        self.buffers[0].len() as u32,
-                    )
-                    .offset(offset)
-                    .build()
-                };
-
-                unsafe {
-                    ring.submission()
-                        .push(&read_e)
-                        .map_err(|_| anyhow::anyhow!("Failed to push read operation"))?;
-                }
-            } else {
-                let write_e = if use_fixed_buffers && use_fixed_files {
-                    opcode::WriteFixed::new(
-                        types::Fixed(0),
-                        self.buffers[buf_index as usize].as_ptr(),
-                        self.buffers[buf_index as usize].len() as u32,
-                        buf_index,
-                    )
-                    .offset(offset)
-                    .build()
-                } else {
-                    opcode::Write::new(
-                        types::Fd(fd),
-                        self.buffers[0].as_ptr(),
-                        self.buffers[0].len() as u32,
-                    )
-                    .offset(offset)
-                    .build()
-                };
-
-                unsafe {
-                    ring.submission()
-                        .push(&write_e)
-                        .map_err(|_| anyhow::anyhow!("Failed to push write operation"))?;
-                }
-            }
-
-            queued_ops += 1;
-        }
-        // Submit initial batch
-        ring.submit()?;
-        pending_ops += queued_ops;
-        queued_ops = 0;
+        // Rate limiting: never issue more than rate * elapsed operations.
+        // Only rate-limited workers pay for the extra clock reads.
+        let rate_ops_per_sec = self
+            .rate
+            .map(|r| r.ops_per_sec(self.pattern.mean_block_size() as usize));
+        let mut issued_ops = 0u64;
 
         // Main loop: keep queue full at all times (like fio does)
-        // Continue using the circular buffer initialized above
-        // CRITICAL FIX: Cache elapsed check to avoid 30% clock_gettime overhead!
         loop {
             // Check elapsed time only occasionally (every N iterations) to avoid overhead
             elapsed_check_counter += 1;
-            if elapsed_check_counter >= ELAPSED_CHECK_INTERVAL {
+            if elapsed_check_counter >= ELAPSED_CHECK_INTERVAL || rate_ops_per_sec.is_some() {
                 elapsed_check_counter = 0;
-                if self.stop_flag.load(Ordering::Relaxed) || Instant::now() >= deadline {
+                let now = Instant::now();
+                if self.stop_flag.load(Ordering::Relaxed) || now >= deadline {
                     break;
                 }
+                if ramping && now >= start + self.ramp_time {
+                    ramping = false;
+                    self.stats.reset();
+                }
             }
 
             // Process completions first (non-blocking) - process ALL available
-            let cq = ring.completion();
-            let mut completed_count = 0;
-            // CRITICAL OPTIMIZATION: Batch stats updates to reduce atomic operation overhead
-            // Accumulate stats locally, then update atomics once per batch
-            let mut batch_bytes_read = 0u64;
-            let mut batch_bytes_written = 0u64;
-            let mut batch_ops = 0u64;
-            let mut batch_failed = 0u64;
-
-            for cqe in cq {
-                if cqe.result() >= 0 {
-                    let bytes = cqe.result() as usize;
-                    // OPTIMIZATION: Sample latency tracking (only 1% of operations)
-                    // This reduces overhead significantly while still providing useful metrics
-                    let track_latency = (op_counter % latency_sample_rate) == 0;
-                    op_counter += 1;
-
-                    if track_latency {
-                        // Only call clock_gettime when we actually need it (1% of ops)
-                        let now = Instant::now();
-                        let idx = if timestamp_capacity_is_pow2 {
-                            (timestamp_head + completed_count) & timestamp_mask
-                        } else {
-                            (timestamp_head + completed_count) % timestamp_capacity
-                        };
-                        let (op_start, is_read) = op_timestamps_circular[idx];
-                        let latency_ns = now.duration_since(op_start).as_nanos() as u64;
-                        self.stats.record_op(bytes, latency_ns, is_read);
-                        // Also count in batch for ops_completed
-                        batch_ops += 1;
-                    } else {
-                        // CRITICAL OPTIMIZATION: Fast path - skip circular buffer lookup!
-                        // For sequential reads (100% reads), we know is_read is always true
-                        if is_sequential_reads {
-                            batch_bytes_read += bytes as u64;
-                        } else {
-                            // Only lookup when we have mixed reads/writes
-                            let idx = if timestamp_capacity_is_pow2 {
-                                (timestamp_head + completed_count) & timestamp_mask
-                            } else {
-                                (timestamp_head + completed_count) % timestamp_capacity
-                            };
-                            let is_read = op_timestamps_circular
-                                .get(idx)
-                                .map(|(_, r)| *r)
-                                .unwrap_or(true);
-                            if is_read {
-                                batch_bytes_read += bytes as u64;
-                            } else {
-                                batch_bytes_written += bytes as u64;
-                            }
-                        }
-                        batch_ops += 1;
+            let flushed =
+                self.reap_completions(&mut ring, &mut slots, &mut free_slots, &mut pending_ops);
+
+            if let Some(wal) = self.wal {
+                committing &= flushed == 0;
+                if wal_sealed.is_some() {
+                    // Only the sealed group's records can be in flight; commit once they land
+                    if pending_ops + queued_ops == 0 {
+                        wal_commit = wal_sealed.take();
+                        flush_due = Some(FlushKind::Fdatasync);
+                        committing = true;
+                    }
+                } else if let Some((oldest, bytes)) = wal_group {
+                    // Records keep joining the open group while the previous commit runs
+                    if !committing
+                        && (bytes >= wal.commit_bytes || oldest.elapsed() >= wal.commit_interval)
+                    {
+                        wal_sealed = Some(oldest);
+                        wal_group = None;
                     }
-                } else {
-                    batch_failed += 1;
                 }
-                completed_count += 1;
-                pending_ops -= 1;
-            }
-
-            // Update atomics once per batch (much faster than per-operation updates)
-            if batch_bytes_read > 0 {
-                self.stats
-                    .bytes_read
-                    .fetch_add(batch_bytes_read, Ordering::Relaxed);
-            }
-            if batch_bytes_written > 0 {
-                self.stats
-                    .bytes_written
-                    .fetch_add(batch_bytes_written, Ordering::Relaxed);
-            }
-            if batch_ops > 0 {
-                self.stats
-                    .ops_completed
-                    .fetch_add(batch_ops, Ordering::Relaxed);
-            }
-            if batch_failed > 0 {
-                self.stats
-                    .ops_failed
-                    .fetch_add(batch_failed, Ordering::Relaxed);
-            }
-
-            // Update circular buffer head
-            if completed_count > 0 {
-                timestamp_head = (timestamp_head + completed_count) % timestamp_capacity;
             }
 
             // CRITICAL: Immediately refill queue to keep it FULL at all times!
-            // Perf shows 52% time in schedule/blocking - we MUST keep queue full
             // Fill submission queue (but don't submit immediately - batch submissions)
-            // Only get batch_start_time when we need it (for latency tracking - 1% of ops)
-            // Check if next operation will need latency tracking
-            let need_batch_time = (op_counter % latency_sample_rate) == 0;
-            let batch_start_time = if need_batch_time {
-                Instant::now()
-            } else {
-                start // Dummy value, won't be used in fast path
-            };
+            let mut throttled = false;
+            let mut ran_sync = false;
+            while let Some(slot) = free_slots.pop() {
+                // A flush that found no free slot when it came due goes first
+                if let Some(kind) = flush_due.take() {
+                    slots[slot] = InFlight {
+                        start: Some(Instant::now()),
+                        flush: true,
+                        commit: wal_commit.take(),
+                        ..Default::default()
+                    };
+                    let entry = Self::flush_entry(slot, kind, fd, use_fixed);
+                    unsafe {
+                        ring.submission()
+                            .push(&entry)
+                            .map_err(|_| anyhow::anyhow!("Failed to push I/O operation"))?;
+                    }
+                    queued_ops += 1;
+                    continue;
+                }
+                if wal_sealed.is_some() {
+                    free_slots.push(slot);
+                    break;
+                }
 
-            // Fill submission queue until we have enough in-flight + queued operations
-            while (pending_ops + queued_ops) < self.queue_depth {
-                // Skip deadline check in inner loop - already checked in outer loop
+                if let Some(rate) = rate_ops_per_sec {
+                    if issued_ops > (rate * start.elapsed().as_secs_f64()) as u64 {
+                        free_slots.push(slot);
+                        throttled = true;
+                        break;
+                    }
+                    issued_ops += 1;
+                }
 
                 // CRITICAL OPTIMIZATION: Fast path for sequential reads
                 // Avoid Mutex locks and function calls in hot path
-                let is_read = if is_sequential_reads {
-                    true // Always read for 100% reads
+                let op = if is_sequential_reads {
+                    OpKind::Read // Always read for 100% reads
                 } else {
-                    self.pattern.is_read(self.read_percent)
+                    self.pattern.next_op(self.read_percent)
                 };
 
                 // Inline sequential offset calculation to avoid function call overhead
+                let previous_len = len;
+                len = self.pattern.next_block_size();
                 offset = if is_sequential_reads {
                     let next = offset + block_size_u64;
-                    if next >= device_size {
+                    if next + block_size_u64 > device_size {
                         0
                     } else {
                         next
                     }
                 } else {
-                    self.pattern.next_offset(offset)
+                    self.pattern.next_offset(offset, previous_len, len)
                 };
 
-                // CRITICAL OPTIMIZATION: Only store timestamps when we need them (1% of ops)
-                // This eliminates 99% of circular buffer writes for sequential reads
-                if need_batch_time {
-                    let idx = if timestamp_capacity_is_pow2 {
-                        (timestamp_head + pending_ops) & timestamp_mask
-                    } else {
-                        (timestamp_head + pending_ops) % timestamp_capacity
-                    };
-                    op_timestamps_circular[idx] = (batch_start_time, is_read);
-                } else if !is_sequential_reads {
-                    // For mixed reads/writes, we still need to track is_read for stats
-                    // But we can skip the timestamp (we don't need it for non-latency tracking)
-                    let idx = if timestamp_capacity_is_pow2 {
-                        (timestamp_head + pending_ops) & timestamp_mask
-                    } else {
-                        (timestamp_head + pending_ops) % timestamp_capacity
-                    };
-                    // Only store is_read flag, use dummy timestamp
-                    op_timestamps_circular[idx] = (start, is_read);
+                // The trim ioctls block until the device is done, so they run
+                // here and the slot is free again right away
+                if op.is_trim() && self.device.is_block_device() {
+                    free_slots.push(slot);
+                    let issued = Instant::now();
+                    match self.device.trim(op, offset, len as u64) {
+                        Ok(()) => {
+                            let latency_ns = issued.elapsed().as_nanos() as u64;
+                            self.stats.record_op(len, latency_ns, op);
+                        }
+                        Err(_) => {
+                            self.stats.ops_failed.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                    // Let the outer loop check the deadline after every blocking call
+                    elapsed_check_counter = ELAPSED_CHECK_INTERVAL;
+                    ran_sync = true;
+                    break;
                 }
-                // For sequential reads without latency tracking: skip circular buffer entirely!
-
-                // OPTIMIZATION: Use ReadFixed/WriteFixed with registered buffers and files
-                // Use round-robin buffer assignment - each operation gets its own buffer
-                // Use pre-calculated mask for power-of-2 optimization
-                let buf_index = if use_fixed_buffers {
-                    let idx = if buffers_len_is_pow2 {
-                        next_buf_index & buffers_mask
-                    } else {
-                        next_buf_index % buffers_len
-                    };
-                    next_buf_index = if buffers_len_is_pow2 {
-                        (next_buf_index + 1) & buffers_mask
-                    } else {
-                        (next_buf_index + 1) % buffers_len
-                    };
-                    idx as u16
-                } else {
-                    0u16 // Not used if fixed buffers not available
-                };
 
-                if is_read {
-                    let read_e = if use_fixed_buffers && use_fixed_files {
-                        opcode::ReadFixed::new(
-                            types::Fixed(0),
-                            self.buffers[buf_index as usize].as_mut_ptr() as *mut _,
-                            self.buffers[buf_index as usize].len() as u32,
-                            buf_index,
-                        )
-                        .offset(offset)
-                        .build()
-                    } else {
-                        opcode::Read::new(
-                            types::Fd(fd),
-                            self.buffers[0].as_mut_ptr() as *mut _,
-                            self.buffers[0].len() as u32,
-                        )
-                        .offset(offset)
-                        .build()
-                    };
+                // Only call clock_gettime for the sampled 1% of operations
+                let sampled = op_counter.is_multiple_of(latency_sample_rate);
+                op_counter += 1;
+                slots[slot] = InFlight {
+                    start: if sampled { Some(Instant::now()) } else { None },
+                    op,
+                    len,
+                    flush: false,
+                    commit: None,
+                };
 
-                    unsafe {
-                        ring.submission()
-                            .push(&read_e)
-                            .map_err(|_| anyhow::anyhow!("Failed to push read operation"))?;
+                let mut entry = self.build_entry(slot, op, offset, len, fd, use_fixed);
+                let mut linked_flush = None;
+                if let (OpKind::Write, Some(flush)) = (op, self.flush) {
+                    writes_since_flush += 1;
+                    if writes_since_flush >= flush.writes {
+                        writes_since_flush = 0;
+                        match free_slots.pop() {
+                            Some(flush_slot) if self.link_flush => {
+                                entry = entry.flags(squeue::Flags::IO_LINK);
+                                linked_flush = Some((flush_slot, flush.kind));
+                            }
+                            Some(flush_slot) => {
+                                free_slots.push(flush_slot);
+                                flush_due = Some(flush.kind);
+                            }
+                            None => flush_due = Some(flush.kind),
+                        }
                     }
-                } else {
-                    let write_e = if use_fixed_buffers && use_fixed_files {
-                        opcode::WriteFixed::new(
-                            types::Fixed(0),
-                            self.buffers[buf_index as usize].as_ptr(),
-                            self.buffers[buf_index as usize].len() as u32,
-                            buf_index,
-                        )
-                        .offset(offset)
-                        .build()
-                    } else {
-                        opcode::Write::new(
-                            types::Fd(fd),
-                            self.buffers[0].as_ptr(),
-                            self.buffers[0].len() as u32,
-                        )
-                        .offset(offset)
-                        .build()
-                    };
+                }
+                unsafe {
+                    ring.submission()
+                        .push(&entry)
+                        .map_err(|_| anyhow::anyhow!("Failed to push I/O operation"))?;
+                }
+                queued_ops += 1;
 
+                if let Some((flush_slot, kind)) = linked_flush {
+                    // Timed from submission, so it includes the write it waits for
+                    slots[flush_slot] = InFlight {
+                        start: Some(Instant::now()),
+                        flush: true,
+                        ..Default::default()
+                    };
+                    let entry = Self::flush_entry(flush_slot, kind, fd, use_fixed);
                     unsafe {
                         ring.submission()
-                            .push(&write_e)
-                            .map_err(|_| anyhow::anyhow!("Failed to push write operation"))?;
+                            .push(&entry)
+                            .map_err(|_| anyhow::anyhow!("Failed to push I/O operation"))?;
                     }
+                    queued_ops += 1;
                 }
 
-                queued_ops += 1;
+                if self.wal.is_some() {
+                    let group = wal_group.get_or_insert_with(|| (Instant::now(), 0));
+                    group.1 += len as u64;
+                }
             }
 
             // CRITICAL OPTIMIZATION: Batch submissions to reduce syscall overhead!
-            // Perf shows 40% syscall overhead - we're submitting too frequently
             // Strategy: Only submit when we have a significant batch (>= 8 ops) OR queue is getting full
-            // This reduces syscall frequency from every iteration to every 8+ operations
             let should_submit = queued_ops >= 8 || // Significant batch ready
-                               (pending_ops + queued_ops) >= self.queue_depth; // Queue full
+                               (pending_ops + queued_ops) >= self.queue_depth || // Queue full
+                               throttled || // Rate limit reached, flush what we have
+                               ran_sync || // Left the refill loop early for a blocking trim
+                               wal_sealed.is_some(); // Group sealed, its records must land
 
             if should_submit && queued_ops > 0 {
                 ring.submit()?;
@@ -593,41 +609,208 @@ impl IoWorker {
             }
 
             // CRITICAL FIX: Minimize blocking!
-            // Perf shows 52% time in schedule/blocking - we MUST avoid waiting
             // Strategy: Only wait when queue is critically low (< 8)
             // If queue is full, just continue loop - don't wait!
-            if pending_ops < 8 && pending_ops > 0 {
+            if throttled {
+                // Over the rate limit: block on a completion, or sleep until the next slot
+                if pending_ops > 0 {
+                    ring.submit_and_wait(1)?;
+                } else if let Some(rate) = rate_ops_per_sec {
+                    let next_slot = Duration::from_secs_f64(issued_ops as f64 / rate);
+                    let wait = next_slot.saturating_sub(start.elapsed());
+                    std::thread::sleep(wait.min(Duration::from_millis(10)));
+                }
+            } else if pending_ops < 8 && pending_ops > 0 {
                 // Queue is critically low, must wait for completions
                 ring.submit_and_wait(1)?;
             }
             // Otherwise: don't wait! Continue loop to check for completions non-blocking
-            // This keeps CPU busy and avoids blocking/sleeping
         }
 
-        // Wait for remaining operations
-        let final_time = Instant::now();
+        // Submit anything still queued, then wait for remaining operations
+        if queued_ops > 0 {
+            ring.submit()?;
+            pending_ops += queued_ops;
+        }
         while pending_ops > 0 {
             ring.submit_and_wait(1)?;
-            let cq = ring.completion();
-            let mut completed_count = 0;
-            for cqe in cq {
-                if cqe.result() >= 0 {
-                    let bytes = cqe.result() as usize;
-                    let idx = (timestamp_head + completed_count) % timestamp_capacity;
-                    let (op_start, is_read) = op_timestamps_circular[idx];
-                    let latency_ns = final_time.duration_since(op_start).as_nanos() as u64;
-                    self.stats.record_op(bytes, latency_ns, is_read);
-                } else {
-                    self.stats.ops_failed.fetch_add(1, Ordering::Relaxed);
+            self.reap_completions(&mut ring, &mut slots, &mut free_slots, &mut pending_ops);
+        }
+
+        Ok(())
+    }
+
+    /// Build the SQE for one operation using the slot's own buffer
+    fn flush_entry(slot: usize, kind: FlushKind, fd: RawFd, use_fixed: bool) -> squeue::Entry {
+        let flags = match kind {
+            FlushKind::Fsync => types::FsyncFlags::empty(),
+            FlushKind::Fdatasync => types::FsyncFlags::DATASYNC,
+        };
+        let entry = if use_fixed {
+            opcode::Fsync::new(types::Fixed(0)).flags(flags).build()
+        } else {
+            opcode::Fsync::new(types::Fd(fd)).flags(flags).build()
+        };
+        entry.user_data(slot as u64)
+    }
+
+    fn build_entry(
+        &self,
+        slot: usize,
+        op: OpKind,
+        offset: u64,
+        len: usize,
+        fd: RawFd,
+        use_fixed: bool,
+    ) -> squeue::Entry {
+        // Trims on regular files punch or zero ranges with fallocate
+        if let Some(mode) = fallocate_mode(op) {
+            let entry = if use_fixed {
+                opcode::Fallocate::new(types::Fixed(0), len as u64)
+                    .offset(offset)
+                    .mode(mode)
+                    .build()
+            } else {
+                opcode::Fallocate::new(types::Fd(fd), len as u64)
+                    .offset(offset)
+                    .mode(mode)
+                    .build()
+            };
+            return entry.user_data(slot as u64);
+        }
+
+        let buf = self.buffers.ptr(slot);
+        let len = len as u32;
+        let is_read = op == OpKind::Read;
+
+        // OPTIMIZATION: Use ReadFixed/WriteFixed with registered buffers and files
+        let entry = match (is_read, use_fixed) {
+            (true, true) => opcode::ReadFixed::new(types::Fixed(0), buf, len, slot as u16)
+                .offset(offset)
+                .build(),
+            (true, false) => opcode::Read::new(types::Fd(fd), buf, len)
+                .offset(offset)
+                .build(),
+            (false, true) => opcode::WriteFixed::new(types::Fixed(0), buf, len, slot as u16)
+                .offset(offset)
+                .build(),
+            (false, false) => opcode::Write::new(types::Fd(fd), buf, len)
+                .offset(offset)
+                .build(),
+        };
+        entry.user_data(slot as u64)
+    }
+
+    /// Drain the completion queue, releasing slots and updating stats
+    fn reap_completions(
+        &self,
+        ring: &mut IoUring,
+        slots: &mut [InFlight],
+        free_slots: &mut Vec<usize>,
+        pending_ops: &mut usize,
+    ) -> usize {
+        // CRITICAL OPTIMIZATION: Batch stats updates to reduce atomic operation overhead
+        // Accumulate stats locally, then update atomics once per batch
+        let mut batch_bytes_read = 0u64;
+        let mut batch_bytes_written = 0u64;
+        let mut batch_ops = 0u64;
+        let mut batch_read_ops = 0u64;
+        let mut batch_bytes_trimmed = 0u64;
+        let mut batch_trim_ops = 0u64;
+        let mut batch_failed = 0u64;
+        let mut flushes = 0;
+        let mut now = None;
+
+        for cqe in ring.completion() {
+            let slot = cqe.user_data() as usize;
+            let op = slots[slot];
+            free_slots.push(slot);
+            *pending_ops -= 1;
+
+            if op.flush {
+                // Flushes are rare enough to time every one
+                flushes += 1;
+                if cqe.result() < 0 {
+                    batch_failed += 1;
+                } else if let Some(op_start) = op.start {
+                    let now = *now.get_or_insert_with(Instant::now);
+                    self.stats
+                        .record_flush(now.duration_since(op_start).as_nanos() as u64);
+                    if let Some(oldest) = op.commit {
+                        self.stats
+                            .commit_latency
+                            .record(now.duration_since(oldest).as_nanos() as u64);
+                    }
                 }
-                completed_count += 1;
-                pending_ops -= 1;
+                continue;
             }
-            if completed_count > 0 {
-                timestamp_head = (timestamp_head + completed_count) % timestamp_capacity;
+            if cqe.result() < 0 {
+                batch_failed += 1;
+                continue;
+            }
+            let bytes = if op.op.is_trim() {
+                op.len as u64
+            } else {
+                cqe.result() as u64
+            };
+            match op.start {
+                Some(op_start) => {
+                    // One clock read covers every sampled completion in this batch
+                    let now = *now.get_or_insert_with(Instant::now);
+                    let latency_ns = now.duration_since(op_start).as_nanos() as u64;
+                    self.stats.record_op(bytes as usize, latency_ns, op.op);
+                }
+                None => {
+                    match op.op {
+                        OpKind::Read => {
+                            batch_bytes_read += bytes;
+                            batch_read_ops += 1;
+                        }
+                        OpKind::Write => batch_bytes_written += bytes,
+                        OpKind::Discard | OpKind::WriteZeroes | OpKind::SecureErase => {
+                            batch_bytes_trimmed += bytes;
+                            batch_trim_ops += 1;
+                        }
+                    }
+                    batch_ops += 1;
+                }
             }
         }
 
-        Ok(())
+        // Update atomics once per batch (much faster than per-operation updates)
+        if batch_bytes_read > 0 {
+            self.stats
+                .bytes_read
+                .fetch_add(batch_bytes_read, Ordering::Relaxed);
+        }
+        if batch_bytes_written > 0 {
+            self.stats
+                .bytes_written
+                .fetch_add(batch_bytes_written, Ordering::Relaxed);
+        }
+        if batch_ops > 0 {
+            self.stats
+                .ops_completed
+                .fetch_add(batch_ops, Ordering::Relaxed);
+        }
+        if batch_read_ops > 0 {
+            self.stats
+                .read_ops_completed
+                .fetch_add(batch_read_ops, Ordering::Relaxed);
+        }
+        if batch_trim_ops > 0 {
+            self.stats
+                .bytes_trimmed
+                .fetch_add(batch_bytes_trimmed, Ordering::Relaxed);
+            self.stats
+                .trim_ops_completed
+                .fetch_add(batch_trim_ops, Ordering::Relaxed);
+        }
+        if batch_failed > 0 {
+            self.stats
+                .ops_failed
+                .fetch_add(batch_failed, Ordering::Relaxed);
+        }
+        flushes
     }
 }
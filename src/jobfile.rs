@@ -0,0 +1,728 @@
+use crate::config::{self, Config, FlushKind, FlushPolicy, JobSpec, Workload};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Declarative benchmark definition loaded from TOML or JSON:
+///
+/// ```toml
+/// [global]
+/// device = "/dev/nvme0n1"
+/// duration = "30s"
+///
+/// [[job]]
+/// name = "reader"
+/// workload = "randread"
+/// bs = "4k"
+/// group = "mixed"     # jobs sharing a group run in parallel
+///
+/// [[job]]
+/// name = "writer"
+/// workload = "seqwrite"
+/// bs = "1m"
+/// group = "mixed"
+/// ```
+///
+/// Groups run one after another in file order; a job without a group runs on its own.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobFile {
+    #[serde(default)]
+    global: Section,
+    #[serde(default, rename = "job")]
+    jobs: Vec<Section>,
+}
+
+/// One group of jobs that run concurrently
+#[derive(Debug, Clone)]
+pub struct JobGroup {
+    pub name: String,
+    pub config: Config,
+}
+
+/// Keys accepted in [global] and [[job]] sections
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Section {
+    name: Option<String>,
+    group: Option<String>,
+    // Per-job keys
+    workload: Option<String>,
+    #[serde(alias = "bs")]
+    block_size: Option<Value>,
+    #[serde(alias = "qd", alias = "iodepth")]
+    queue_depth: Option<usize>,
+    #[serde(alias = "numjobs")]
+    threads: Option<usize>,
+    rate: Option<Value>,
+    #[serde(alias = "read_percent")]
+    rwmixread: Option<u8>,
+    bssplit: Option<String>,
+    #[serde(alias = "random_distribution")]
+    distribution: Option<String>,
+    #[serde(alias = "opmix")]
+    op_mix: Option<String>,
+    /// Flush after every N writes, as fio's fsync= and fdatasync=
+    fsync: Option<u32>,
+    fdatasync: Option<u32>,
+    // Run-wide keys; in a job section they apply to the job's whole group
+    #[serde(alias = "devices")]
+    device: Option<OneOrMany>,
+    duration: Option<Value>,
+    ramp_time: Option<Value>,
+    numa: Option<String>,
+    cpus: Option<String>,
+    cpu_mapping: Option<String>,
+    mem: Option<String>,
+    monitor: Option<bool>,
+    /// O_DIRECT on or off; `buffered` is the same switch inverted
+    direct: Option<bool>,
+    buffered: Option<bool>,
+    sync: Option<String>,
+    /// Bytes of each target to use; missing or shorter files are created
+    size: Option<Value>,
+    layout: Option<String>,
+    filename_format: Option<String>,
+    /// wal workload group commit and checkpoint settings
+    wal_commit_interval: Option<Value>,
+    wal_commit_bytes: Option<Value>,
+    wal_checkpoint_threads: Option<usize>,
+}
+
+/// A value written either as a number or a string ("4k", "30s")
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum Value {
+    Number(u64),
+    Text(String),
+}
+
+impl Value {
+    fn text(&self) -> String {
+        match self {
+            Value::Number(n) => n.to_string(),
+            Value::Text(s) => s.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    fn to_vec(&self) -> Vec<String> {
+        match self {
+            OneOrMany::One(s) => vec![s.clone()],
+            OneOrMany::Many(v) => v.clone(),
+        }
+    }
+}
+
+/// Run-wide settings resolved for one section, used to detect conflicts within a group
+#[derive(Debug, Clone, PartialEq)]
+struct RunSettings {
+    devices: Option<Vec<PathBuf>>,
+    duration: Option<Duration>,
+    ramp_time: Option<Duration>,
+    numa: Option<String>,
+    cpus: Option<String>,
+    cpu_mapping: Option<String>,
+    mem: Option<String>,
+    monitor: Option<bool>,
+    buffered: Option<bool>,
+    sync: Option<String>,
+    size: Option<u64>,
+    layout: Option<String>,
+    filename_format: Option<String>,
+    wal_commit_interval: Option<Duration>,
+    wal_commit_bytes: Option<u64>,
+    wal_checkpoint_threads: Option<usize>,
+}
+
+impl JobFile {
+    /// Load a job file; `.json` files are parsed as JSON, everything else as TOML
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read job file {}", path.display()))?;
+        let is_json = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        if is_json {
+            serde_json::from_str(&content)
+                .with_context(|| format!("Invalid job file {}", path.display()))
+        } else {
+            toml::from_str(&content).with_context(|| format!("Invalid job file {}", path.display()))
+        }
+    }
+
+    /// Resolve the file into groups, validating every key.
+    /// `devices` from the command line, when given, replaces the file's devices.
+    pub fn into_groups(self, devices: Option<Vec<PathBuf>>) -> Result<Vec<JobGroup>> {
+        if self.jobs.is_empty() {
+            return Err(anyhow::anyhow!("Job file defines no [[job]] sections"));
+        }
+        if self.global.name.is_some() || self.global.group.is_some() {
+            return Err(anyhow::anyhow!(
+                "[global]: keys 'name' and 'group' are only valid in [[job]] sections"
+            ));
+        }
+
+        let global_run = self.global.run_settings("[global]")?;
+        let global_job = self.global.job_spec(String::new(), "[global]")?;
+
+        // Collect jobs into groups, preserving the order groups first appear in
+        let mut groups: Vec<(String, Vec<JobSpec>, RunSettings)> = Vec::new();
+        for (i, section) in self.jobs.iter().enumerate() {
+            let name = section
+                .name
+                .clone()
+                .ok_or_else(|| anyhow::anyhow!("job[{}]: missing required key 'name'", i))?;
+            let context = format!("job '{}'", name);
+            let run = section.run_settings(&context)?;
+            let job = section.job_spec(name.clone(), &context)?;
+
+            let group_name = section.group.clone().unwrap_or_else(|| name.clone());
+            match groups.iter_mut().find(|(g, _, _)| *g == group_name) {
+                Some((_, jobs, settings)) => {
+                    if jobs.iter().any(|j| j.name == name) {
+                        return Err(anyhow::anyhow!("{}: duplicate job name", context));
+                    }
+                    settings.merge(&run, &context, &group_name)?;
+                    jobs.push(job);
+                }
+                None => groups.push((group_name, vec![job], run)),
+            }
+        }
+
+        groups
+            .into_iter()
+            .map(|(name, mut jobs, run)| {
+                let workload = global_job.workload.unwrap_or(Workload::SeqRead);
+                // Same default as the CLI, but following each job's own workload
+                if global_job.block_size.is_none() {
+                    for job in jobs.iter_mut().filter(|j| j.block_size.is_none()) {
+                        job.block_size =
+                            Some(job.workload.unwrap_or(workload).default_block_size());
+                    }
+                }
+                let base = Config::default();
+                let mut config = Config {
+                    workload,
+                    block_size: global_job
+                        .block_size
+                        .unwrap_or(workload.default_block_size()),
+                    queue_depth: global_job.queue_depth.unwrap_or(base.queue_depth),
+                    threads: global_job.threads.unwrap_or(base.threads),
+                    rate: global_job.rate,
+                    read_percent: global_job.read_percent,
+                    block_size_split: global_job.block_size_split.clone(),
+                    distribution: global_job.distribution.unwrap_or_default(),
+                    jobs,
+                    op_mix: global_job.op_mix.clone(),
+                    flush: global_job.flush,
+                    ..base
+                };
+
+                let context = format!("group '{}'", name);
+                let settings = global_run.overridden_by(&run);
+                config.devices = match (&devices, settings.devices) {
+                    (Some(cli), _) => cli.clone(),
+                    (None, Some(file)) => file,
+                    (None, None) => {
+                        return Err(anyhow::anyhow!(
+                            "{}: no 'device' given in [global] or its jobs",
+                            context
+                        ))
+                    }
+                };
+                if let Some(duration) = settings.duration {
+                    config.duration = duration;
+                }
+                if let Some(ramp_time) = settings.ramp_time {
+                    config.ramp_time = ramp_time;
+                }
+                if let Some(numa) = &settings.numa {
+                    config.numa = Some(parse_key(&context, "numa", numa)?);
+                }
+                if let Some(cpus) = &settings.cpus {
+                    config.cpus = Some(
+                        crate::monitor::numa::parse_cpu_list(cpus)
+                            .with_context(|| format!("{}: invalid value for 'cpus'", context))?,
+                    );
+                }
+                if let Some(mapping) = &settings.cpu_mapping {
+                    config.cpu_mapping = parse_key(&context, "cpu_mapping", mapping)?;
+                }
+                if let Some(mem) = &settings.mem {
+                    config.memory_mode = parse_key(&context, "mem", mem)?;
+                }
+                config.monitor = settings.monitor.unwrap_or(false);
+                if let Some(buffered) = settings.buffered {
+                    config.buffered = buffered;
+                }
+                if let Some(sync) = &settings.sync {
+                    config.sync = parse_key(&context, "sync", sync)?;
+                }
+                config.size = settings.size;
+                if let Some(layout) = &settings.layout {
+                    config.file_layout = parse_key(&context, "layout", layout)?;
+                }
+                config.filename_format = settings.filename_format;
+                if let Some(interval) = settings.wal_commit_interval {
+                    config.wal.commit_interval = interval;
+                }
+                if let Some(bytes) = settings.wal_commit_bytes {
+                    config.wal.commit_bytes = bytes;
+                }
+                if let Some(threads) = settings.wal_checkpoint_threads {
+                    config.wal.checkpoint_threads = threads;
+                }
+
+                // Resolve once here so per-job problems surface before anything runs
+                config
+                    .job_configs()
+                    .with_context(|| format!("{}: invalid job set", context))?;
+                Ok(JobGroup { name, config })
+            })
+            .collect()
+    }
+}
+
+impl Section {
+    fn job_spec(&self, name: String, context: &str) -> Result<JobSpec> {
+        Ok(JobSpec {
+            name,
+            workload: self
+                .workload
+                .as_deref()
+                .map(|w| parse_key::<Workload>(context, "workload", w))
+                .transpose()?,
+            block_size: self
+                .block_size
+                .as_ref()
+                .map(|bs| {
+                    config::parse_block_size(&bs.text())
+                        .with_context(|| format!("{}: invalid value for 'bs'", context))
+                })
+                .transpose()?,
+            queue_depth: self.queue_depth,
+            threads: self.threads,
+            rate: self
+                .rate
+                .as_ref()
+                .map(|rate| parse_key(context, "rate", &rate.text()))
+                .transpose()?,
+            read_percent: self
+                .rwmixread
+                .map(|pct| {
+                    config::parse_percent(&pct.to_string())
+                        .with_context(|| format!("{}: invalid value for 'rwmixread'", context))
+                })
+                .transpose()?,
+            block_size_split: self
+                .bssplit
+                .as_deref()
+                .map(|split| parse_key(context, "bssplit", split))
+                .transpose()?,
+            distribution: self
+                .distribution
+                .as_deref()
+                .map(|d| parse_key(context, "distribution", d))
+                .transpose()?,
+            op_mix: self
+                .op_mix
+                .as_deref()
+                .map(|mix| parse_key(context, "op_mix", mix))
+                .transpose()?,
+            flush: match (self.fsync, self.fdatasync) {
+                (Some(_), Some(_)) => {
+                    return Err(anyhow::anyhow!(
+                        "{}: 'fsync' and 'fdatasync' cannot both be set",
+                        context
+                    ))
+                }
+                (Some(writes), None) => FlushPolicy::new(FlushKind::Fsync, writes),
+                (None, Some(writes)) => FlushPolicy::new(FlushKind::Fdatasync, writes),
+                (None, None) => None,
+            },
+        })
+    }
+
+    fn run_settings(&self, context: &str) -> Result<RunSettings> {
+        Ok(RunSettings {
+            devices: self
+                .device
+                .as_ref()
+                .map(|d| {
+                    config::expand_devices(&d.to_vec())
+                        .with_context(|| format!("{}: invalid value for 'device'", context))
+                })
+                .transpose()?,
+            duration: self
+                .duration
+                .as_ref()
+                .map(|d| {
+                    config::parse_duration(&d.text())
+                        .with_context(|| format!("{}: invalid value for 'duration'", context))
+                })
+                .transpose()?,
+            ramp_time: self
+                .ramp_time
+                .as_ref()
+                .map(|d| {
+                    config::parse_duration(&d.text())
+                        .with_context(|| format!("{}: invalid value for 'ramp_time'", context))
+                })
+                .transpose()?,
+            numa: self.numa.clone(),
+            cpus: self.cpus.clone(),
+            cpu_mapping: self.cpu_mapping.clone(),
+            mem: self.mem.clone(),
+            monitor: self.monitor,
+            buffered: match (self.direct, self.buffered) {
+                (Some(direct), Some(buffered)) if direct == buffered => {
+                    return Err(anyhow::anyhow!(
+                        "{}: 'direct' and 'buffered' contradict each other",
+                        context
+                    ))
+                }
+                (Some(direct), _) => Some(!direct),
+                (None, buffered) => buffered,
+            },
+            sync: self.sync.clone(),
+            size: self
+                .size
+                .as_ref()
+                .map(|size| {
+                    config::parse_block_size(&size.text())
+                        .map(|bytes| bytes as u64)
+                        .with_context(|| format!("{}: invalid value for 'size'", context))
+                })
+                .transpose()?,
+            layout: self.layout.clone(),
+            filename_format: self.filename_format.clone(),
+            wal_commit_interval: self
+                .wal_commit_interval
+                .as_ref()
+                .map(|d| {
+                    config::parse_duration(&d.text()).with_context(|| {
+                        format!("{}: invalid value for 'wal_commit_interval'", context)
+                    })
+                })
+                .transpose()?,
+            wal_commit_bytes: self
+                .wal_commit_bytes
+                .as_ref()
+                .map(|bytes| {
+                    config::parse_block_size(&bytes.text())
+                        .map(|bytes| bytes as u64)
+                        .with_context(|| {
+                            format!("{}: invalid value for 'wal_commit_bytes'", context)
+                        })
+                })
+                .transpose()?,
+            wal_checkpoint_threads: self.wal_checkpoint_threads,
+        })
+    }
+}
+
+impl RunSettings {
+    /// Fold another job's run-wide settings into the group, rejecting conflicts
+    fn merge(&mut self, other: &RunSettings, context: &str, group: &str) -> Result<()> {
+        fn merge_key<T: Clone + PartialEq>(
+            mine: &mut Option<T>,
+            theirs: &Option<T>,
+            key: &str,
+            context: &str,
+            group: &str,
+        ) -> Result<()> {
+            match (mine.as_ref(), theirs) {
+                (Some(a), Some(b)) if a != b => Err(anyhow::anyhow!(
+                    "{}: key '{}' conflicts with another job in group '{}'",
+                    context,
+                    key,
+                    group
+                )),
+                (None, Some(b)) => {
+                    *mine = Some(b.clone());
+                    Ok(())
+                }
+                _ => Ok(()),
+            }
+        }
+        merge_key(&mut self.devices, &other.devices, "device", context, group)?;
+        merge_key(
+            &mut self.duration,
+            &other.duration,
+            "duration",
+            context,
+            group,
+        )?;
+        merge_key(
+            &mut self.ramp_time,
+            &other.ramp_time,
+            "ramp_time",
+            context,
+            group,
+        )?;
+        merge_key(&mut self.numa, &other.numa, "numa", context, group)?;
+        merge_key(&mut self.cpus, &other.cpus, "cpus", context, group)?;
+        merge_key(
+            &mut self.cpu_mapping,
+            &other.cpu_mapping,
+            "cpu_mapping",
+            context,
+            group,
+        )?;
+        merge_key(&mut self.mem, &other.mem, "mem", context, group)?;
+        merge_key(&mut self.monitor, &other.monitor, "monitor", context, group)?;
+        merge_key(
+            &mut self.buffered,
+            &other.buffered,
+            "direct",
+            context,
+            group,
+        )?;
+        merge_key(&mut self.sync, &other.sync, "sync", context, group)?;
+        merge_key(&mut self.size, &other.size, "size", context, group)?;
+        merge_key(&mut self.layout, &other.layout, "layout", context, group)?;
+        merge_key(
+            &mut self.filename_format,
+            &other.filename_format,
+            "filename_format",
+            context,
+            group,
+        )?;
+        merge_key(
+            &mut self.wal_commit_interval,
+            &other.wal_commit_interval,
+            "wal_commit_interval",
+            context,
+            group,
+        )?;
+        merge_key(
+            &mut self.wal_commit_bytes,
+            &other.wal_commit_bytes,
+            "wal_commit_bytes",
+            context,
+            group,
+        )?;
+        merge_key(
+            &mut self.wal_checkpoint_threads,
+            &other.wal_checkpoint_threads,
+            "wal_checkpoint_threads",
+            context,
+            group,
+        )?;
+        Ok(())
+    }
+
+    /// Group settings take precedence over [global]
+    fn overridden_by(&self, group: &RunSettings) -> RunSettings {
+        RunSettings {
+            devices: group.devices.clone().or_else(|| self.devices.clone()),
+            duration: group.duration.or(self.duration),
+            ramp_time: group.ramp_time.or(self.ramp_time),
+            numa: group.numa.clone().or_else(|| self.numa.clone()),
+            cpus: group.cpus.clone().or_else(|| self.cpus.clone()),
+            cpu_mapping: group
+                .cpu_mapping
+                .clone()
+                .or_else(|| self.cpu_mapping.clone()),
+            mem: group.mem.clone().or_else(|| self.mem.clone()),
+            monitor: group.monitor.or(self.monitor),
+            buffered: group.buffered.or(self.buffered),
+            sync: group.sync.clone().or_else(|| self.sync.clone()),
+            size: group.size.or(self.size),
+            layout: group.layout.clone().or_else(|| self.layout.clone()),
+            filename_format: group
+                .filename_format
+                .clone()
+                .or_else(|| self.filename_format.clone()),
+            wal_commit_interval: group.wal_commit_interval.or(self.wal_commit_interval),
+            wal_commit_bytes: group.wal_commit_bytes.or(self.wal_commit_bytes),
+            wal_checkpoint_threads: group.wal_checkpoint_threads.or(self.wal_checkpoint_threads),
+        }
+    }
+}
+
+/// Parse a key's value, naming the section and key on failure
+fn parse_key<T>(context: &str, key: &str, value: &str) -> Result<T>
+where
+    T: std::str::FromStr<Err = anyhow::Error>,
+{
+    value
+        .parse()
+        .with_context(|| format!("{}: invalid value for '{}'", context, key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::FileLayout;
+
+    fn translate(content: &str) -> Result<Vec<JobGroup>> {
+        let file: JobFile = toml::from_str(content)?;
+        file.into_groups(None)
+    }
+
+    fn error(content: &str) -> String {
+        format!("{:#}", translate(content).unwrap_err())
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        let message =
+            error("[global]\ndevice = \"/dev/fixture\"\niodepht = 4\n[[job]]\nname = \"a\"\n");
+        assert!(message.contains("unknown field `iodepht`"), "{}", message);
+
+        let message = error(
+            "[global]\ndevice = \"/dev/fixture\"\n[[job]]\nname = \"a\"\nbsplit = \"4k/100\"\n",
+        );
+        assert!(message.contains("unknown field `bsplit`"), "{}", message);
+
+        let message = error("[defaults]\nqd = 4\n[[job]]\nname = \"a\"\n");
+        assert!(message.contains("unknown field `defaults`"), "{}", message);
+    }
+
+    #[test]
+    fn jobs_sharing_a_group_run_together() {
+        let groups = translate(
+            "[global]\n\
+             device = \"/dev/fixture\"\n\
+             duration = \"30s\"\n\
+             qd = 16\n\
+             [[job]]\n\
+             name = \"reader\"\n\
+             workload = \"randread\"\n\
+             group = \"mixed\"\n\
+             [[job]]\n\
+             name = \"writer\"\n\
+             workload = \"seqwrite\"\n\
+             group = \"mixed\"\n\
+             duration = \"10s\"\n\
+             [[job]]\n\
+             name = \"alone\"\n",
+        )
+        .unwrap();
+        assert_eq!(groups.len(), 2);
+        let mixed = &groups[0].config;
+        assert_eq!(groups[0].name, "mixed");
+        assert_eq!(mixed.jobs.len(), 2);
+        assert_eq!(mixed.queue_depth, 16);
+        // Run-wide keys of a job apply to its whole group
+        assert_eq!(mixed.duration, Duration::from_secs(10));
+        // Default block sizes follow each job's workload
+        assert_eq!(
+            mixed.jobs[0].block_size,
+            Some(Workload::RandRead.default_block_size())
+        );
+        assert_eq!(
+            mixed.jobs[1].block_size,
+            Some(Workload::SeqWrite.default_block_size())
+        );
+        assert_eq!(groups[1].config.duration, Duration::from_secs(30));
+    }
+
+    #[test]
+    fn rejects_conflicts_within_a_group() {
+        let message = error(
+            "[global]\ndevice = \"/dev/fixture\"\n\
+             [[job]]\nname = \"a\"\ngroup = \"g\"\nduration = \"5s\"\n\
+             [[job]]\nname = \"b\"\ngroup = \"g\"\nduration = \"6s\"\n",
+        );
+        assert!(message.contains("key 'duration' conflicts"), "{}", message);
+
+        let message = error(
+            "[global]\ndevice = \"/dev/fixture\"\n\
+             [[job]]\nname = \"a\"\ngroup = \"g\"\n[[job]]\nname = \"a\"\ngroup = \"g\"\n",
+        );
+        assert!(message.contains("duplicate job name"), "{}", message);
+    }
+
+    #[test]
+    fn rejects_contradicting_keys() {
+        let message = error("[global]\ndevice = \"/dev/fixture\"\ndirect = true\nbuffered = true\n[[job]]\nname = \"a\"\n");
+        assert!(message.contains("contradict"), "{}", message);
+
+        let message = error("[global]\ndevice = \"/dev/fixture\"\n[[job]]\nname = \"a\"\nfsync = 1\nfdatasync = 1\n");
+        assert!(message.contains("cannot both be set"), "{}", message);
+
+        assert!(error("[[job]]\nname = \"a\"\n").contains("no 'device'"));
+        assert!(
+            error("[global]\ndevice = \"/dev/fixture\"\n[[job]]\nworkload = \"randread\"\n")
+                .contains("missing required key 'name'")
+        );
+        assert!(error(
+            "[global]\ndevice = \"/dev/fixture\"\nname = \"a\"\n[[job]]\nname = \"a\"\n"
+        )
+        .contains("only valid in [[job]]"));
+        assert!(error(
+            "[global]\ndevice = \"/dev/fixture\"\n[[job]]\nname = \"a\"\nbs = \"huge\"\n"
+        )
+        .contains("invalid value for 'bs'"));
+    }
+
+    #[test]
+    fn maps_file_and_io_mode_keys() {
+        let groups = translate(
+            "[global]\n\
+             device = \"/srv/bench\"\n\
+             size = \"64m\"\n\
+             layout = \"write\"\n\
+             filename_format = \"$jobname.$jobnum\"\n\
+             direct = false\n\
+             sync = \"dsync\"\n\
+             [[job]]\n\
+             name = \"a\"\n\
+             fdatasync = 8\n",
+        )
+        .unwrap();
+        let config = &groups[0].config;
+        assert_eq!(config.size, Some(64 << 20));
+        assert_eq!(config.file_layout, FileLayout::Write);
+        assert_eq!(config.filename_format.as_deref(), Some("$jobname.$jobnum"));
+        assert!(config.buffered);
+        assert_eq!(config.sync, crate::config::SyncMode::Dsync);
+        assert_eq!(
+            config.jobs[0].flush,
+            FlushPolicy::new(FlushKind::Fdatasync, 8)
+        );
+    }
+
+    #[test]
+    fn maps_wal_keys() {
+        let groups = translate(
+            "[global]\n\
+             device = \"/dev/fixture\"\n\
+             workload = \"wal\"\n\
+             wal_commit_interval = \"2ms\"\n\
+             [[job]]\n\
+             name = \"log\"\n\
+             wal_commit_bytes = \"32k\"\n\
+             wal_checkpoint_threads = 1\n",
+        )
+        .unwrap();
+        let wal = &groups[0].config.wal;
+        assert_eq!(wal.commit_interval, Duration::from_millis(2));
+        assert_eq!(wal.commit_bytes, 32768);
+        assert_eq!(wal.checkpoint_threads, 1);
+
+        let message = error(
+            "[global]\ndevice = \"/dev/fixture\"\n\
+             [[job]]\nname = \"a\"\ngroup = \"g\"\nwal_commit_bytes = \"4k\"\n\
+             [[job]]\nname = \"b\"\ngroup = \"g\"\nwal_commit_bytes = \"8k\"\n",
+        );
+        assert!(
+            message.contains("key 'wal_commit_bytes' conflicts"),
+            "{}",
+            message
+        );
+    }
+}
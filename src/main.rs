@@ -1,12 +1,20 @@
 use crate::io::engine::{BenchmarkResults, IoEngine};
-use crate::io::Device;
+use crate::io::blockdev::{self, BlockDevice, BlockKind, ListFilter};
+use crate::io::device::SYSFS_ROOT;
 use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use rand::SeedableRng;
 
+mod compare;
 mod config;
+mod fio;
 mod io;
+mod jobfile;
 mod monitor;
 mod optimizer;
+mod output;
+mod profile;
+mod stats;
+mod sweep;
 
 use config::Config;
 
@@ -18,15 +26,19 @@ struct Cli {
     command: Commands,
 }
 
+// Parsed once per process, so the size of Run does not matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 enum Commands {
     /// Run a benchmark test
     Run {
-        /// Path to storage device (e.g., /dev/nvme0n1)
-        #[arg(short, long)]
-        device: PathBuf,
+        /// Storage device(s): a path, comma-separated list, or glob (e.g., "/dev/nvme*n1")
+        /// With --job-file or --fio-job, overrides the devices named in the file
+        #[arg(short, long, required_unless_present_any = ["job_file", "fio_job"])]
+        device: Vec<String>,
 
-        /// Workload type: seqread, seqwrite, randread, randwrite, seq, rand, all
+        /// Workload type: seqread, seqwrite, randread, randwrite, seq, rand, trim,
+        /// write-zeroes, secure-erase, wal, all
         #[arg(short, long, default_value = "seqread")]
         workload: String,
 
@@ -39,7 +51,7 @@ enum Commands {
         #[arg(short = 'q', long, default_value = "32")]
         queue_depth: usize,
 
-        /// Number of worker threads
+        /// Number of worker threads per device
         #[arg(short = 'n', long)]
         threads: Option<usize>,
 
@@ -54,9 +66,354 @@ enum Commands {
         /// Enable real-time monitoring
         #[arg(short = 'm', long)]
         monitor: bool,
+
+        /// NUMA placement: auto (device's home node), node:N, or interleave
+        #[arg(long)]
+        numa: Option<String>,
+
+        /// Pin workers to these CPUs (e.g., 0-7,16-23)
+        #[arg(long)]
+        cpus: Option<String>,
+
+        /// Worker to CPU mapping for --cpus: round-robin or one-to-one
+        #[arg(long, default_value = "round-robin")]
+        cpu_mapping: String,
+
+        /// I/O buffer memory: anon, thp (transparent huge pages), or hugepage (MAP_HUGETLB)
+        #[arg(long = "mem", default_value = "anon")]
+        memory_mode: String,
+
+        /// Per-worker rate limit: IOPS (e.g., 5000 or 5000iops) or bandwidth (e.g., 200m)
+        #[arg(long)]
+        rate: Option<String>,
+
+        /// Concurrent job, repeatable (e.g., "name=reader,workload=randread,bs=4k,qd=16,threads=2,rate=20000").
+        /// Unset fields inherit from the other options
+        #[arg(long = "job")]
+        jobs: Vec<String>,
+
+        /// Load jobs from a TOML or JSON job file instead of the command line options
+        #[arg(
+            long,
+            conflicts_with_all = [
+                "workload", "block_size", "queue_depth", "threads", "duration", "optimize",
+                "numa", "cpus", "cpu_mapping", "memory_mode", "rate", "jobs",
+            ]
+        )]
+        job_file: Option<std::path::PathBuf>,
+
+        /// Translate and run a fio job file (.fio); unsupported options warn or fail
+        #[arg(
+            long,
+            conflicts_with_all = [
+                "workload", "block_size", "queue_depth", "threads", "duration", "optimize",
+                "numa", "cpus", "cpu_mapping", "memory_mode", "rate", "jobs", "job_file",
+            ]
+        )]
+        fio_job: Option<std::path::PathBuf>,
+
+        /// Result format: normal, json (storage-bench results), or fio-json (fio's JSON schema)
+        #[arg(long, default_value = "normal")]
+        output_format: String,
+
+        /// Write the json/fio-json document to this file instead of stdout
+        #[arg(long)]
+        output: Option<std::path::PathBuf>,
+
+        /// Run the workload this many times and summarize the spread of the results
+        #[arg(long, default_value = "1")]
+        repeat: usize,
+
+        /// Idle gap between repeated trials, in seconds
+        #[arg(long, default_value = "0")]
+        cooldown: u64,
+
+        /// Coefficient of variation (percent) above which a metric is flagged as unstable
+        #[arg(long, default_value = "5")]
+        max_cv: f64,
+
+        /// Use tuned parameters: "auto" picks the saved profile matching the device's
+        /// model, firmware and link speed; otherwise a profile file path
+        #[arg(
+            long,
+            conflicts_with_all = [
+                "block_size", "queue_depth", "threads", "jobs", "job_file", "fio_job",
+            ]
+        )]
+        profile: Option<String>,
+
+        /// Profile store directory. Default: $XDG_DATA_HOME/storage-bench/profiles
+        #[arg(long)]
+        profile_dir: Option<std::path::PathBuf>,
+
+        /// Write even to devices that are mounted, used by md/dm/LVM, or carry a
+        /// filesystem, partition table or swap signature
+        #[arg(long)]
+        allow_destructive: bool,
+
+        /// Buffered I/O through the page cache instead of O_DIRECT
+        #[arg(long)]
+        no_direct: bool,
+
+        /// Open targets with O_SYNC: writes complete once data and metadata are durable
+        #[arg(long, conflicts_with = "dsync")]
+        sync: bool,
+
+        /// Open targets with O_DSYNC: writes complete once their data is durable
+        #[arg(long)]
+        dsync: bool,
+
+        /// Bytes of each target to use (e.g., 4g); missing or shorter files are created
+        #[arg(long)]
+        size: Option<String>,
+
+        /// How new files are allocated: auto (written when the workload reads,
+        /// fallocated otherwise; the default), fallocate, or write
+        #[arg(long)]
+        layout: Option<String>,
+
+        /// Give each worker its own file in the target directories, named by this format
+        /// ($jobname and $jobnum are replaced, e.g., "bench.$jobname.$jobnum")
+        #[arg(long)]
+        filename_format: Option<String>,
+
+        /// Block queue attributes to set for the run and restore afterwards
+        /// (e.g., "scheduler=none,nr_requests=1023,rq_affinity=2,nomerges=2")
+        #[arg(long)]
+        set_queue: Option<String>,
+
+        /// Weighted mix of operations, overriding the workload's reads and writes
+        /// (e.g., "read/70:write/20:discard/10"; also write-zeroes, secure-erase)
+        #[arg(long)]
+        op_mix: Option<String>,
+
+        /// Discard each target's whole range before the run to reset SSD state
+        #[arg(long)]
+        trim_before: bool,
+
+        /// Issue an fsync after every N writes of each worker
+        #[arg(long, conflicts_with = "fdatasync_every")]
+        fsync_every: Option<u32>,
+
+        /// Issue an fdatasync after every N writes of each worker
+        #[arg(long)]
+        fdatasync_every: Option<u32>,
+
+        /// Link each flush to its write (IOSQE_IO_LINK) so it starts once the write completes
+        #[arg(long)]
+        link_flush: bool,
+
+        /// wal workload: commit once the oldest uncommitted record is this old [default: 1ms]
+        #[arg(long)]
+        wal_commit_interval: Option<String>,
+
+        /// wal workload: commit once this many bytes are waiting [default: 64k]
+        #[arg(long)]
+        wal_commit_bytes: Option<String>,
+
+        /// wal workload: threads per target reading the log back like a checkpoint [default: 0]
+        #[arg(long)]
+        wal_checkpoint_threads: Option<usize>,
+    },
+    /// Compare two json result files; exits with status 2 when the candidate regressed
+    Compare {
+        /// Results of the reference run (--output-format json)
+        baseline: std::path::PathBuf,
+
+        /// Results of the run under test
+        candidate: std::path::PathBuf,
+
+        /// Largest tolerated change for the worse, in percent
+        #[arg(long, default_value = "5")]
+        threshold: f64,
+
+        /// Significance level for runs with repeated samples
+        #[arg(long, default_value = "0.05")]
+        alpha: f64,
+    },
+    /// Run a workload over a grid of queue depths, block sizes and thread counts
+    Sweep {
+        /// Storage device(s): a path, comma-separated list, or glob
+        #[arg(short, long, required = true)]
+        device: Vec<String>,
+
+        /// Workload type: seqread, seqwrite, randread, randwrite, seq, rand
+        #[arg(short, long, default_value = "randread")]
+        workload: String,
+
+        /// Queue depths; "a..b" doubles from a to b (e.g., 1,2,4..256)
+        #[arg(long, default_value = "1..256")]
+        qd: String,
+
+        /// Block sizes (e.g., 4k,16k,128k). Default: the workload's default block size
+        #[arg(long)]
+        bs: Option<String>,
+
+        /// Worker threads per device (e.g., 1,2,4)
+        #[arg(long, default_value = "1")]
+        threads: String,
+
+        /// Measured duration of each point in seconds
+        #[arg(short = 't', long, default_value = "10")]
+        duration: u64,
+
+        /// Unmeasured warm-up before each point, in seconds
+        #[arg(long, default_value = "0")]
+        ramp_time: u64,
+
+        /// Also write the results as CSV to this file
+        #[arg(long)]
+        csv: Option<std::path::PathBuf>,
+
+        /// Block queue attributes to set for the sweep and restore afterwards
+        /// (e.g., "scheduler=none,nr_requests=1023,rq_affinity=2,nomerges=2")
+        #[arg(long)]
+        set_queue: Option<String>,
+
+        /// Write even to devices that are mounted, used by md/dm/LVM, or carry a
+        /// filesystem, partition table or swap signature
+        #[arg(long)]
+        allow_destructive: bool,
+    },
+    /// Search for the parameters that maximize an objective, using short trial runs
+    Optimize {
+        /// Storage device(s): a path, comma-separated list, or glob
+        #[arg(short, long, required = true)]
+        device: Vec<String>,
+
+        /// Workload type: seqread, seqwrite, randread, randwrite, seq, rand
+        #[arg(short, long, default_value = "randread")]
+        workload: String,
+
+        /// Search strategy: genetic, annealing (simulated annealing), or exhaustive
+        #[arg(long, default_value = "genetic")]
+        strategy: String,
+
+        /// Goals, optionally weighted: throughput, iops, p99 (minimized), iops-per-core
+        /// (e.g., "iops:0.7,p99:0.3"). Several goals yield a Pareto front of tradeoffs
+        #[arg(long, default_value = "iops")]
+        objective: String,
+
+        /// Hard constraint, repeatable (e.g., "p99<1ms", "iops>=100k", "cpu<=4")
+        #[arg(long = "constraint")]
+        constraints: Vec<String>,
+
+        /// Queue depths to choose from; "a..b" doubles from a to b
+        #[arg(long, default_value = "1..256")]
+        qd: String,
+
+        /// Block sizes to choose from
+        #[arg(long, default_value = "4k..1m")]
+        bs: String,
+
+        /// Worker threads per device to choose from
+        #[arg(long, default_value = "1,2,4,8")]
+        threads: String,
+
+        /// Concurrent job counts to choose from
+        #[arg(long, default_value = "1")]
+        jobs: String,
+
+        /// Measured duration of each trial in seconds
+        #[arg(short = 't', long, default_value = "5")]
+        duration: u64,
+
+        /// Unmeasured warm-up before each trial, in seconds
+        #[arg(long, default_value = "0")]
+        ramp_time: u64,
+
+        /// Genetic search: individuals per generation
+        #[arg(long, default_value = "12")]
+        population: usize,
+
+        /// Genetic search: maximum number of generations
+        #[arg(long, default_value = "10")]
+        generations: usize,
+
+        /// Genetic search: chance that each parameter of a child is mutated
+        #[arg(long, default_value = "0.2")]
+        mutation_rate: f64,
+
+        /// Genetic search: chance that a child combines two parents
+        #[arg(long, default_value = "0.8")]
+        crossover_rate: f64,
+
+        /// Stop after this many generations (genetic) or steps (annealing) without a 1%
+        /// improvement. Default: 3 generations or 15 steps
+        #[arg(long)]
+        patience: Option<usize>,
+
+        /// Annealing: starting temperature, as the relative score loss accepted with
+        /// probability 1/e (0.1 = a 10% worse point)
+        #[arg(long, default_value = "0.1")]
+        temperature: f64,
+
+        /// Annealing: temperature multiplier per step
+        #[arg(long, default_value = "0.9")]
+        cooling_rate: f64,
+
+        /// Annealing: maximum number of steps
+        #[arg(long, default_value = "40")]
+        steps: usize,
+
+        /// Random seed, for reproducible searches
+        #[arg(long)]
+        seed: Option<u64>,
+
+        /// Block queue attributes to set for every trial and restore afterwards
+        /// (e.g., "scheduler=none,nr_requests=1023,rq_affinity=2,nomerges=2")
+        #[arg(long)]
+        set_queue: Option<String>,
+
+        /// After the search, refine the best parameters for this many more trials,
+        /// each adjusted to the bottleneck the previous one hit
+        #[arg(long, default_value_t = 0)]
+        tune_rounds: usize,
+
+        /// Let the refinement also change block queue attributes (rq_affinity,
+        /// scheduler, nr_requests, nomerges)
+        #[arg(long)]
+        tune_queue: bool,
+
+        /// Don't save the best parameters as the device's tuned profile
+        #[arg(long)]
+        no_save_profile: bool,
+
+        /// Profile store directory. Default: $XDG_DATA_HOME/storage-bench/profiles
+        #[arg(long)]
+        profile_dir: Option<std::path::PathBuf>,
+
+        /// Write even to devices that are mounted, used by md/dm/LVM, or carry a
+        /// filesystem, partition table or swap signature
+        #[arg(long)]
+        allow_destructive: bool,
+    },
+    /// List block devices as a tree of disks, partitions and stacked devices
+    List {
+        /// Include empty devices, RAM disks and unattached loop devices
+        #[arg(long)]
+        all: bool,
+
+        /// Print the device tree as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Only these device types, comma-separated (disk, part, loop, md, lvm, crypt, dm, ram)
+        #[arg(long = "type")]
+        kind: Option<String>,
+
+        /// Only devices on this transport (nvme, sata, sas, usb, virtio, ...)
+        #[arg(long)]
+        transport: Option<String>,
+
+        /// Only devices whose kernel name matches a glob (e.g., "nvme*")
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Only devices that are not mounted, swapped on or used by md/dm/LVM
+        #[arg(long)]
+        unused: bool,
     },
-    /// List available storage devices
-    List,
     /// Show system information
     Info,
 }
@@ -75,19 +432,174 @@ async fn main() -> anyhow::Result<()> {
             duration,
             optimize,
             monitor,
+            numa,
+            cpus,
+            cpu_mapping,
+            memory_mode,
+            rate,
+            jobs,
+            job_file,
+            fio_job,
+            output_format,
+            output,
+            repeat,
+            cooldown,
+            max_cv,
+            profile,
+            profile_dir,
+            allow_destructive,
+            no_direct,
+            sync,
+            dsync,
+            size,
+            layout,
+            filename_format,
+            set_queue,
+            op_mix,
+            trim_before,
+            fsync_every,
+            fdatasync_every,
+            link_flush,
+            wal_commit_interval,
+            wal_commit_bytes,
+            wal_checkpoint_threads,
         } => {
+            let wal_commit_interval = wal_commit_interval
+                .as_deref()
+                .map(crate::config::parse_duration)
+                .transpose()?;
+            let wal_commit_bytes = wal_commit_bytes
+                .as_deref()
+                .map(crate::config::parse_block_size)
+                .transpose()?;
+            // Only the flags given replace a setting, so job files keep their own
+            let apply_wal = |wal: &mut crate::config::WalSettings| {
+                if let Some(interval) = wal_commit_interval {
+                    wal.commit_interval = interval;
+                }
+                if let Some(bytes) = wal_commit_bytes {
+                    wal.commit_bytes = bytes as u64;
+                }
+                if let Some(threads) = wal_checkpoint_threads {
+                    wal.checkpoint_threads = threads;
+                }
+            };
+            let flush = match (fsync_every, fdatasync_every) {
+                (Some(writes), _) => {
+                    crate::config::FlushPolicy::new(crate::config::FlushKind::Fsync, writes)
+                }
+                (None, Some(writes)) => {
+                    crate::config::FlushPolicy::new(crate::config::FlushKind::Fdatasync, writes)
+                }
+                (None, None) => None,
+            };
+            let op_mix = op_mix
+                .as_deref()
+                .map(str::parse::<crate::config::OpMix>)
+                .transpose()?;
+            let set_queue = set_queue
+                .as_deref()
+                .map(str::parse::<crate::config::QueueSettings>)
+                .transpose()?;
+            let size = size
+                .as_deref()
+                .map(|s| crate::config::parse_block_size(s).map(|b| b as u64))
+                .transpose()?;
+            let layout: Option<crate::config::FileLayout> =
+                layout.as_deref().map(str::parse).transpose()?;
+            let sync_mode = if sync {
+                crate::config::SyncMode::Sync
+            } else if dsync {
+                crate::config::SyncMode::Dsync
+            } else {
+                crate::config::SyncMode::None
+            };
+            let output_format: output::OutputFormat = output_format.parse()?;
+            if output_format == output::OutputFormat::Normal && output.is_some() {
+                return Err(anyhow::anyhow!(
+                    "--output needs --output-format json or fio-json"
+                ));
+            }
+            if repeat == 0 {
+                return Err(anyhow::anyhow!("--repeat must be at least 1"));
+            }
+            let trials = Trials {
+                repeat,
+                cooldown: std::time::Duration::from_secs(cooldown),
+                max_cv,
+            };
+            // A document on stdout must be the only thing printed there
+            let print_text = output_format == output::OutputFormat::Normal || output.is_some();
+            if !print_text && monitor {
+                return Err(anyhow::anyhow!(
+                    "--monitor writes to stdout; use --output with --output-format {}",
+                    output_format
+                ));
+            }
+
+            if job_file.is_some() || fio_job.is_some() {
+                let devices = if device.is_empty() {
+                    None
+                } else {
+                    Some(crate::config::expand_devices(&device)?)
+                };
+                let groups = match (job_file, fio_job) {
+                    (Some(path), _) => jobfile::JobFile::load(&path)?.into_groups(devices)?,
+                    (None, Some(path)) => fio::FioJobFile::load(&path)?.into_groups(devices)?,
+                    (None, None) => unreachable!(),
+                };
+                let mut results = Vec::with_capacity(groups.len());
+                for group in groups {
+                    let mut config = group.config;
+                    config.monitor |= monitor;
+                    config.allow_destructive |= allow_destructive;
+                    config.buffered |= no_direct;
+                    if sync_mode != crate::config::SyncMode::None {
+                        config.sync = sync_mode;
+                    }
+                    config.size = size.or(config.size);
+                    if let Some(layout) = layout {
+                        config.file_layout = layout;
+                    }
+                    config.filename_format = filename_format.clone().or(config.filename_format);
+                    if let Some(set_queue) = &set_queue {
+                        config.set_queue = set_queue.clone();
+                    }
+                    if op_mix.is_some() {
+                        config.op_mix = op_mix.clone();
+                    }
+                    config.trim_before |= trim_before;
+                    if flush.is_some() {
+                        config.flush = flush;
+                    }
+                    config.link_flush |= link_flush;
+                    apply_wal(&mut config.wal);
+                    if print_text {
+                        println!("=== Group: {} ===", group.name);
+                        print_config(&config);
+                    }
+                    results.extend(run_trials(config, trials, print_text).await?);
+                }
+                return write_output(output_format, output.as_deref(), &results);
+            }
+
             let workload_parsed: crate::config::Workload = workload.parse()?;
             // Determine default block size based on workload
-            let default_block_size = if workload_parsed.is_sequential() {
-                "128k"
-            } else {
-                "4k"
+            let block_size_bytes = match block_size.as_deref() {
+                Some(block_size) => crate::config::parse_block_size(block_size)?,
+                None => workload_parsed.default_block_size(),
             };
-            let block_size_str = block_size.as_deref().unwrap_or(default_block_size);
-            let block_size_bytes = crate::config::parse_block_size(block_size_str)?;
+            let numa_policy = numa
+                .as_deref()
+                .map(str::parse::<crate::config::NumaPolicy>)
+                .transpose()?;
+            let cpu_list = cpus
+                .as_deref()
+                .map(crate::monitor::numa::parse_cpu_list)
+                .transpose()?;
 
-            let config = Config {
-                device: device.clone(),
+            let mut config = Config {
+                devices: crate::config::expand_devices(&device)?,
                 workload: workload_parsed,
                 block_size: block_size_bytes,
                 queue_depth,
@@ -95,21 +607,285 @@ async fn main() -> anyhow::Result<()> {
                 duration: std::time::Duration::from_secs(duration),
                 optimize,
                 monitor,
+                numa: numa_policy,
+                cpus: cpu_list,
+                cpu_mapping: cpu_mapping.parse()?,
+                memory_mode: memory_mode.parse()?,
+                rate: rate.as_deref().map(str::parse).transpose()?,
+                jobs: jobs
+                    .iter()
+                    .map(|job| job.parse())
+                    .collect::<anyhow::Result<Vec<_>>>()?,
+                allow_destructive,
+                buffered: no_direct,
+                sync: sync_mode,
+                size,
+                file_layout: layout.unwrap_or_default(),
+                filename_format,
+                set_queue: set_queue.unwrap_or_default(),
+                op_mix,
+                trim_before,
+                flush,
+                link_flush,
+                ..Default::default()
             };
+            apply_wal(&mut config.wal);
 
-            println!("Starting benchmark...");
-            println!("Device: {:?}", config.device);
-            println!("Workload: {:?}", config.workload);
-            println!("Block size: {} ({})", block_size_str, config.block_size);
-            println!("Queue depth: {}", config.queue_depth);
-            println!("Threads: {}", config.threads);
-            println!("Duration: {} seconds", duration);
-            println!("I/O Engine: io_uring");
+            if let Some(profile) = profile {
+                if workload_parsed == crate::config::Workload::All {
+                    return Err(anyhow::anyhow!("--profile needs a single workload, not 'all'"));
+                }
+                let (source, tuned) = if profile == "auto" {
+                    let store = profile::ProfileStore::new(match profile_dir {
+                        Some(dir) => dir,
+                        None => profile::ProfileStore::default_dir()?,
+                    });
+                    let (key, tuned) = store.lookup(&config.devices, workload_parsed)?;
+                    (key.to_string(), tuned)
+                } else {
+                    let path = std::path::Path::new(&profile);
+                    (
+                        path.display().to_string(),
+                        profile::ProfileStore::load_file(path, workload_parsed)?,
+                    )
+                };
+                config = tuned.params().apply(&config);
+                if print_text {
+                    println!(
+                        "Profile: {} ({}, {})",
+                        source,
+                        optimizer::trial::describe_params(&tuned.params()),
+                        tuned.objective
+                    );
+                }
+            }
 
-            run_benchmark(config).await?;
+            if print_text {
+                print_config(&config);
+            }
+            let results = run_trials(config, trials, print_text).await?;
+            write_output(output_format, output.as_deref(), &results)?;
         }
-        Commands::List => {
-            list_devices().await?;
+        Commands::Compare {
+            baseline,
+            candidate,
+            threshold,
+            alpha,
+        } => {
+            let thresholds = compare::Thresholds {
+                max_regression_percent: threshold,
+                alpha,
+            };
+            let comparison = compare::compare(
+                &output::load_results(&baseline)?,
+                &output::load_results(&candidate)?,
+                thresholds,
+            );
+            compare::print_comparison(&comparison);
+            let regressions = comparison.regressions();
+            if regressions > 0 {
+                eprintln!(
+                    "{} metric(s) regressed by more than {}%",
+                    regressions, threshold
+                );
+                std::process::exit(2);
+            }
+        }
+        Commands::Sweep {
+            device,
+            workload,
+            qd,
+            bs,
+            threads,
+            duration,
+            ramp_time,
+            csv,
+            set_queue,
+            allow_destructive,
+        } => {
+            let workload: crate::config::Workload = workload.parse()?;
+            let space = optimizer::search::SearchSpace {
+                block_sizes: match bs.as_deref() {
+                    Some(bs) => crate::config::parse_sweep_list(bs, crate::config::parse_block_size)?,
+                    None => vec![workload.default_block_size()],
+                },
+                queue_depths: crate::config::parse_sweep_list(&qd, parse_count)?,
+                threads: crate::config::parse_sweep_list(&threads, parse_count)?,
+                jobs: vec![1],
+            };
+            let mut base =
+                search_base_config(&device, workload, duration, ramp_time, allow_destructive)?;
+            if let Some(set_queue) = set_queue {
+                base.set_queue = set_queue.parse()?;
+            }
+            println!(
+                "Sweeping {} points on {:?} ({:?} each)",
+                space.num_points(),
+                base.devices,
+                base.duration + base.ramp_time
+            );
+
+            let points = sweep::run_sweep(&base, space)?;
+            let knees = sweep::find_knees(&points);
+            sweep::print_sweep(&points, &knees);
+            if let Some(path) = csv {
+                sweep::write_csv(&path, &points, &knees)?;
+                println!("CSV written to {}", path.display());
+            }
+        }
+        Commands::Optimize {
+            device,
+            workload,
+            strategy,
+            objective,
+            constraints,
+            qd,
+            bs,
+            threads,
+            jobs,
+            duration,
+            ramp_time,
+            population,
+            generations,
+            mutation_rate,
+            crossover_rate,
+            patience,
+            temperature,
+            cooling_rate,
+            steps,
+            seed,
+            set_queue,
+            tune_rounds,
+            tune_queue,
+            no_save_profile,
+            profile_dir,
+            allow_destructive,
+        } => {
+            let workload: crate::config::Workload = workload.parse()?;
+            let strategy: optimizer::SearchStrategy = strategy.parse()?;
+            let mut objective: optimizer::objective::Objective = objective.parse()?;
+            for constraint in &constraints {
+                objective.constraints.push(constraint.parse()?);
+            }
+            let space = optimizer::search::SearchSpace {
+                block_sizes: crate::config::parse_sweep_list(&bs, crate::config::parse_block_size)?,
+                queue_depths: crate::config::parse_sweep_list(&qd, parse_count)?,
+                threads: crate::config::parse_sweep_list(&threads, parse_count)?,
+                jobs: crate::config::parse_sweep_list(&jobs, parse_count)?,
+            };
+            let mut base =
+                search_base_config(&device, workload, duration, ramp_time, allow_destructive)?;
+            if let Some(set_queue) = set_queue {
+                base.set_queue = set_queue.parse()?;
+            }
+            if tune_queue && tune_rounds == 0 {
+                return Err(anyhow::anyhow!("--tune-queue needs --tune-rounds"));
+            }
+            let rng = match seed {
+                Some(seed) => rand::rngs::StdRng::seed_from_u64(seed),
+                None => rand::rngs::StdRng::from_entropy(),
+            };
+            let genetic = optimizer::genetic::GeneticSettings {
+                population,
+                max_generations: generations,
+                mutation_rate,
+                crossover_rate,
+                patience: patience.unwrap_or(3),
+            };
+            let annealing = optimizer::annealing::AnnealingSettings {
+                initial_temperature: temperature,
+                cooling_rate,
+                max_steps: steps,
+                patience: patience.unwrap_or(15),
+                ..Default::default()
+            };
+            if temperature <= 0.0 || !(0.0..1.0).contains(&cooling_rate) {
+                return Err(anyhow::anyhow!(
+                    "--temperature must be positive and --cooling-rate within 0..1"
+                ));
+            }
+            println!(
+                "Optimizing {} on {:?}: {} search over {} points ({:?} per trial)",
+                objective.describe(),
+                base.devices,
+                strategy,
+                space.num_points(),
+                base.duration + base.ramp_time
+            );
+
+            let mut search = optimizer::search::SearchEngine::new(strategy)
+                .with_space(space)
+                .with_objective(objective.clone())
+                .with_genetic(genetic, rng.clone())
+                .with_annealing(annealing, rng);
+            let mut trials = optimizer::trial::run_search(&base, &mut search)?;
+            if tune_rounds > 0 {
+                let mut tuner = optimizer::ParameterTuner::new();
+                if tune_queue {
+                    tuner = tuner.with_queue_tuning();
+                }
+                println!("Refining the best parameters for {} trials", tune_rounds);
+                optimizer::trial::run_tuning(&base, &mut search, tuner, tune_rounds, &mut trials)?;
+            }
+            let front = search.pareto_front();
+            optimizer::trial::print_search(&trials, &front, &objective);
+
+            let best = search.best_params().and_then(|params| {
+                trials
+                    .iter()
+                    .filter(|t| t.params == *params)
+                    .max_by(|a, b| a.evaluation.score.total_cmp(&b.evaluation.score))
+            });
+            if let (Some(best), false) = (best, no_save_profile) {
+                let store = profile::ProfileStore::new(match profile_dir {
+                    Some(dir) => dir,
+                    None => profile::ProfileStore::default_dir()?,
+                });
+                let keys = profile::ProfileStore::device_keys(&base.devices)?;
+                for (index, key) in keys.iter().enumerate() {
+                    // Devices of the same kind share one profile
+                    if keys[..index].contains(key) {
+                        continue;
+                    }
+                    let tuned = profile::TunedProfile::new(
+                        workload,
+                        objective.describe(),
+                        &best.params,
+                        &best.results,
+                        &base.devices[index],
+                    );
+                    let path = store.save(key, tuned)?;
+                    println!("Saved profile for {} to {}", key, path.display());
+                }
+            }
+        }
+        Commands::List {
+            all,
+            json,
+            kind,
+            transport,
+            name,
+            unused,
+        } => {
+            let kinds = match kind {
+                Some(kind) => kind
+                    .split(',')
+                    .map(str::parse)
+                    .collect::<anyhow::Result<Vec<BlockKind>>>()?,
+                None => Vec::new(),
+            };
+            let name = name
+                .map(|pattern| glob::Pattern::new(&pattern))
+                .transpose()
+                .map_err(|e| anyhow::anyhow!("Invalid --name pattern: {}", e))?;
+            let filter = ListFilter {
+                all,
+                kinds,
+                transport,
+                name,
+                unused,
+            };
+            list_devices(&filter, json).await?;
         }
         Commands::Info => {
             show_system_info().await?;
@@ -119,44 +895,359 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
-async fn run_benchmark(config: Config) -> anyhow::Result<()> {
+/// Parse a plain count in a sweep list
+fn parse_count(s: &str) -> anyhow::Result<usize> {
+    s.trim()
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid number: {}", s))
+}
+
+/// Configuration that sweeps and searches vary the parameters of
+fn search_base_config(
+    device: &[String],
+    workload: crate::config::Workload,
+    duration: u64,
+    ramp_time: u64,
+    allow_destructive: bool,
+) -> anyhow::Result<Config> {
+    if workload == crate::config::Workload::All {
+        return Err(anyhow::anyhow!("needs a single workload, not 'all'"));
+    }
+    Ok(Config {
+        devices: crate::config::expand_devices(device)?,
+        workload,
+        block_size: workload.default_block_size(),
+        queue_depth: 1,
+        threads: 1,
+        duration: std::time::Duration::from_secs(duration),
+        ramp_time: std::time::Duration::from_secs(ramp_time),
+        allow_destructive,
+        ..Default::default()
+    })
+}
+
+fn print_config(config: &Config) {
+    println!("Starting benchmark...");
+    if config.devices.len() == 1 {
+        println!("Device: {:?}", config.devices[0]);
+    } else {
+        println!("Devices ({}):", config.devices.len());
+        for device in &config.devices {
+            println!("  {}", device.display());
+        }
+    }
+    println!("Workload: {:?}", config.workload);
+    println!("Block size: {}", config.block_size);
+    println!("Queue depth: {}", config.queue_depth);
+    println!("Threads: {} per device", config.threads);
+    println!("Duration: {:?}", config.duration);
+    if !config.ramp_time.is_zero() {
+        println!("Ramp time: {:?}", config.ramp_time);
+    }
+    if let Some(policy) = config.numa {
+        println!("NUMA: {}", policy);
+    }
+    if let Some(cpus) = &config.cpus {
+        println!("CPUs: {:?} ({})", cpus, config.cpu_mapping);
+    }
+    println!("Buffer memory: {}", config.memory_mode);
+    if config.buffered {
+        println!("Direct I/O: off (buffered)");
+    }
+    if config.sync != crate::config::SyncMode::None {
+        println!("Sync: {}", config.sync);
+    }
+    if let Some(size) = config.size {
+        println!("Size: {} per target ({} layout)", size, config.file_layout);
+    }
+    if let Some(format) = &config.filename_format {
+        println!("Files: one per worker, named {}", format);
+    }
+    if !config.set_queue.is_empty() {
+        println!("Queue settings: {}", config.set_queue);
+    }
+    if let Some(rate) = config.rate {
+        println!("Rate limit: {} per worker", rate);
+    }
+    if let Some(read_percent) = config.read_percent {
+        println!("Read mix: {}%", read_percent);
+    }
+    if let Some(split) = &config.block_size_split() {
+        println!("Block size split: {}", split);
+    }
+    if let Some(mix) = &config.op_mix {
+        println!("Operation mix: {}", mix);
+    }
+    if config.trim_before {
+        println!("Trim before run: yes");
+    }
+    if config.workload == crate::config::Workload::Wal {
+        println!("WAL: {}", config.wal);
+    }
+    if let Some(flush) = config.flush {
+        let linked = if config.link_flush { " (linked)" } else { "" };
+        println!("Flush: {}{}", flush, linked);
+    }
+    if config.distribution != crate::config::AccessDistribution::Uniform {
+        println!("Random distribution: {}", config.distribution);
+    }
+    for job in &config.jobs {
+        println!("Job: {:?}", job);
+    }
+    println!("I/O Engine: io_uring");
+}
+
+async fn run_benchmark(config: Config, print_text: bool) -> anyhow::Result<BenchmarkResults> {
     let engine = IoEngine::new(config.clone())?;
     let results = engine.run()?;
 
-    print_results(&results);
+    if print_text {
+        print_results(&results);
+    }
 
-    Ok(())
+    Ok(results)
 }
 
-async fn list_devices() -> anyhow::Result<()> {
-    let devices = Device::list_devices()?;
+/// How often to repeat each benchmark
+#[derive(Debug, Clone, Copy)]
+struct Trials {
+    repeat: usize,
+    cooldown: std::time::Duration,
+    max_cv: f64,
+}
 
-    println!("Available storage devices:\n");
+/// Run the benchmark `repeat` times, idling for the cooldown in between, and
+/// summarize the trials when there is more than one
+async fn run_trials(
+    config: Config,
+    trials: Trials,
+    print_text: bool,
+) -> anyhow::Result<Vec<BenchmarkResults>> {
+    let nr_requests = config.set_queue.get("nr_requests").and_then(|n| n.parse::<usize>().ok());
+    if let Some(nr_requests) = nr_requests {
+        let in_flight = config.queue_depth * config.threads;
+        if nr_requests < in_flight {
+            eprintln!(
+                "Warning: nr_requests={} is below the {} I/Os in flight per device; the rest wait in the block layer",
+                nr_requests, in_flight
+            );
+        }
+    }
+    // Restored when dropped, so errors put the settings back too
+    let tuning = if config.set_queue.is_empty() {
+        None
+    } else {
+        let tuning = crate::io::queue::QueueTuning::apply(&config.devices, &config.set_queue)?;
+        if print_text {
+            for change in tuning.changes() {
+                println!(
+                    "Queue: {} {} = {} (was {})",
+                    change.disk, change.attribute, change.applied, change.original
+                );
+            }
+        }
+        Some(tuning)
+    };
+
+    let mut results = Vec::with_capacity(trials.repeat);
+    for trial in 1..=trials.repeat {
+        if trial > 1 && !trials.cooldown.is_zero() {
+            if print_text {
+                println!("Cooling down for {:?}...", trials.cooldown);
+            }
+            tokio::time::sleep(trials.cooldown).await;
+        }
+        if print_text && trials.repeat > 1 {
+            println!("\n=== Trial {}/{} ===", trial, trials.repeat);
+        }
+        results.push(run_benchmark(config.clone(), print_text).await?);
+    }
+
+    if let Some(tuning) = tuning {
+        let changes = tuning.restore();
+        if print_text {
+            for change in &changes {
+                println!(
+                    "Queue: {} {} restored to {}",
+                    change.disk,
+                    change.attribute,
+                    change.restored.as_deref().unwrap_or("?")
+                );
+            }
+        }
+        for result in &mut results {
+            result.queue_changes = changes.clone();
+        }
+    }
+
+    if print_text && trials.repeat > 1 {
+        print_trial_summary(&results, trials.max_cv);
+    }
+    Ok(results)
+}
+
+/// Mean, spread and 95% confidence interval of each metric over repeated trials
+fn print_trial_summary(results: &[BenchmarkResults], max_cv: f64) {
+    println!("\n{}", "=".repeat(70));
+    println!("Summary of {} trials", results.len());
+    println!("{}", "=".repeat(70));
     println!(
-        "{:<20} {:<15} {:<30} {:<15} {:<20}",
-        "Device", "Size (GB)", "Model", "Type", "Link Speed"
+        "  {:<14} {:>12} {:>10} {:>7}  95% CI",
+        "Metric", "Mean", "Stddev", "CV"
     );
-    println!("{}", "-".repeat(100));
-
-    for device in devices {
-        let size_gb = device.size as f64 / (1024.0 * 1024.0 * 1024.0);
-        let model = device.model.as_deref().unwrap_or("N/A");
-        let device_type = device.device_type.as_deref().unwrap_or("Unknown");
-        let link_speed = device.link_speed.as_deref().unwrap_or("N/A");
+    let mut unstable = false;
+    for metric in &output::METRICS {
+        let values: Vec<f64> = results.iter().map(|r| (metric.value)(r)).collect();
+        let summary = stats::Summary::of(&values);
+        if summary.mean == 0.0 {
+            continue;
+        }
+        let flagged = summary.cv_percent > max_cv;
+        unstable |= flagged;
+        println!(
+            "  {:<14} {:>12.2} {:>10.2} {:>6.2}% [{:.2}, {:.2}]{}{}",
+            metric.name,
+            summary.mean,
+            summary.stddev,
+            summary.cv_percent,
+            summary.ci_low,
+            summary.ci_high,
+            metric.unit,
+            if flagged { "  <- UNSTABLE" } else { "" }
+        );
+    }
 
+    // Trials far from the typical one point at interference (GC, thermal throttling, ...)
+    let iops: Vec<f64> = results.iter().map(|r| r.iops).collect();
+    let mut sorted = iops.clone();
+    sorted.sort_by(f64::total_cmp);
+    let median = sorted[sorted.len() / 2];
+    println!("\n  {:<8} {:>12} {:>10}", "Trial", "IOPS", "vs median");
+    for (i, value) in iops.iter().enumerate() {
+        let deviation = if median > 0.0 {
+            (value - median) / median * 100.0
+        } else {
+            0.0
+        };
+        println!(
+            "  {:<8} {:>12.0} {:>+9.2}%{}",
+            i + 1,
+            value,
+            deviation,
+            if deviation.abs() > 2.0 * max_cv {
+                "  <- outlier"
+            } else {
+                ""
+            }
+        );
+    }
+    if unstable {
         println!(
-            "{:<20} {:<15.2} {:<30} {:<15} {:<20}",
-            device.path.display(),
-            size_gb,
-            model,
-            device_type,
-            link_speed
+            "\nWARNING: results vary by more than {}% between trials; consider longer runs, \
+             a ramp time or a cooldown",
+            max_cv
         );
     }
+    println!("{}", "=".repeat(70));
+}
 
+/// Emit the json/fio-json document, to a file or stdout
+fn write_output(
+    format: output::OutputFormat,
+    path: Option<&std::path::Path>,
+    results: &[BenchmarkResults],
+) -> anyhow::Result<()> {
+    if format == output::OutputFormat::Normal {
+        return Ok(());
+    }
+    let document = output::render(format, results)?;
+    match path {
+        Some(path) => {
+            std::fs::write(path, document + "\n")?;
+            println!("Results written to {} ({})", path.display(), format);
+        }
+        None => println!("{}", document),
+    }
+    Ok(())
+}
+
+async fn list_devices(filter: &ListFilter, json: bool) -> anyhow::Result<()> {
+    let devices = filter.apply(blockdev::list(std::path::Path::new(SYSFS_ROOT))?);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&devices)?);
+        return Ok(());
+    }
+    if devices.is_empty() {
+        println!("No matching block devices");
+        return Ok(());
+    }
+
+    println!(
+        "{:<24} {:>9} {:<6} {:<7} {:<4} {:<28} {:<20} {:<10} MOUNT/USE",
+        "NAME", "SIZE", "TYPE", "TRAN", "ROTA", "MODEL", "SERIAL", "FIRMWARE"
+    );
+    for device in &devices {
+        print_device_tree(device, "", "");
+    }
     Ok(())
 }
 
+/// One `list` row per device, children indented below it
+fn print_device_tree(device: &BlockDevice, prefix: &str, child_prefix: &str) {
+    let name = match &device.label {
+        Some(label) if device.kind != BlockKind::Loop && device.kind != BlockKind::Md => {
+            format!("{}{} ({})", prefix, device.name, label)
+        }
+        _ => format!("{}{}", prefix, device.name),
+    };
+    let size = device.size as f64;
+    let size = if size >= 1024f64.powi(4) {
+        format!("{:.1}T", size / 1024f64.powi(4))
+    } else if size >= 1024f64.powi(3) {
+        format!("{:.1}G", size / 1024f64.powi(3))
+    } else {
+        format!("{:.1}M", size / 1024f64.powi(2))
+    };
+    let kind = match (&device.label, device.kind) {
+        (Some(level), BlockKind::Md) => level.clone(),
+        _ => device.kind.to_string(),
+    };
+    let rotational = match device.rotational {
+        Some(true) => "yes",
+        Some(false) => "no",
+        None => "",
+    };
+    let usage = match (&device.mount_point, device.kind) {
+        (Some(mount_point), _) => mount_point.clone(),
+        (None, BlockKind::Loop) => device.label.clone().unwrap_or_default(),
+        _ if !device.holders.is_empty() => format!("held by {}", device.holders.join(",")),
+        _ if device.in_use => "in use".to_string(),
+        _ => String::new(),
+    };
+    println!(
+        "{:<24} {:>9} {:<6} {:<7} {:<4} {:<28} {:<20} {:<10} {}",
+        name,
+        size,
+        kind,
+        device.transport.as_deref().unwrap_or(""),
+        rotational,
+        device.model.as_deref().unwrap_or(""),
+        device.serial.as_deref().unwrap_or(""),
+        device.firmware.as_deref().unwrap_or(""),
+        usage
+    );
+
+    for (i, child) in device.children.iter().enumerate() {
+        let last = i + 1 == device.children.len();
+        let (branch, indent) = if last { ("└─", "  ") } else { ("├─", "│ ") };
+        print_device_tree(
+            child,
+            &format!("{}{}", child_prefix, branch),
+            &format!("{}{}", child_prefix, indent),
+        );
+    }
+}
+
 async fn show_system_info() -> anyhow::Result<()> {
     use crate::monitor::{CpuMonitor, MemoryMonitor, NumaMonitor};
 
@@ -202,7 +1293,11 @@ async fn show_system_info() -> anyhow::Result<()> {
 
     // Device Info
     println!("\nStorage Devices:");
-    let devices = Device::list_devices()?;
+    let devices = ListFilter {
+        kinds: vec![BlockKind::Disk],
+        ..Default::default()
+    }
+    .apply(blockdev::list(std::path::Path::new(SYSFS_ROOT))?);
     for device in devices {
         let size_gb = device.size as f64 / (1024.0 * 1024.0 * 1024.0);
         println!("  {}: {:.2} GB", device.path.display(), size_gb);
@@ -217,6 +1312,9 @@ fn print_results(results: &BenchmarkResults) {
     println!("{}", "=".repeat(70));
 
     println!("\nDuration: {:.2} seconds", results.duration.as_secs_f64());
+    if !results.open_flags.is_empty() {
+        println!("Open flags: {}", results.open_flags);
+    }
     println!("\nOperations:");
     println!("  Total operations: {}", results.total_ops);
     println!("  Failed operations: {}", results.failed_ops);
@@ -233,11 +1331,60 @@ fn print_results(results: &BenchmarkResults) {
         results.throughput_write_mbps,
         results.throughput_write_mbps / 1024.0
     );
+    if results.trim_ops > 0 {
+        println!(
+            "  Trim:  {:.2} MB/s ({:.0} ops/s)",
+            results.throughput_trim_mbps,
+            results.trim_ops as f64 / results.duration.as_secs_f64()
+        );
+    }
 
     println!("\nLatency:");
     println!("  Average: {:.2} μs", results.avg_latency_us);
     println!("  Min:     {:.2} μs", results.min_latency_us);
     println!("  Max:     {:.2} μs", results.max_latency_us);
+    println!("  p50:     {:.2} μs", results.p50_latency_us);
+    println!("  p90:     {:.2} μs", results.p90_latency_us);
+    println!("  p99:     {:.2} μs", results.p99_latency_us);
+    println!("  p99.9:   {:.2} μs", results.p999_latency_us);
+    if results.trim_latency.count > 0 {
+        println!(
+            "  Trim:    p50 {:.2} μs, p99 {:.2} μs",
+            results.trim_latency.percentile_us(50.0),
+            results.trim_latency.percentile_us(99.0)
+        );
+    }
+
+    if results.flush_ops > 0 {
+        let flush = &results.flush_latency;
+        println!("\nFlush:");
+        println!(
+            "  Flushes: {} ({:.0}/s)",
+            results.flush_ops,
+            results.flush_ops as f64 / results.duration.as_secs_f64()
+        );
+        println!("  Average: {:.2} μs", flush.mean_ns() / 1000.0);
+        println!("  p50:     {:.2} μs", flush.percentile_us(50.0));
+        println!("  p99:     {:.2} μs", flush.percentile_us(99.0));
+        println!("  p99.9:   {:.2} μs", flush.percentile_us(99.9));
+        println!("  Max:     {:.2} μs", flush.max_ns() as f64 / 1000.0);
+    }
+
+    if results.commit_latency.count > 0 {
+        let commit = &results.commit_latency;
+        println!("\nCommits:");
+        println!(
+            "  Commits: {} ({:.0}/s, {:.1} KiB each on average)",
+            commit.count,
+            commit.count as f64 / results.duration.as_secs_f64(),
+            results.total_bytes_written as f64 / commit.count as f64 / 1024.0
+        );
+        println!("  p50:     {:.2} μs", commit.percentile_us(50.0));
+        println!("  p90:     {:.2} μs", commit.percentile_us(90.0));
+        println!("  p99:     {:.2} μs", commit.percentile_us(99.0));
+        println!("  p99.9:   {:.2} μs", commit.percentile_us(99.9));
+        println!("  Max:     {:.2} μs", commit.max_ns() as f64 / 1000.0);
+    }
 
     println!("\nData:");
     println!(
@@ -250,6 +1397,167 @@ fn print_results(results: &BenchmarkResults) {
         results.total_bytes_written,
         results.total_bytes_written as f64 / 1e9
     );
+    if results.total_bytes_trimmed > 0 {
+        println!(
+            "  Bytes trimmed: {} ({:.2} GB)",
+            results.total_bytes_trimmed,
+            results.total_bytes_trimmed as f64 / 1e9
+        );
+    }
+
+    if !results.jobs.is_empty() {
+        println!("\nPer-job:");
+        println!(
+            "  {:<16} {:<10} {:>8} {:>5} {:>12} {:>12} {:>12} {:>12}",
+            "Job", "Workload", "BS", "QD", "IOPS", "MB/s", "p50 μs", "p99 μs"
+        );
+        for job in &results.jobs {
+            println!(
+                "  {:<16} {:<10} {:>8} {:>5} {:>12.0} {:>12.2} {:>12.2} {:>12.2}",
+                job.name,
+                job.config.workload.name(),
+                job.config.block_size,
+                job.config.queue_depth,
+                job.iops,
+                job.throughput_read_mbps + job.throughput_write_mbps,
+                job.p50_latency_us,
+                job.p99_latency_us
+            );
+        }
+    }
+
+    if results.targets.len() > 1 {
+        println!("\nPer-target:");
+        println!(
+            "  {:<24} {:>12} {:>12} {:>12} {:>12}",
+            "Device", "IOPS", "Read MB/s", "Write MB/s", "Avg lat μs"
+        );
+        for target in &results.targets {
+            println!(
+                "  {:<24} {:>12.0} {:>12.2} {:>12.2} {:>12.2}{}",
+                target.path.display(),
+                target.iops,
+                target.throughput_read_mbps,
+                target.throughput_write_mbps,
+                target.avg_latency_us,
+                if target.outlier { "  <- outlier" } else { "" }
+            );
+        }
+    }
+
+    if !results.disk_util.is_empty() {
+        println!("\nDisk utilization:");
+        for disk in &results.disk_util {
+            println!(
+                "  {:<12} util={:.2}% ios={}/{} merges={}/{} in_queue={}ms",
+                disk.name,
+                disk.util,
+                disk.stats.read_ios,
+                disk.stats.write_ios,
+                disk.stats.read_merges,
+                disk.stats.write_merges,
+                disk.stats.time_in_queue
+            );
+        }
+    }
+
+    let devices: Vec<_> = results
+        .targets
+        .iter()
+        .filter_map(|t| t.device.as_ref())
+        .collect();
+    if !devices.is_empty() {
+        println!("\nDevice queues:");
+        for device in devices {
+            println!("  {}: {}", device.path.display(), device.queue);
+        }
+    }
+
+    if !results.health.is_empty() {
+        println!("\nDevice health:");
+        for health in &results.health {
+            let temperature = |snapshot: &crate::monitor::health::HealthSnapshot| {
+                snapshot
+                    .smart
+                    .as_ref()
+                    .map(|smart| smart.temperature_c as f64)
+                    .or_else(|| snapshot.temperatures.first().map(|t| t.celsius))
+            };
+            let mut line = format!("  {}:", health.disk);
+            if let (Some(before), Some(after)) =
+                (temperature(&health.before), temperature(&health.after))
+            {
+                line += &format!(" temperature {:.0}°C -> {:.0}°C", before, after);
+            }
+            if health.after.smart.is_none() && health.disk.starts_with("nvme") {
+                line += " (SMART log not readable)";
+            }
+            let counters = health
+                .changes
+                .iter()
+                .filter(|c| {
+                    !c.name.starts_with("temperature.") && c.name != "smart.temperature_c"
+                })
+                .count();
+            line += &format!(" {} attribute(s) changed", counters);
+            println!("{}", line);
+            for warning in &health.warnings {
+                println!("    WARNING: {}", warning);
+            }
+        }
+    }
+
+    println!("\nBuffers:");
+    println!("  Requested memory: {}", results.memory_mode_requested);
+    println!("  Used memory:      {}", summarize_memory_modes(&results.memory_modes));
+
+    if let Some(numa) = &results.numa {
+        if numa.num_nodes > 1 || numa.policy.is_some() {
+            println!("\nNUMA:");
+            match numa.policy {
+                Some(policy) => println!("  Policy:        {}", policy),
+                None => println!("  Policy:        none (unpinned)"),
+            }
+            println!("  Device nodes:  {:?}", numa.device_nodes);
+            println!("  Worker nodes:  {:?}", numa.worker_nodes);
+            if numa.cross_node {
+                println!("  WARNING: cross-node placement detected");
+            }
+        }
+
+        if numa.worker_cpus.iter().any(|cpus| !cpus.is_empty()) {
+            println!("\nCPU affinity:");
+            for (i, (cpus, node)) in numa
+                .worker_cpus
+                .iter()
+                .zip(numa.worker_nodes.iter())
+                .enumerate()
+            {
+                let node = node.map_or("?".to_string(), |n| n.to_string());
+                println!("  Worker {:<3} CPUs {:?} (node {})", i, cpus, node);
+            }
+        }
+    }
 
     println!("{}", "=".repeat(70));
 }
+
+/// Collapse per-worker memory modes into e.g. "hugepage" or "hugepage x3, anon x1"
+fn summarize_memory_modes(modes: &[crate::config::MemoryMode]) -> String {
+    let mut counts: Vec<(crate::config::MemoryMode, usize)> = Vec::new();
+    for mode in modes {
+        match counts.iter_mut().find(|(m, _)| m == mode) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((*mode, 1)),
+        }
+    }
+    match counts.as_slice() {
+        [] => "n/a".to_string(),
+        [(mode, _)] => mode.to_string(),
+        _ => counts
+            .iter()
+            .map(|(mode, count)| format!("{} x{}", mode, count))
+            .collect::<Vec<_>>()
+            .join(", "),
+    }
+}
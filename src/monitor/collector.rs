@@ -1,7 +1,7 @@
 use crate::monitor::{CpuMonitor, MemoryMonitor, NumaMonitor, IoStatsMonitor};
 use crate::monitor::cpu::CpuMetrics;
 use crate::monitor::memory::MemoryMetrics;
-use crate::monitor::numa::NumaMetrics;
+use crate::monitor::numa::{device_numa_node, NumaMetrics};
 use crate::monitor::io_stats::IoStats;
 use std::path::PathBuf;
 use std::time::Duration;
@@ -12,7 +12,7 @@ pub enum Bottleneck {
     CpuBound { utilization: f32, cores: Vec<usize> },
     MemoryBound { utilization: f32, available_bytes: u64 },
     IoBound { queue_depth: usize, latency_p99: Duration },
-    NumaBound { cross_node_access: bool },
+    NumaBound { cross_node_access: bool, device_node: usize, current_node: usize },
     Balanced,
 }
 
@@ -32,15 +32,18 @@ pub struct MonitorCollector {
     memory_monitor: MemoryMonitor,
     numa_monitor: NumaMonitor,
     io_monitor: Option<IoStatsMonitor>,
+    device_node: Option<usize>,
 }
 
 impl MonitorCollector {
     pub fn new(device_path: Option<PathBuf>) -> Self {
+        let device_node = device_path.as_deref().and_then(device_numa_node);
         Self {
             cpu_monitor: CpuMonitor::new(),
             memory_monitor: MemoryMonitor::new(),
             numa_monitor: NumaMonitor::default(),
             io_monitor: device_path.map(IoStatsMonitor::new),
+            device_node,
         }
     }
     
@@ -109,12 +112,17 @@ impl MonitorCollector {
             }
         }
         
-        // NUMA bottleneck detection
+        // NUMA bottleneck detection: running on a different node than the device
         if numa.num_nodes > 1 {
-            // TODO: Implement cross-node access detection
-            return Bottleneck::NumaBound {
-                cross_node_access: false,
-            };
+            if let (Some(device_node), Some(current_node)) = (self.device_node, numa.current_node) {
+                if device_node != current_node {
+                    return Bottleneck::NumaBound {
+                        cross_node_access: true,
+                        device_node,
+                        current_node,
+                    };
+                }
+            }
         }
         
         Bottleneck::Balanced
@@ -141,10 +149,13 @@ impl MonitorCollector {
                     "Consider increasing queue depth or reducing block size".to_string(),
                 ]
             }
-            Bottleneck::NumaBound { .. } => {
+            Bottleneck::NumaBound { device_node, current_node, .. } => {
                 vec![
-                    "NUMA cross-node access detected".to_string(),
-                    "Consider binding threads to specific NUMA nodes".to_string(),
+                    format!(
+                        "NUMA cross-node access detected: device on node {}, running on node {}",
+                        device_node, current_node
+                    ),
+                    "Consider --numa=auto to pin workers to the device's node".to_string(),
                 ]
             }
             Bottleneck::Balanced => {
@@ -0,0 +1,583 @@
+use crate::io::device::read_string;
+use crate::monitor::io_stats::IoStats;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+
+// NVME_IOCTL_ADMIN_CMD: _IOWR('N', 0x41, struct nvme_passthru_cmd)
+const NVME_IOCTL_ADMIN_CMD: libc::c_ulong = 0xC048_4E41;
+const NVME_ADMIN_GET_LOG_PAGE: u8 = 0x02;
+const NVME_LOG_SMART: u32 = 0x02;
+const SMART_LOG_LEN: usize = 512;
+
+// Device attributes longer than this are identity strings or binary pages,
+// not counters worth tracking
+const MAX_ATTRIBUTE_LEN: usize = 64;
+
+/// struct nvme_passthru_cmd from linux/nvme_ioctl.h
+#[repr(C)]
+#[derive(Default)]
+struct NvmePassthruCmd {
+    opcode: u8,
+    flags: u8,
+    rsvd1: u16,
+    nsid: u32,
+    cdw2: u32,
+    cdw3: u32,
+    metadata: u64,
+    addr: u64,
+    metadata_len: u32,
+    data_len: u32,
+    cdw10: u32,
+    cdw11: u32,
+    cdw12: u32,
+    cdw13: u32,
+    cdw14: u32,
+    cdw15: u32,
+    timeout_ms: u32,
+    result: u32,
+}
+
+const _: () = assert!(std::mem::size_of::<NvmePassthruCmd>() == 72);
+
+/// NVMe SMART / health information log page (log identifier 02h)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SmartLog {
+    /// Bit field: 0 spare, 1 temperature, 2 reliability, 3 read-only, 4 volatile backup
+    pub critical_warning: u8,
+    pub temperature_c: i32,
+    pub available_spare: u8,
+    pub available_spare_threshold: u8,
+    pub percentage_used: u8,
+    /// In units of 512,000 bytes
+    pub data_units_read: u64,
+    pub data_units_written: u64,
+    pub host_read_commands: u64,
+    pub host_write_commands: u64,
+    /// Minutes
+    pub controller_busy_time: u64,
+    pub power_cycles: u64,
+    pub power_on_hours: u64,
+    pub unsafe_shutdowns: u64,
+    pub media_errors: u64,
+    pub error_log_entries: u64,
+    /// Minutes spent above the warning and critical composite temperatures
+    pub warning_temp_time: u32,
+    pub critical_temp_time: u32,
+    /// Times the controller entered light (TMT1) and heavy (TMT2) thermal throttling
+    pub thermal_transitions_light: u32,
+    pub thermal_transitions_heavy: u32,
+    /// Seconds spent throttled at each level
+    pub thermal_time_light: u32,
+    pub thermal_time_heavy: u32,
+}
+
+impl SmartLog {
+    /// Read the log through the admin passthrough ioctl. Needs CAP_SYS_ADMIN;
+    /// None when the device is not NVMe or the command is refused.
+    pub fn read(device: &Path) -> Option<Self> {
+        let file = File::open(device).ok()?;
+        let mut data = vec![0u8; SMART_LOG_LEN];
+        let mut cmd = NvmePassthruCmd {
+            opcode: NVME_ADMIN_GET_LOG_PAGE,
+            nsid: 0xffff_ffff,
+            addr: data.as_mut_ptr() as u64,
+            data_len: SMART_LOG_LEN as u32,
+            // Number of dwords minus one in the upper half, log identifier in the lower
+            cdw10: ((SMART_LOG_LEN as u32 / 4 - 1) << 16) | NVME_LOG_SMART,
+            ..Default::default()
+        };
+        let ret = unsafe { libc::ioctl(file.as_raw_fd(), NVME_IOCTL_ADMIN_CMD, &mut cmd) };
+        if ret != 0 {
+            return None;
+        }
+        Some(Self::parse(&data))
+    }
+
+    fn parse(data: &[u8]) -> Self {
+        let u16_at = |o: usize| u16::from_le_bytes([data[o], data[o + 1]]);
+        let u32_at = |o: usize| u32::from_le_bytes(data[o..o + 4].try_into().unwrap());
+        // 128-bit counters, saturated to 64 bits
+        let u128_at = |o: usize| {
+            let value = u128::from_le_bytes(data[o..o + 16].try_into().unwrap());
+            value.min(u64::MAX as u128) as u64
+        };
+        SmartLog {
+            critical_warning: data[0],
+            temperature_c: u16_at(1) as i32 - 273,
+            available_spare: data[3],
+            available_spare_threshold: data[4],
+            percentage_used: data[5],
+            data_units_read: u128_at(32),
+            data_units_written: u128_at(48),
+            host_read_commands: u128_at(64),
+            host_write_commands: u128_at(80),
+            controller_busy_time: u128_at(96),
+            power_cycles: u128_at(112),
+            power_on_hours: u128_at(128),
+            unsafe_shutdowns: u128_at(144),
+            media_errors: u128_at(160),
+            error_log_entries: u128_at(176),
+            warning_temp_time: u32_at(192),
+            critical_temp_time: u32_at(196),
+            thermal_transitions_light: u32_at(216),
+            thermal_transitions_heavy: u32_at(220),
+            thermal_time_light: u32_at(224),
+            thermal_time_heavy: u32_at(228),
+        }
+    }
+
+    /// Counters worth comparing across a run
+    fn counters(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("smart.critical_warning", self.critical_warning as u64),
+            ("smart.percentage_used", self.percentage_used as u64),
+            ("smart.available_spare", self.available_spare as u64),
+            ("smart.data_units_read", self.data_units_read),
+            ("smart.data_units_written", self.data_units_written),
+            ("smart.host_read_commands", self.host_read_commands),
+            ("smart.host_write_commands", self.host_write_commands),
+            ("smart.controller_busy_time", self.controller_busy_time),
+            ("smart.unsafe_shutdowns", self.unsafe_shutdowns),
+            ("smart.media_errors", self.media_errors),
+            ("smart.error_log_entries", self.error_log_entries),
+            ("smart.warning_temp_time", self.warning_temp_time as u64),
+            ("smart.critical_temp_time", self.critical_temp_time as u64),
+            (
+                "smart.thermal_transitions_light",
+                self.thermal_transitions_light as u64,
+            ),
+            (
+                "smart.thermal_transitions_heavy",
+                self.thermal_transitions_heavy as u64,
+            ),
+            ("smart.thermal_time_light", self.thermal_time_light as u64),
+            ("smart.thermal_time_heavy", self.thermal_time_heavy as u64),
+        ]
+    }
+}
+
+/// A temperature sensor reading from hwmon, in °C
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Temperature {
+    pub label: String,
+    pub celsius: f64,
+    /// Throttling (max) and shutdown (crit) thresholds, when the driver reports them
+    pub max: Option<f64>,
+    pub crit: Option<f64>,
+}
+
+/// Device-level counters at one point in time
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HealthSnapshot {
+    pub diskstats: Option<IoStats>,
+    /// Short attributes of /sys/block/<dev>/device (state, ioerr_cnt, ...)
+    pub attributes: BTreeMap<String, String>,
+    pub temperatures: Vec<Temperature>,
+    /// NVMe SMART / health log, when the admin ioctl is permitted
+    pub smart: Option<SmartLog>,
+}
+
+/// A value that moved between the two snapshots
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthChange {
+    pub name: String,
+    pub before: String,
+    pub after: String,
+}
+
+/// A disk's health before and after a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceHealth {
+    /// Kernel name of the disk (e.g., nvme0n1)
+    pub disk: String,
+    pub before: HealthSnapshot,
+    pub after: HealthSnapshot,
+    /// diskstats counters accumulated over the whole run, ramp-up included
+    pub diskstats_delta: Option<IoStats>,
+    pub changes: Vec<HealthChange>,
+    /// Throttling, errors and state changes that may explain bad numbers
+    pub warnings: Vec<String>,
+}
+
+/// Reads health snapshots of one disk
+pub struct HealthMonitor {
+    disk: String,
+    device: PathBuf,
+    sysfs_dir: PathBuf,
+}
+
+impl HealthMonitor {
+    pub fn new(sysfs: &Path, disk: &str) -> Self {
+        Self {
+            disk: disk.to_string(),
+            device: PathBuf::from("/dev").join(disk),
+            sysfs_dir: sysfs.join("class/block").join(disk),
+        }
+    }
+
+    pub fn snapshot(&self) -> HealthSnapshot {
+        let device_dir = self.sysfs_dir.join("device");
+        HealthSnapshot {
+            diskstats: read_diskstats(&self.sysfs_dir),
+            attributes: read_attributes(&device_dir),
+            temperatures: read_temperatures(&device_dir),
+            smart: if self.disk.starts_with("nvme") {
+                SmartLog::read(&self.device)
+            } else {
+                None
+            },
+        }
+    }
+
+    /// Compare a snapshot taken before the run with the current state
+    pub fn report(&self, before: HealthSnapshot) -> DeviceHealth {
+        let after = self.snapshot();
+        let diskstats_delta = after
+            .diskstats
+            .as_ref()
+            .zip(before.diskstats.as_ref())
+            .map(|(after, before)| after.since(before));
+        let changes = changes(&before, &after);
+        let warnings = warnings(&before, &after);
+        DeviceHealth {
+            disk: self.disk.clone(),
+            before,
+            after,
+            diskstats_delta,
+            changes,
+            warnings,
+        }
+    }
+}
+
+/// /sys/block/<dev>/stat, which has the same fields as /proc/diskstats
+fn read_diskstats(sysfs_dir: &Path) -> Option<IoStats> {
+    let stat = read_string(&sysfs_dir.join("stat"))?;
+    let fields: Vec<u64> = stat
+        .split_whitespace()
+        .map(|f| f.parse().unwrap_or(0))
+        .collect();
+    if fields.len() < 11 {
+        return None;
+    }
+    Some(IoStats {
+        read_ios: fields[0],
+        read_merges: fields[1],
+        read_sectors: fields[2],
+        read_ticks: fields[3],
+        write_ios: fields[4],
+        write_merges: fields[5],
+        write_sectors: fields[6],
+        write_ticks: fields[7],
+        in_flight: fields[8],
+        io_ticks: fields[9],
+        time_in_queue: fields[10],
+    })
+}
+
+fn read_attributes(device_dir: &Path) -> BTreeMap<String, String> {
+    let mut attributes = BTreeMap::new();
+    let Ok(entries) = fs::read_dir(device_dir) else {
+        return attributes;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name == "uevent" || name == "modalias" {
+            continue;
+        }
+        // Symlinks lead to other devices and subsystems
+        if !entry.file_type().map(|t| t.is_file()).unwrap_or(false) {
+            continue;
+        }
+        let Ok(value) = fs::read_to_string(entry.path()) else {
+            continue;
+        };
+        let value = value.trim();
+        if value.len() <= MAX_ATTRIBUTE_LEN && !value.contains('\n') {
+            attributes.insert(name, value.to_string());
+        }
+    }
+    attributes
+}
+
+/// hwmon sensors of the device: NVMe controllers register theirs directly,
+/// drivetemp (SATA) under a hwmon/ subdirectory
+fn read_temperatures(device_dir: &Path) -> Vec<Temperature> {
+    let mut temperatures = Vec::new();
+    let base = device_dir.to_string_lossy();
+    let patterns = [
+        format!("{}/hwmon*/temp*_input", glob::Pattern::escape(&base)),
+        format!("{}/hwmon/hwmon*/temp*_input", glob::Pattern::escape(&base)),
+    ];
+    let millidegrees = |path: PathBuf| read_string(&path)?.parse::<f64>().ok().map(|m| m / 1000.0);
+    for pattern in &patterns {
+        let Ok(inputs) = glob::glob(pattern) else {
+            continue;
+        };
+        for input in inputs.flatten() {
+            let Some(celsius) = millidegrees(input.clone()) else {
+                continue;
+            };
+            let file_name = input.file_name().unwrap_or_default().to_string_lossy();
+            let sensor = file_name.trim_end_matches("_input").to_string();
+            let sibling = |suffix: &str| input.with_file_name(format!("{}_{}", sensor, suffix));
+            temperatures.push(Temperature {
+                label: read_string(&sibling("label")).unwrap_or_else(|| sensor.clone()),
+                celsius,
+                max: millidegrees(sibling("max")),
+                crit: millidegrees(sibling("crit")),
+            });
+        }
+    }
+    temperatures
+}
+
+fn changes(before: &HealthSnapshot, after: &HealthSnapshot) -> Vec<HealthChange> {
+    let mut changes = Vec::new();
+    let mut push = |name: String, before: String, after: String| {
+        if before != after {
+            changes.push(HealthChange {
+                name,
+                before,
+                after,
+            });
+        }
+    };
+    for (name, value) in &after.attributes {
+        if let Some(earlier) = before.attributes.get(name) {
+            push(name.clone(), earlier.clone(), value.clone());
+        }
+    }
+    for temperature in &after.temperatures {
+        if let Some(earlier) = before
+            .temperatures
+            .iter()
+            .find(|t| t.label == temperature.label)
+        {
+            push(
+                format!("temperature.{}", temperature.label),
+                format!("{:.1}", earlier.celsius),
+                format!("{:.1}", temperature.celsius),
+            );
+        }
+    }
+    if let (Some(earlier), Some(smart)) = (&before.smart, &after.smart) {
+        push(
+            "smart.temperature_c".to_string(),
+            earlier.temperature_c.to_string(),
+            smart.temperature_c.to_string(),
+        );
+        for ((name, earlier), (_, value)) in earlier.counters().into_iter().zip(smart.counters()) {
+            push(name.to_string(), earlier.to_string(), value.to_string());
+        }
+    }
+    changes
+}
+
+fn warnings(before: &HealthSnapshot, after: &HealthSnapshot) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if let (Some(earlier), Some(smart)) = (&before.smart, &after.smart) {
+        let light = smart
+            .thermal_transitions_light
+            .saturating_sub(earlier.thermal_transitions_light);
+        let heavy = smart
+            .thermal_transitions_heavy
+            .saturating_sub(earlier.thermal_transitions_heavy);
+        if light > 0 || heavy > 0 {
+            warnings.push(format!(
+                "thermal throttling during the run: {} light, {} heavy transitions ({}s / {}s throttled)",
+                light,
+                heavy,
+                smart.thermal_time_light.saturating_sub(earlier.thermal_time_light),
+                smart.thermal_time_heavy.saturating_sub(earlier.thermal_time_heavy)
+            ));
+        }
+        if smart.warning_temp_time > earlier.warning_temp_time
+            || smart.critical_temp_time > earlier.critical_temp_time
+        {
+            warnings.push(format!(
+                "spent time above the warning/critical temperature (now {}°C)",
+                smart.temperature_c
+            ));
+        }
+        if smart.critical_warning != 0 {
+            warnings.push(format!(
+                "SMART critical warning 0x{:02x}",
+                smart.critical_warning
+            ));
+        }
+        if smart.media_errors > earlier.media_errors {
+            warnings.push(format!(
+                "{} new media errors",
+                smart.media_errors - earlier.media_errors
+            ));
+        }
+    }
+    for temperature in &after.temperatures {
+        if let Some(limit) = temperature.max.or(temperature.crit) {
+            if temperature.celsius >= limit {
+                warnings.push(format!(
+                    "{} at {:.1}°C, at or above its {:.1}°C limit",
+                    temperature.label, temperature.celsius, limit
+                ));
+            }
+        }
+    }
+    // Error counters (SCSI ioerr_cnt is hex) and controller state
+    for (name, value) in &after.attributes {
+        let Some(earlier) = before.attributes.get(name) else {
+            continue;
+        };
+        if name.contains("err") && value != earlier {
+            warnings.push(format!("{} went from {} to {}", name, earlier, value));
+        }
+    }
+    if let (Some(earlier), Some(state)) = (
+        before.attributes.get("state"),
+        after.attributes.get("state"),
+    ) {
+        if earlier != state {
+            warnings.push(format!(
+                "device state changed from {} to {}",
+                earlier, state
+            ));
+        }
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A SMART log page with distinct values in every field, laid out as in the
+    /// NVMe base specification
+    fn smart_log_page() -> Vec<u8> {
+        let mut data = vec![0u8; SMART_LOG_LEN];
+        let mut put = |offset: usize, bytes: &[u8]| {
+            data[offset..offset + bytes.len()].copy_from_slice(bytes);
+        };
+        put(0, &[0x04]);
+        put(1, &318u16.to_le_bytes());
+        put(3, &[97, 10, 3]);
+        for (i, offset) in (32..=176).step_by(16).enumerate() {
+            put(offset, &(1000 + i as u128).to_le_bytes());
+        }
+        // Above 64 bits: saturates
+        put(64, &(u128::MAX - 1).to_le_bytes());
+        put(192, &7u32.to_le_bytes());
+        put(196, &2u32.to_le_bytes());
+        put(216, &11u32.to_le_bytes());
+        put(220, &12u32.to_le_bytes());
+        put(224, &130u32.to_le_bytes());
+        put(228, &140u32.to_le_bytes());
+        data
+    }
+
+    fn snapshot(smart: SmartLog) -> HealthSnapshot {
+        HealthSnapshot {
+            smart: Some(smart),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn smart_log_fields_at_spec_offsets() {
+        assert_eq!(
+            SmartLog::parse(&smart_log_page()),
+            SmartLog {
+                critical_warning: 0x04,
+                temperature_c: 45,
+                available_spare: 97,
+                available_spare_threshold: 10,
+                percentage_used: 3,
+                data_units_read: 1000,
+                data_units_written: 1001,
+                host_read_commands: u64::MAX,
+                host_write_commands: 1003,
+                controller_busy_time: 1004,
+                power_cycles: 1005,
+                power_on_hours: 1006,
+                unsafe_shutdowns: 1007,
+                media_errors: 1008,
+                error_log_entries: 1009,
+                warning_temp_time: 7,
+                critical_temp_time: 2,
+                thermal_transitions_light: 11,
+                thermal_transitions_heavy: 12,
+                thermal_time_light: 130,
+                thermal_time_heavy: 140,
+            }
+        );
+    }
+
+    #[test]
+    fn unchanged_smart_log_is_quiet() {
+        let smart = SmartLog {
+            thermal_transitions_light: 4,
+            media_errors: 1,
+            ..Default::default()
+        };
+        let (before, after) = (snapshot(smart.clone()), snapshot(smart));
+        assert!(changes(&before, &after).is_empty());
+        assert!(warnings(&before, &after).is_empty());
+    }
+
+    #[test]
+    fn thermal_transitions_warn() {
+        let earlier = SmartLog {
+            thermal_transitions_light: 4,
+            thermal_time_light: 100,
+            ..Default::default()
+        };
+        let smart = SmartLog {
+            thermal_transitions_light: 6,
+            thermal_transitions_heavy: 1,
+            thermal_time_light: 130,
+            thermal_time_heavy: 5,
+            ..earlier.clone()
+        };
+        let (before, after) = (snapshot(earlier), snapshot(smart));
+        assert_eq!(
+            warnings(&before, &after),
+            ["thermal throttling during the run: 2 light, 1 heavy transitions (30s / 5s throttled)"]
+        );
+        let names: Vec<String> = changes(&before, &after)
+            .into_iter()
+            .map(|c| c.name)
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "smart.thermal_transitions_light",
+                "smart.thermal_transitions_heavy",
+                "smart.thermal_time_light",
+                "smart.thermal_time_heavy",
+            ]
+        );
+    }
+
+    #[test]
+    fn media_errors_warn() {
+        let earlier = SmartLog {
+            media_errors: 2,
+            ..Default::default()
+        };
+        let smart = SmartLog {
+            media_errors: 5,
+            ..earlier.clone()
+        };
+        let (before, after) = (snapshot(earlier), snapshot(smart));
+        assert_eq!(warnings(&before, &after), ["3 new media errors"]);
+        let changes = changes(&before, &after);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].name, "smart.media_errors");
+        assert_eq!(
+            (changes[0].before.as_str(), changes[0].after.as_str()),
+            ("2", "5")
+        );
+    }
+}
@@ -1,12 +1,15 @@
 use procfs::ProcResult;
+use serde::{Deserialize, Serialize};
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
 use std::path::PathBuf;
+use std::time::Duration;
 
 /// I/O statistics monitoring
 pub struct IoStatsMonitor {
     device_path: PathBuf,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct IoStats {
     pub read_ios: u64,
     pub read_merges: u64,
@@ -21,25 +24,55 @@ pub struct IoStats {
     pub time_in_queue: u64,
 }
 
+/// Disk activity over a run, in the shape of fio's disk_util entries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskUtil {
+    /// Kernel name of the disk (e.g., nvme0n1)
+    pub name: String,
+    /// Counter deltas over the measured interval; in_flight is the value at the end
+    pub stats: IoStats,
+    pub elapsed: Duration,
+    /// Share of the interval the disk had I/O in flight (0-100)
+    pub util: f64,
+}
+
 impl IoStatsMonitor {
     pub fn new(device_path: PathBuf) -> Self {
         Self { device_path }
     }
-    
+
+    /// Kernel name of the disk backing the path: the device itself for block
+    /// devices, the containing filesystem's device for regular files
+    pub fn disk_name(&self) -> Option<String> {
+        let metadata = std::fs::metadata(&self.device_path).ok()?;
+        let dev = if metadata.file_type().is_block_device() {
+            metadata.rdev()
+        } else {
+            metadata.dev()
+        };
+        let (major, minor) = (libc::major(dev) as i32, libc::minor(dev) as i32);
+        procfs::diskstats()
+            .ok()?
+            .into_iter()
+            .find(|entry| entry.major == major && entry.minor == minor)
+            .map(|entry| entry.name)
+    }
+
     pub fn collect(&self) -> ProcResult<IoStats> {
         // Read from /proc/diskstats
         let diskstats = procfs::diskstats()?;
-        
+
         // Find the device
-        let device_name = self.device_path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("");
-        
+        let device_name = self.disk_name().unwrap_or_else(|| {
+            self.device_path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("")
+                .to_string()
+        });
+
         for entry in diskstats {
             if entry.name == device_name {
-                // Map available fields from procfs DiskStat
-                // Note: Some fields may not be available in all procfs versions
                 return Ok(IoStats {
                     read_ios: entry.reads,
                     read_merges: entry.merged,
@@ -49,14 +82,71 @@ impl IoStatsMonitor {
                     write_merges: entry.writes_merged,
                     write_sectors: entry.sectors_written,
                     write_ticks: entry.time_writing,
-                    in_flight: 0, // TODO: Get from procfs when available
-                    io_ticks: 0,  // TODO: Get from procfs when available
-                    time_in_queue: 0, // TODO: Get from procfs when available
+                    in_flight: entry.in_progress,
+                    io_ticks: entry.time_in_progress,
+                    time_in_queue: entry.weighted_time_in_progress,
                 });
             }
         }
-        
+
         Err(procfs::ProcError::NotFound(None))
     }
 }
 
+impl IoStats {
+    /// Counters accumulated since `earlier`
+    pub fn since(&self, earlier: &IoStats) -> IoStats {
+        IoStats {
+            read_ios: self.read_ios.saturating_sub(earlier.read_ios),
+            read_merges: self.read_merges.saturating_sub(earlier.read_merges),
+            read_sectors: self.read_sectors.saturating_sub(earlier.read_sectors),
+            read_ticks: self.read_ticks.saturating_sub(earlier.read_ticks),
+            write_ios: self.write_ios.saturating_sub(earlier.write_ios),
+            write_merges: self.write_merges.saturating_sub(earlier.write_merges),
+            write_sectors: self.write_sectors.saturating_sub(earlier.write_sectors),
+            write_ticks: self.write_ticks.saturating_sub(earlier.write_ticks),
+            in_flight: self.in_flight,
+            io_ticks: self.io_ticks.saturating_sub(earlier.io_ticks),
+            time_in_queue: self.time_in_queue.saturating_sub(earlier.time_in_queue),
+        }
+    }
+}
+
+impl DiskUtil {
+    pub fn new(name: String, stats: IoStats, elapsed: Duration) -> Self {
+        let elapsed_ms = elapsed.as_millis() as f64;
+        let util = if elapsed_ms > 0.0 {
+            (stats.io_ticks as f64 / elapsed_ms * 100.0).min(100.0)
+        } else {
+            0.0
+        };
+        Self {
+            name,
+            stats,
+            elapsed,
+            util,
+        }
+    }
+
+    /// Fold in another interval on the same disk (e.g., the next workload of a sequence)
+    pub fn merge(&mut self, other: &DiskUtil) {
+        let s = &mut self.stats;
+        let o = &other.stats;
+        s.read_ios += o.read_ios;
+        s.read_merges += o.read_merges;
+        s.read_sectors += o.read_sectors;
+        s.read_ticks += o.read_ticks;
+        s.write_ios += o.write_ios;
+        s.write_merges += o.write_merges;
+        s.write_sectors += o.write_sectors;
+        s.write_ticks += o.write_ticks;
+        s.in_flight = o.in_flight;
+        s.io_ticks += o.io_ticks;
+        s.time_in_queue += o.time_in_queue;
+        *self = DiskUtil::new(
+            self.name.clone(),
+            self.stats.clone(),
+            self.elapsed + other.elapsed,
+        );
+    }
+}
@@ -2,6 +2,7 @@ pub mod cpu;
 pub mod memory;
 pub mod numa;
 pub mod io_stats;
+pub mod health;
 pub mod collector;
 
 pub use collector::{MonitorCollector, Bottleneck, BottleneckReport};
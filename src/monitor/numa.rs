@@ -1,8 +1,17 @@
+use crate::config::{CpuMapping, NumaPolicy};
 use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
 
+// Memory policy modes (from linux/mempolicy.h)
+const MPOL_PREFERRED: libc::c_int = 1;
+const MPOL_INTERLEAVE: libc::c_int = 3;
+// Upper bound on node ids covered by the nodemask passed to set_mempolicy
+const MAX_NUMA_NODES: usize = 1024;
+
 /// NUMA monitoring
+#[derive(Debug, Clone)]
 pub struct NumaMonitor {
     nodes: Vec<NumaNode>,
 }
@@ -22,6 +31,51 @@ pub struct NumaMetrics {
     pub current_node: Option<usize>,
 }
 
+/// Memory allocation policy for a worker thread's I/O buffers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemPolicy {
+    /// Prefer allocations from the given node
+    Local(usize),
+    /// Interleave allocations across all nodes
+    Interleave,
+}
+
+/// CPU and memory placement for a single worker thread
+#[derive(Debug, Clone, Default)]
+pub struct WorkerPlacement {
+    pub cpus: Vec<usize>,
+    pub mem_policy: Option<MemPolicy>,
+}
+
+impl WorkerPlacement {
+    /// Apply the placement to the calling thread. Must run before the worker
+    /// allocates its buffers so the memory policy covers them.
+    pub fn apply(&self) -> Result<()> {
+        if !self.cpus.is_empty() {
+            set_thread_affinity(&self.cpus)?;
+        }
+        if let Some(policy) = self.mem_policy {
+            set_memory_policy(policy)?;
+        }
+        Ok(())
+    }
+}
+
+/// NUMA placement actually used for a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NumaReport {
+    pub policy: Option<NumaPolicy>,
+    pub num_nodes: usize,
+    /// Home node of the device each worker drives
+    pub device_nodes: Vec<Option<usize>>,
+    /// CPUs each worker was pinned to (empty when unpinned)
+    pub worker_cpus: Vec<Vec<usize>>,
+    /// Node each worker was running on when it finished
+    pub worker_nodes: Vec<Option<usize>>,
+    /// True when any worker ran on a node other than its device's home node
+    pub cross_node: bool,
+}
+
 impl NumaMonitor {
     pub fn new() -> Result<Self> {
         let nodes = Self::detect_nodes()?;
@@ -74,19 +128,7 @@ impl NumaMonitor {
         }
 
         let content = fs::read_to_string(&cpulist_path).context("Failed to read cpulist")?;
-
-        let mut cpus = Vec::new();
-        for range in content.trim().split(',') {
-            if let Some((start, end)) = range.split_once('-') {
-                let start: usize = start.trim().parse()?;
-                let end: usize = end.trim().parse()?;
-                cpus.extend(start..=end);
-            } else {
-                cpus.push(range.trim().parse()?);
-            }
-        }
-
-        Ok(cpus)
+        parse_cpu_list(&content)
     }
 
     fn read_node_memory(node_path: &Path) -> Result<u64> {
@@ -168,25 +210,231 @@ impl NumaMonitor {
         }
     }
 
-    /// Bind current thread to a NUMA node
-    pub fn bind_to_node(&self, node_id: usize) -> Result<()> {
-        if let Some(node) = self.nodes.iter().find(|n| n.id == node_id) {
-            if let Some(&cpu) = node.cpus.first() {
-                // Use libc to set CPU affinity
-                unsafe {
-                    let mut cpuset: libc::cpu_set_t = std::mem::zeroed();
-                    libc::CPU_ZERO(&mut cpuset);
-                    libc::CPU_SET(cpu, &mut cpuset);
-                    let result =
-                        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &cpuset);
-                    if result != 0 {
-                        return Err(anyhow::anyhow!("Failed to set CPU affinity"));
+    pub fn nodes(&self) -> &[NumaNode] {
+        &self.nodes
+    }
+
+    /// Work out CPU and memory placement for each worker under a NUMA policy
+    pub fn plan_placement(
+        &self,
+        policy: NumaPolicy,
+        device_node: Option<usize>,
+        workers: usize,
+    ) -> Result<Vec<WorkerPlacement>> {
+        let node_placement = |node: &NumaNode, mem_policy| WorkerPlacement {
+            cpus: node.cpus.clone(),
+            mem_policy: Some(mem_policy),
+        };
+
+        match policy {
+            NumaPolicy::Auto => match device_node {
+                Some(id) => {
+                    let node = self.nodes.iter().find(|n| n.id == id).ok_or_else(|| {
+                        anyhow::anyhow!("Device NUMA node {} not found in topology", id)
+                    })?;
+                    Ok(vec![node_placement(node, MemPolicy::Local(id)); workers])
+                }
+                None => {
+                    eprintln!("Warning: device has no NUMA affinity, workers will not be pinned");
+                    Ok(vec![WorkerPlacement::default(); workers])
+                }
+            },
+            NumaPolicy::Node(id) => {
+                let node = self
+                    .nodes
+                    .iter()
+                    .find(|n| n.id == id)
+                    .ok_or_else(|| anyhow::anyhow!("NUMA node {} does not exist", id))?;
+                if let Some(dev) = device_node {
+                    if dev != id {
+                        eprintln!(
+                            "Warning: workers pinned to node {} but device is attached to node {}",
+                            id, dev
+                        );
                     }
                 }
+                Ok(vec![node_placement(node, MemPolicy::Local(id)); workers])
+            }
+            NumaPolicy::Interleave => {
+                let nodes: Vec<&NumaNode> =
+                    self.nodes.iter().filter(|n| !n.cpus.is_empty()).collect();
+                if nodes.is_empty() {
+                    return Ok(vec![WorkerPlacement::default(); workers]);
+                }
+                Ok((0..workers)
+                    .map(|i| node_placement(nodes[i % nodes.len()], MemPolicy::Interleave))
+                    .collect())
             }
         }
+    }
 
-        Ok(())
+    /// Map workers onto an explicit CPU list, validating it against the topology
+    pub fn plan_cpu_affinity(
+        &self,
+        cpus: &[usize],
+        mapping: CpuMapping,
+        workers: usize,
+    ) -> Result<Vec<Vec<usize>>> {
+        if cpus.is_empty() {
+            return Err(anyhow::anyhow!("CPU list is empty"));
+        }
+        if let Some(&cpu) = cpus
+            .iter()
+            .find(|&&cpu| self.get_numa_node_for_cpu(cpu).is_none())
+        {
+            return Err(anyhow::anyhow!(
+                "CPU {} is not online in this system's topology",
+                cpu
+            ));
+        }
+        for (i, cpu) in cpus.iter().enumerate() {
+            if cpus[..i].contains(cpu) {
+                return Err(anyhow::anyhow!("CPU {} listed more than once", cpu));
+            }
+        }
+
+        match mapping {
+            CpuMapping::RoundRobin => Ok((0..workers).map(|i| vec![cpus[i % cpus.len()]]).collect()),
+            CpuMapping::OneToOne => {
+                if workers > cpus.len() {
+                    return Err(anyhow::anyhow!(
+                        "one-to-one mapping needs at least {} CPUs, got {}",
+                        workers,
+                        cpus.len()
+                    ));
+                }
+                Ok(cpus[..workers].iter().map(|&cpu| vec![cpu]).collect())
+            }
+        }
+    }
+}
+
+/// Parse a kernel CPU list such as "0-7,16-23"
+pub fn parse_cpu_list(list: &str) -> Result<Vec<usize>> {
+    // Nothing past the affinity mask can be pinned to; checked before expanding ranges
+    let cpu_id = |s: &str| -> Result<usize> {
+        let cpu: usize = s
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid CPU in list: {}", s.trim()))?;
+        if cpu >= libc::CPU_SETSIZE as usize {
+            return Err(anyhow::anyhow!(
+                "CPU {} is beyond the {} CPUs an affinity mask holds",
+                cpu,
+                libc::CPU_SETSIZE
+            ));
+        }
+        Ok(cpu)
+    };
+
+    let mut cpus = Vec::new();
+    for range in list.trim().split(',').filter(|r| !r.trim().is_empty()) {
+        if let Some((start, end)) = range.split_once('-') {
+            let (start, end) = (cpu_id(start)?, cpu_id(end)?);
+            if end < start {
+                return Err(anyhow::anyhow!("CPU range {} runs backwards", range.trim()));
+            }
+            cpus.extend(start..=end);
+        } else {
+            cpus.push(cpu_id(range)?);
+        }
+    }
+
+    Ok(cpus)
+}
+
+/// Home NUMA node of a block device, read from sysfs (None if unknown or -1)
+pub fn device_numa_node(device_path: &Path) -> Option<usize> {
+    let resolved = fs::canonicalize(device_path).unwrap_or_else(|_| device_path.to_path_buf());
+    let name = resolved.file_name()?.to_str()?;
+
+    let candidates = [
+        Path::new("/sys/block").join(name).join("device/numa_node"),
+        // NVMe namespaces: device/ is the controller, device/device/ the PCI function
+        Path::new("/sys/block").join(name).join("device/device/numa_node"),
+        // Partitions: look at the parent disk
+        Path::new("/sys/class/block").join(name).join("../device/numa_node"),
+        Path::new("/sys/class/block").join(name).join("../device/device/numa_node"),
+    ];
+
+    for path in &candidates {
+        if let Ok(content) = fs::read_to_string(path) {
+            if let Ok(node) = content.trim().parse::<i64>() {
+                return if node >= 0 { Some(node as usize) } else { None };
+            }
+        }
+    }
+    None
+}
+
+/// Restrict the calling thread to the given CPUs
+pub fn set_thread_affinity(cpus: &[usize]) -> Result<()> {
+    unsafe {
+        let mut cpuset: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_ZERO(&mut cpuset);
+        for &cpu in cpus {
+            libc::CPU_SET(cpu, &mut cpuset);
+        }
+        let result = libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &cpuset);
+        if result != 0 {
+            return Err(anyhow::anyhow!(
+                "Failed to set CPU affinity to {:?}: {}",
+                cpus,
+                std::io::Error::last_os_error()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Set the memory policy of the calling thread (set_mempolicy(2))
+pub fn set_memory_policy(policy: MemPolicy) -> Result<()> {
+    const BITS: usize = std::mem::size_of::<libc::c_ulong>() * 8;
+    let mut nodemask = [0 as libc::c_ulong; MAX_NUMA_NODES / BITS];
+
+    let mode = match policy {
+        MemPolicy::Local(node) => {
+            if node >= MAX_NUMA_NODES {
+                return Err(anyhow::anyhow!("NUMA node {} out of range", node));
+            }
+            nodemask[node / BITS] |= 1 << (node % BITS);
+            MPOL_PREFERRED
+        }
+        MemPolicy::Interleave => {
+            for node in NumaMonitor::default().nodes() {
+                if node.id < MAX_NUMA_NODES {
+                    nodemask[node.id / BITS] |= 1 << (node.id % BITS);
+                }
+            }
+            MPOL_INTERLEAVE
+        }
+    };
+
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_set_mempolicy,
+            mode,
+            nodemask.as_ptr(),
+            MAX_NUMA_NODES + 1,
+        )
+    };
+    if result != 0 {
+        return Err(anyhow::anyhow!(
+            "Failed to set memory policy {:?}: {}",
+            policy,
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(())
+}
+
+/// CPU the calling thread is currently running on
+pub fn current_cpu() -> Option<usize> {
+    let cpu = unsafe { libc::sched_getcpu() };
+    if cpu >= 0 {
+        Some(cpu as usize)
+    } else {
+        None
     }
 }
 
@@ -204,3 +452,23 @@ impl Default for NumaMonitor {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ranges_and_single_cpus() {
+        assert_eq!(parse_cpu_list("0-3,8,10-11").unwrap(), vec![0, 1, 2, 3, 8, 10, 11]);
+        assert_eq!(parse_cpu_list(" 5 ").unwrap(), vec![5]);
+        assert!(parse_cpu_list("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_backwards_and_oversized_ranges() {
+        assert!(parse_cpu_list("7-0").is_err());
+        assert!(parse_cpu_list("0-99999999999").is_err());
+        assert!(parse_cpu_list(&libc::CPU_SETSIZE.to_string()).is_err());
+        assert!(parse_cpu_list("a-b").is_err());
+    }
+}
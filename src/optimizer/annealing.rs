@@ -0,0 +1,243 @@
+use super::search::SearchSpace;
+use crate::config::TestParams;
+use rand::rngs::StdRng;
+use rand::Rng;
+
+// Relative gain in the best score that counts as progress
+const IMPROVEMENT_TOLERANCE: f64 = 0.01;
+
+/// Simulated annealing settings. Temperatures are relative: at temperature T a
+/// candidate scoring a fraction d worse than the current point is accepted with
+/// probability exp(-d / T), so they don't depend on the objective's units.
+#[derive(Debug, Clone, Copy)]
+pub struct AnnealingSettings {
+    pub initial_temperature: f64,
+    /// Temperature multiplier applied after every step
+    pub cooling_rate: f64,
+    pub min_temperature: f64,
+    pub max_steps: usize,
+    /// Stop after this many steps without improving the best score
+    pub patience: usize,
+    /// Measure the current point again every this many steps, so one lucky
+    /// measurement can't hold the search in place (0 disables)
+    pub remeasure_every: usize,
+}
+
+impl Default for AnnealingSettings {
+    fn default() -> Self {
+        Self {
+            initial_temperature: 0.1,
+            cooling_rate: 0.9,
+            min_temperature: 0.001,
+            max_steps: 40,
+            patience: 15,
+            remeasure_every: 5,
+        }
+    }
+}
+
+/// Search point: block size, queue depth, threads and jobs
+type State = [usize; 4];
+
+/// Simulated annealing over a SearchSpace, driven one trial at a time like the
+/// genetic search. Queue depth and block size move by a factor of two or four,
+/// snapped to values of their lists; threads and jobs step to a neighbouring value.
+pub struct AnnealingSearch {
+    space: SearchSpace,
+    settings: AnnealingSettings,
+    rng: StdRng,
+    current: State,
+    /// Measurements so far: repeated points average out their noise
+    measured: Vec<(State, f64, usize)>,
+    pending: Option<State>,
+    temperature: f64,
+    step: usize,
+    best_score: f64,
+    stale_steps: usize,
+}
+
+impl AnnealingSearch {
+    pub fn new(space: SearchSpace, settings: AnnealingSettings, rng: StdRng) -> Self {
+        // Start in the middle of the space
+        let current = [
+            median(&space.block_sizes),
+            median(&space.queue_depths),
+            median(&space.threads),
+            median(&space.jobs),
+        ];
+        Self {
+            space,
+            settings,
+            rng,
+            current,
+            measured: Vec::new(),
+            pending: None,
+            temperature: settings.initial_temperature,
+            step: 0,
+            best_score: 0.0,
+            stale_steps: 0,
+        }
+    }
+
+    /// Steps taken so far
+    pub fn step(&self) -> usize {
+        self.step
+    }
+
+    /// Next point to evaluate, or None once the search cooled down or stalled
+    pub fn next(&mut self, base: &TestParams) -> Option<TestParams> {
+        let state = if self.score(&self.current).is_none() {
+            self.current
+        } else {
+            if self.step >= self.settings.max_steps
+                || self.temperature < self.settings.min_temperature
+                || self.stale_steps >= self.settings.patience
+            {
+                return None;
+            }
+            self.step += 1;
+            let remeasure = self.settings.remeasure_every > 0
+                && self.step.is_multiple_of(self.settings.remeasure_every);
+            if remeasure {
+                self.current
+            } else {
+                self.neighbour()
+            }
+        };
+        self.pending = Some(state);
+        Some(TestParams {
+            block_size: state[0],
+            queue_depth: state[1],
+            num_threads: state[2],
+            num_jobs: state[3],
+            ..base.clone()
+        })
+    }
+
+    /// Score of the point returned by the last `next`
+    pub fn record(&mut self, score: f64) {
+        let Some(state) = self.pending.take() else {
+            return;
+        };
+        let current_score = self.score(&self.current);
+        match self.measured.iter_mut().find(|(s, _, _)| *s == state) {
+            Some((_, sum, count)) => {
+                *sum += score;
+                *count += 1;
+            }
+            None => self.measured.push((state, score, 1)),
+        }
+        let candidate_score = self.score(&state).unwrap_or(score);
+
+        if state != self.current {
+            let current_score = current_score.unwrap_or(0.0);
+            let delta = if current_score > 0.0 {
+                (candidate_score - current_score) / current_score
+            } else {
+                1.0
+            };
+            let accept = delta >= 0.0
+                || self.rng.gen::<f64>() < (delta / self.temperature.max(f64::EPSILON)).exp();
+            if accept {
+                self.current = state;
+            }
+            self.temperature *= self.settings.cooling_rate;
+        }
+
+        if candidate_score > self.best_score * (1.0 + IMPROVEMENT_TOLERANCE) {
+            self.stale_steps = 0;
+        } else if self.step > 0 {
+            self.stale_steps += 1;
+        }
+        self.best_score = self.best_score.max(candidate_score);
+    }
+
+    /// Mean of the measurements of a point
+    fn score(&self, state: &State) -> Option<f64> {
+        self.measured
+            .iter()
+            .find(|(s, _, _)| s == state)
+            .map(|(_, sum, count)| sum / *count as f64)
+    }
+
+    /// Change one parameter of the current point
+    fn neighbour(&mut self) -> State {
+        let mut state = self.current;
+        // Hot searches take bigger steps
+        let factor = if self
+            .rng
+            .gen_bool((self.temperature / self.settings.initial_temperature).clamp(0.0, 1.0) / 2.0)
+        {
+            4
+        } else {
+            2
+        };
+        for _ in 0..8 {
+            let up = self.rng.gen_bool(0.5);
+            match self.rng.gen_range(0..4) {
+                0 => state[0] = scale(state[0], factor, up, &self.space.block_sizes),
+                1 => state[1] = scale(state[1], factor, up, &self.space.queue_depths),
+                2 => state[2] = adjacent(state[2], up, &self.space.threads),
+                _ => state[3] = adjacent(state[3], up, &self.space.jobs),
+            }
+            if state != self.current {
+                break;
+            }
+        }
+        state
+    }
+}
+
+fn median(values: &[usize]) -> usize {
+    values[values.len() / 2]
+}
+
+/// Multiply or divide by `factor`, snapped to the nearest larger or smaller value
+/// of the list so the move always lands on a size the space allows
+fn scale(value: usize, factor: usize, up: bool, values: &[usize]) -> usize {
+    let target = if up {
+        value * factor
+    } else {
+        (value / factor).max(1)
+    } as f64;
+    values
+        .iter()
+        .copied()
+        .filter(|&v| if up { v > value } else { v < value })
+        .min_by(|a, b| {
+            let distance = |v: usize| (v as f64 / target).ln().abs();
+            distance(*a).total_cmp(&distance(*b))
+        })
+        .unwrap_or(value)
+}
+
+/// The next larger or smaller value of the list
+fn adjacent(value: usize, up: bool, values: &[usize]) -> usize {
+    let index = values.iter().position(|v| *v == value).unwrap_or(0);
+    if up {
+        values[(index + 1).min(values.len() - 1)]
+    } else {
+        values[index.saturating_sub(1)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scale_lands_on_list_values() {
+        let block_sizes = [4096, 24576];
+        // 4k doubled is 8k, which isn't in the list; 24k is the only larger size
+        assert_eq!(scale(4096, 2, true, &block_sizes), 24576);
+        assert_eq!(scale(24576, 4, false, &block_sizes), 4096);
+        assert_eq!(scale(24576, 2, true, &block_sizes), 24576);
+
+        let queue_depths = [32, 1, 8, 4, 16, 2];
+        assert_eq!(scale(4, 2, true, &queue_depths), 8);
+        assert_eq!(scale(4, 4, true, &queue_depths), 16);
+        assert_eq!(scale(16, 4, false, &queue_depths), 4);
+        assert_eq!(scale(2, 4, false, &queue_depths), 1);
+        assert_eq!(scale(1, 2, false, &queue_depths), 1);
+    }
+}
@@ -0,0 +1,216 @@
+use super::search::SearchSpace;
+use crate::config::TestParams;
+use rand::rngs::StdRng;
+use rand::Rng;
+
+// Relative gain in the best score that counts as progress between generations
+const IMPROVEMENT_TOLERANCE: f64 = 0.01;
+
+/// Genetic algorithm settings
+#[derive(Debug, Clone, Copy)]
+pub struct GeneticSettings {
+    pub population: usize,
+    pub max_generations: usize,
+    /// Chance that each gene of a child is mutated
+    pub mutation_rate: f64,
+    /// Chance that a child mixes two parents instead of copying one
+    pub crossover_rate: f64,
+    /// Stop after this many generations without improving the best score
+    pub patience: usize,
+}
+
+impl Default for GeneticSettings {
+    fn default() -> Self {
+        Self {
+            population: 12,
+            max_generations: 10,
+            mutation_rate: 0.2,
+            crossover_rate: 0.8,
+            patience: 3,
+        }
+    }
+}
+
+/// Indices into the SearchSpace value lists: block size, queue depth, threads, jobs
+type Genome = [usize; 4];
+
+/// Genetic search over a SearchSpace, driven one trial at a time: `next` hands
+/// out the next individual to evaluate and `record` takes its fitness
+pub struct GeneticSearch {
+    space: SearchSpace,
+    settings: GeneticSettings,
+    rng: StdRng,
+    population: Vec<Genome>,
+    scores: Vec<Option<f64>>,
+    /// Fitness of every genome tried so far, so repeats don't rerun a trial
+    evaluated: Vec<(Genome, f64)>,
+    pending: Option<usize>,
+    generation: usize,
+    best_score: f64,
+    stale_generations: usize,
+    done: bool,
+}
+
+impl GeneticSearch {
+    pub fn new(space: SearchSpace, settings: GeneticSettings, mut rng: StdRng) -> Self {
+        let population = (0..settings.population.max(2))
+            .map(|_| random_genome(&space, &mut rng))
+            .collect::<Vec<_>>();
+        Self {
+            scores: vec![None; population.len()],
+            space,
+            settings,
+            rng,
+            population,
+            evaluated: Vec::new(),
+            pending: None,
+            generation: 1,
+            best_score: 0.0,
+            stale_generations: 0,
+            done: false,
+        }
+    }
+
+    /// Generation of the individual handed out last (starting at 1)
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Next individual to evaluate, or None once the search converged
+    pub fn next(&mut self, base: &TestParams) -> Option<TestParams> {
+        loop {
+            if self.done {
+                return None;
+            }
+            let Some(index) = self.scores.iter().position(Option::is_none) else {
+                self.evolve();
+                continue;
+            };
+            let genome = self.population[index];
+            match self.evaluated.iter().find(|(g, _)| *g == genome) {
+                Some(&(_, score)) => self.scores[index] = Some(score),
+                None => {
+                    self.pending = Some(index);
+                    return Some(self.params(&genome, base));
+                }
+            }
+        }
+    }
+
+    /// Fitness of the individual returned by the last `next`
+    pub fn record(&mut self, score: f64) {
+        if let Some(index) = self.pending.take() {
+            self.scores[index] = Some(score);
+            self.evaluated.push((self.population[index], score));
+        }
+    }
+
+    fn params(&self, genome: &Genome, base: &TestParams) -> TestParams {
+        TestParams {
+            block_size: self.space.block_sizes[genome[0]],
+            queue_depth: self.space.queue_depths[genome[1]],
+            num_threads: self.space.threads[genome[2]],
+            num_jobs: self.space.jobs[genome[3]],
+            ..base.clone()
+        }
+    }
+
+    /// Breed the next generation from the scored one, or finish on convergence
+    fn evolve(&mut self) {
+        let mut ranked: Vec<(Genome, f64)> = self
+            .population
+            .iter()
+            .zip(&self.scores)
+            .map(|(g, s)| (*g, s.unwrap_or(0.0)))
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        let generation_best = ranked[0].1;
+        if generation_best > self.best_score * (1.0 + IMPROVEMENT_TOLERANCE) {
+            self.stale_generations = 0;
+        } else {
+            self.stale_generations += 1;
+        }
+        self.best_score = self.best_score.max(generation_best);
+
+        let converged = ranked.iter().all(|(g, _)| *g == ranked[0].0);
+        if converged
+            || self.stale_generations >= self.settings.patience
+            || self.generation >= self.settings.max_generations
+        {
+            self.done = true;
+            return;
+        }
+
+        // Carry the best individuals over unchanged
+        let elite = (ranked.len() / 10).max(1);
+        let mut next: Vec<Genome> = ranked.iter().take(elite).map(|(g, _)| *g).collect();
+        while next.len() < ranked.len() {
+            let first = self.tournament(&ranked);
+            let mut child = if self
+                .rng
+                .gen_bool(self.settings.crossover_rate.clamp(0.0, 1.0))
+            {
+                let second = self.tournament(&ranked);
+                let mut child = first;
+                for (gene, other) in child.iter_mut().zip(second) {
+                    if self.rng.gen_bool(0.5) {
+                        *gene = other;
+                    }
+                }
+                child
+            } else {
+                first
+            };
+            self.mutate(&mut child);
+            next.push(child);
+        }
+
+        self.population = next;
+        self.scores = vec![None; self.population.len()];
+        self.generation += 1;
+    }
+
+    /// Fitter of two random individuals
+    fn tournament(&mut self, ranked: &[(Genome, f64)]) -> Genome {
+        let a = self.rng.gen_range(0..ranked.len());
+        let b = self.rng.gen_range(0..ranked.len());
+        // `ranked` is sorted best first
+        ranked[a.min(b)].0
+    }
+
+    /// Nudge genes to a neighbouring value; the value lists are ordered, so small
+    /// steps explore around good points instead of jumping across the space
+    fn mutate(&mut self, genome: &mut Genome) {
+        let lengths = gene_lengths(&self.space);
+        for (gene, len) in genome.iter_mut().zip(lengths) {
+            if len < 2
+                || !self
+                    .rng
+                    .gen_bool(self.settings.mutation_rate.clamp(0.0, 1.0))
+            {
+                continue;
+            }
+            *gene = if *gene == 0 {
+                1
+            } else if *gene == len - 1 || self.rng.gen_bool(0.5) {
+                *gene - 1
+            } else {
+                *gene + 1
+            };
+        }
+    }
+}
+
+fn gene_lengths(space: &SearchSpace) -> [usize; 4] {
+    [
+        space.block_sizes.len(),
+        space.queue_depths.len(),
+        space.threads.len(),
+        space.jobs.len(),
+    ]
+}
+
+fn random_genome(space: &SearchSpace, rng: &mut StdRng) -> Genome {
+    gene_lengths(space).map(|len| rng.gen_range(0..len))
+}
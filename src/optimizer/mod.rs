@@ -1,4 +1,8 @@
 pub mod search;
+pub mod annealing;
+pub mod genetic;
+pub mod objective;
+pub mod trial;
 pub mod detector;
 pub mod tuner;
 
@@ -0,0 +1,363 @@
+use crate::io::engine::BenchmarkResults;
+
+/// A quantity a search can maximize or minimize
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    MaxThroughput,
+    MaxIops,
+    MinP99,
+    MaxIopsPerCore,
+}
+
+impl Goal {
+    pub fn value(&self, results: &BenchmarkResults) -> f64 {
+        match self {
+            Goal::MaxThroughput => results.throughput_read_mbps + results.throughput_write_mbps,
+            Goal::MaxIops => results.iops,
+            Goal::MinP99 => results.p99_latency_us,
+            Goal::MaxIopsPerCore => {
+                if results.cpu_cores > 0.0 {
+                    results.iops / results.cpu_cores
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+
+    pub fn maximize(&self) -> bool {
+        !matches!(self, Goal::MinP99)
+    }
+}
+
+impl std::str::FromStr for Goal {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "throughput" | "bw" => Ok(Goal::MaxThroughput),
+            "iops" => Ok(Goal::MaxIops),
+            "p99" | "latency" => Ok(Goal::MinP99),
+            "iops-per-core" | "efficiency" => Ok(Goal::MaxIopsPerCore),
+            _ => Err(anyhow::anyhow!(
+                "Invalid goal: {}. Valid options: throughput, iops, p99, iops-per-core",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Goal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Goal::MaxThroughput => write!(f, "max throughput (MB/s)"),
+            Goal::MaxIops => write!(f, "max IOPS"),
+            Goal::MinP99 => write!(f, "min p99 (µs)"),
+            Goal::MaxIopsPerCore => write!(f, "max IOPS per core"),
+        }
+    }
+}
+
+/// A measured quantity a constraint can bound
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstraintMetric {
+    P99Latency,
+    AvgLatency,
+    Iops,
+    Throughput,
+    CpuCores,
+}
+
+impl ConstraintMetric {
+    fn value(&self, results: &BenchmarkResults) -> f64 {
+        match self {
+            ConstraintMetric::P99Latency => results.p99_latency_us,
+            ConstraintMetric::AvgLatency => results.avg_latency_us,
+            ConstraintMetric::Iops => results.iops,
+            ConstraintMetric::Throughput => {
+                results.throughput_read_mbps + results.throughput_write_mbps
+            }
+            ConstraintMetric::CpuCores => results.cpu_cores,
+        }
+    }
+
+    fn is_latency(&self) -> bool {
+        matches!(
+            self,
+            ConstraintMetric::P99Latency | ConstraintMetric::AvgLatency
+        )
+    }
+}
+
+/// A hard limit on a metric, e.g. "p99 < 1ms" or "iops >= 100k"
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Constraint {
+    pub metric: ConstraintMetric,
+    /// True for upper bounds (<, <=), false for lower bounds (>, >=)
+    pub upper: bool,
+    pub inclusive: bool,
+    /// Limit in the metric's unit (µs for latencies, MB/s for throughput)
+    pub limit: f64,
+}
+
+impl Constraint {
+    pub fn satisfied(&self, results: &BenchmarkResults) -> bool {
+        let value = self.metric.value(results);
+        match (self.upper, self.inclusive) {
+            (true, true) => value <= self.limit,
+            (true, false) => value < self.limit,
+            (false, true) => value >= self.limit,
+            (false, false) => value > self.limit,
+        }
+    }
+
+    /// How far the limit is overshot, as a ratio >= 1 (1 when satisfied)
+    fn violation(&self, results: &BenchmarkResults) -> f64 {
+        if self.satisfied(results) {
+            return 1.0;
+        }
+        let value = self.metric.value(results);
+        let ratio = if self.upper {
+            value / self.limit
+        } else if value > 0.0 {
+            self.limit / value
+        } else {
+            f64::INFINITY
+        };
+        ratio.max(1.0 + f64::EPSILON)
+    }
+}
+
+impl std::str::FromStr for Constraint {
+    type Err = anyhow::Error;
+
+    /// Parse "p99<1ms", "avg<=200us", "iops>=100k", "throughput>2000", "cpu<4"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        let (metric, op, limit) = ["<=", ">=", "<", ">"]
+            .iter()
+            .find_map(|op| s.split_once(op).map(|(m, l)| (m.trim(), *op, l.trim())))
+            .ok_or_else(|| anyhow::anyhow!("Invalid constraint: {} (expected e.g. p99<1ms)", s))?;
+        let metric = match metric {
+            "p99" => ConstraintMetric::P99Latency,
+            "avg" | "latency" => ConstraintMetric::AvgLatency,
+            "iops" => ConstraintMetric::Iops,
+            "throughput" | "bw" => ConstraintMetric::Throughput,
+            "cpu" | "cores" => ConstraintMetric::CpuCores,
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Invalid constraint metric: {}. Valid options: p99, avg, iops, throughput, cpu",
+                    metric
+                ))
+            }
+        };
+        let limit = if metric.is_latency() {
+            parse_latency_us(limit)?
+        } else {
+            parse_count(limit)?
+        };
+        Ok(Constraint {
+            metric,
+            upper: op.starts_with('<'),
+            inclusive: op.ends_with('='),
+            limit,
+        })
+    }
+}
+
+impl std::fmt::Display for Constraint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let metric = match self.metric {
+            ConstraintMetric::P99Latency => "p99",
+            ConstraintMetric::AvgLatency => "avg",
+            ConstraintMetric::Iops => "iops",
+            ConstraintMetric::Throughput => "throughput",
+            ConstraintMetric::CpuCores => "cpu",
+        };
+        let op = match (self.upper, self.inclusive) {
+            (true, true) => "<=",
+            (true, false) => "<",
+            (false, true) => ">=",
+            (false, false) => ">",
+        };
+        let unit = if self.metric.is_latency() { "us" } else { "" };
+        write!(f, "{}{}{}{}", metric, op, self.limit, unit)
+    }
+}
+
+/// Latency in µs from "500", "500us", "2ms", "1s" or "800ns"
+fn parse_latency_us(s: &str) -> anyhow::Result<f64> {
+    let (number, scale) = if let Some(n) = s.strip_suffix("ns") {
+        (n, 0.001)
+    } else if let Some(n) = s.strip_suffix("us") {
+        (n, 1.0)
+    } else if let Some(n) = s.strip_suffix("ms") {
+        (n, 1000.0)
+    } else if let Some(n) = s.strip_suffix('s') {
+        (n, 1_000_000.0)
+    } else {
+        (s, 1.0)
+    };
+    number
+        .trim()
+        .parse::<f64>()
+        .map(|n| n * scale)
+        .map_err(|_| anyhow::anyhow!("Invalid latency: {}", s))
+}
+
+/// Plain number with an optional k/m suffix (e.g., 100k)
+fn parse_count(s: &str) -> anyhow::Result<f64> {
+    let (number, scale) = if let Some(n) = s.strip_suffix('k') {
+        (n, 1e3)
+    } else if let Some(n) = s.strip_suffix('m') {
+        (n, 1e6)
+    } else {
+        (s, 1.0)
+    };
+    number
+        .trim()
+        .parse::<f64>()
+        .map(|n| n * scale)
+        .map_err(|_| anyhow::anyhow!("Invalid number: {}", s))
+}
+
+/// How an objective judged one trial
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluation {
+    /// Scalar fitness the search strategies maximize
+    pub score: f64,
+    /// Whether every hard constraint held
+    pub feasible: bool,
+    /// Value of each goal, in the objective's goal order
+    pub goals: Vec<f64>,
+}
+
+/// What a search optimizes: weighted goals under hard constraints.
+///
+/// The score is the weighted geometric product of the goals (maximized goals
+/// raised to their weight, minimized ones to minus their weight), so goals in
+/// different units combine without normalization and a 10% change in any goal
+/// moves the score by the same factor. Infeasible trials have their score cut by
+/// the squared constraint overshoot: the search can still tell nearly-compliant
+/// points from hopeless ones, but they are never reported as best or on the front.
+#[derive(Debug, Clone)]
+pub struct Objective {
+    pub goals: Vec<(Goal, f64)>,
+    pub constraints: Vec<Constraint>,
+}
+
+impl Objective {
+    pub fn evaluate(&self, results: &BenchmarkResults) -> Evaluation {
+        let goals: Vec<f64> = self.goals.iter().map(|(g, _)| g.value(results)).collect();
+        let mut score = 1.0;
+        for ((goal, weight), value) in self.goals.iter().zip(&goals) {
+            let value = value.max(f64::MIN_POSITIVE);
+            score *= if goal.maximize() {
+                value.powf(*weight)
+            } else {
+                value.powf(-*weight)
+            };
+        }
+        let feasible = self.constraints.iter().all(|c| c.satisfied(results));
+        for constraint in &self.constraints {
+            score /= constraint.violation(results).powi(2);
+        }
+        Evaluation {
+            score,
+            feasible,
+            goals,
+        }
+    }
+
+    /// True when `a` is at least as good as `b` on every goal and better on one
+    pub fn dominates(&self, a: &Evaluation, b: &Evaluation) -> bool {
+        let mut better = false;
+        for ((goal, _), (x, y)) in self.goals.iter().zip(a.goals.iter().zip(&b.goals)) {
+            let (x, y) = if goal.maximize() { (*x, *y) } else { (-x, -y) };
+            if x < y {
+                return false;
+            }
+            better |= x > y;
+        }
+        better
+    }
+
+    pub fn describe(&self) -> String {
+        let goals = self
+            .goals
+            .iter()
+            .map(|(goal, weight)| {
+                if self.goals.len() > 1 {
+                    format!("{} x{}", goal, weight)
+                } else {
+                    goal.to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        if self.constraints.is_empty() {
+            goals
+        } else {
+            let constraints = self
+                .constraints
+                .iter()
+                .map(Constraint::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("{} subject to {}", goals, constraints)
+        }
+    }
+}
+
+impl Default for Objective {
+    fn default() -> Self {
+        Objective {
+            goals: vec![(Goal::MaxIops, 1.0)],
+            constraints: Vec::new(),
+        }
+    }
+}
+
+impl std::str::FromStr for Objective {
+    type Err = anyhow::Error;
+
+    /// Parse weighted goals such as "iops", "iops:0.7,p99:0.3" or "iops-per-core".
+    /// "iops-p99:<us>" is shorthand for IOPS under a p99 latency cap.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        if let Some(cap) = s.strip_prefix("iops-p99:") {
+            return Ok(Objective {
+                goals: vec![(Goal::MaxIops, 1.0)],
+                constraints: vec![format!("p99<={}", cap).parse()?],
+            });
+        }
+        let mut goals = Vec::new();
+        for item in s.split(',').map(str::trim).filter(|i| !i.is_empty()) {
+            let (goal, weight) = match item.split_once(':') {
+                Some((goal, weight)) => (
+                    goal,
+                    weight
+                        .trim()
+                        .parse::<f64>()
+                        .map_err(|_| anyhow::anyhow!("Invalid weight: {}", item))?,
+                ),
+                None => (item, 1.0),
+            };
+            if weight <= 0.0 {
+                return Err(anyhow::anyhow!("Goal weights must be positive: {}", item));
+            }
+            let goal: Goal = goal.parse()?;
+            if goals.iter().any(|(g, _)| *g == goal) {
+                return Err(anyhow::anyhow!("Goal listed twice: {}", item));
+            }
+            goals.push((goal, weight));
+        }
+        if goals.is_empty() {
+            return Err(anyhow::anyhow!("Objective needs at least one goal"));
+        }
+        Ok(Objective {
+            goals,
+            constraints: Vec::new(),
+        })
+    }
+}
@@ -1,12 +1,79 @@
-use crate::config::{TestParams, IoMode};
-use crate::monitor::BottleneckReport;
+use super::annealing::{AnnealingSearch, AnnealingSettings};
+use super::genetic::{GeneticSearch, GeneticSettings};
+use super::objective::{Evaluation, Objective};
+use crate::config::TestParams;
+use crate::io::engine::BenchmarkResults;
+use rand::rngs::StdRng;
 
 /// Parameter search strategies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SearchStrategy {
     Exhaustive,
     Genetic,
     SimulatedAnnealing,
-    Adaptive,
+}
+
+impl std::str::FromStr for SearchStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "exhaustive" | "grid" => Ok(SearchStrategy::Exhaustive),
+            "genetic" | "ga" => Ok(SearchStrategy::Genetic),
+            "annealing" | "simulated-annealing" | "sa" => Ok(SearchStrategy::SimulatedAnnealing),
+            _ => Err(anyhow::anyhow!(
+                "Invalid search strategy: {}. Valid options: genetic, annealing, exhaustive",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for SearchStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SearchStrategy::Exhaustive => write!(f, "exhaustive"),
+            SearchStrategy::Genetic => write!(f, "genetic"),
+            SearchStrategy::SimulatedAnnealing => write!(f, "simulated-annealing"),
+        }
+    }
+}
+
+/// Parameter values a search may pick from (each list in ascending order)
+#[derive(Debug, Clone)]
+pub struct SearchSpace {
+    pub block_sizes: Vec<usize>,
+    pub queue_depths: Vec<usize>,
+    pub threads: Vec<usize>,
+    pub jobs: Vec<usize>,
+}
+
+impl SearchSpace {
+    /// Number of points in the cartesian grid
+    pub fn num_points(&self) -> usize {
+        self.block_sizes.len() * self.queue_depths.len() * self.threads.len() * self.jobs.len()
+    }
+
+    /// Grid point `index`, with queue depth varying fastest so that each
+    /// block size / thread count / job count combination forms a contiguous QD curve
+    fn point(&self, index: usize, base: &TestParams) -> Option<TestParams> {
+        if index >= self.num_points() {
+            return None;
+        }
+        let qd = index % self.queue_depths.len();
+        let rest = index / self.queue_depths.len();
+        let threads = rest % self.threads.len();
+        let rest = rest / self.threads.len();
+        let jobs = rest % self.jobs.len();
+        let bs = rest / self.jobs.len();
+        Some(TestParams {
+            queue_depth: self.queue_depths[qd],
+            block_size: self.block_sizes[bs],
+            num_threads: self.threads[threads],
+            num_jobs: self.jobs[jobs],
+            ..base.clone()
+        })
+    }
 }
 
 pub struct SearchEngine {
@@ -14,6 +81,13 @@ pub struct SearchEngine {
     current_params: TestParams,
     best_params: Option<TestParams>,
     best_score: f64,
+    space: Option<SearchSpace>,
+    grid_index: usize,
+    genetic: Option<GeneticSearch>,
+    annealing: Option<AnnealingSearch>,
+    objective: Objective,
+    /// Every trial recorded so far
+    evaluated: Vec<(TestParams, Evaluation)>,
 }
 
 impl SearchEngine {
@@ -23,73 +97,121 @@ impl SearchEngine {
             current_params: TestParams::default(),
             best_params: None,
             best_score: 0.0,
+            space: None,
+            grid_index: 0,
+            genetic: None,
+            annealing: None,
+            objective: Objective::default(),
+            evaluated: Vec::new(),
         }
     }
-    
-    pub fn next_params(&mut self, report: &BottleneckReport) -> TestParams {
-        match self.strategy {
-            SearchStrategy::Adaptive => self.adaptive_search(report),
-            SearchStrategy::Exhaustive => self.exhaustive_search(),
-            SearchStrategy::Genetic => self.genetic_search(),
-            SearchStrategy::SimulatedAnnealing => self.simulated_annealing(),
+
+    /// Restrict the search to a grid of parameter values
+    pub fn with_space(mut self, space: SearchSpace) -> Self {
+        self.space = Some(space);
+        self.grid_index = 0;
+        self
+    }
+
+    /// What the search optimizes (maximum IOPS by default)
+    pub fn with_objective(mut self, objective: Objective) -> Self {
+        self.objective = objective;
+        self
+    }
+
+    /// Drive the genetic strategy over the search space (set the space first)
+    pub fn with_genetic(mut self, settings: GeneticSettings, rng: StdRng) -> Self {
+        if let Some(space) = &self.space {
+            self.genetic = Some(GeneticSearch::new(space.clone(), settings, rng));
         }
+        self
     }
-    
-    fn adaptive_search(&mut self, report: &BottleneckReport) -> TestParams {
-        // Adjust parameters based on bottleneck detection
-        let mut params = self.current_params.clone();
-        
-        match &report.bottleneck {
-            crate::monitor::Bottleneck::CpuBound { .. } => {
-                // Reduce threads or increase block size
-                if params.num_threads > 1 {
-                    params.num_threads /= 2;
-                } else {
-                    params.block_size *= 2;
-                }
-            }
-            crate::monitor::Bottleneck::MemoryBound { .. } => {
-                // Reduce block size
-                params.block_size = (params.block_size / 2).max(4096);
-            }
-            crate::monitor::Bottleneck::IoBound { queue_depth: _queue_depth, .. } => {
-                // Increase queue depth
-                params.queue_depth = (params.queue_depth * 2).min(1024);
-            }
-            crate::monitor::Bottleneck::NumaBound { .. } => {
-                // Keep threads per NUMA node
-                // TODO: Implement NUMA-aware thread binding
-            }
-            crate::monitor::Bottleneck::Balanced => {
-                // Try to increase throughput
-                params.queue_depth = (params.queue_depth * 2).min(1024);
-            }
+
+    /// Drive the simulated annealing strategy over the search space (set the space first)
+    pub fn with_annealing(mut self, settings: AnnealingSettings, rng: StdRng) -> Self {
+        if let Some(space) = &self.space {
+            self.annealing = Some(AnnealingSearch::new(space.clone(), settings, rng));
         }
-        
-        self.current_params = params.clone();
-        params
+        self
     }
-    
-    fn exhaustive_search(&mut self) -> TestParams {
-        // TODO: Implement exhaustive parameter search
-        self.current_params.clone()
+
+    /// Next parameters to try for strategies that need no bottleneck report,
+    /// or None once the search is complete
+    pub fn next_candidate(&mut self) -> Option<TestParams> {
+        let params = match self.strategy {
+            SearchStrategy::Exhaustive => return self.next_grid_point(),
+            SearchStrategy::Genetic => self.genetic.as_mut()?.next(&self.current_params)?,
+            SearchStrategy::SimulatedAnnealing => {
+                self.annealing.as_mut()?.next(&self.current_params)?
+            }
+        };
+        self.current_params = params.clone();
+        Some(params)
     }
-    
-    fn genetic_search(&mut self) -> TestParams {
-        // TODO: Implement genetic algorithm
-        self.current_params.clone()
+
+    /// Progress of the search: the genetic generation or the annealing step
+    pub fn round(&self) -> usize {
+        match self.strategy {
+            SearchStrategy::Genetic => self.genetic.as_ref().map_or(0, GeneticSearch::generation),
+            SearchStrategy::SimulatedAnnealing => {
+                self.annealing.as_ref().map_or(0, AnnealingSearch::step)
+            }
+            SearchStrategy::Exhaustive => self.grid_index,
+        }
     }
-    
-    fn simulated_annealing(&mut self) -> TestParams {
-        // TODO: Implement simulated annealing
-        self.current_params.clone()
+
+    /// Next unvisited point of the grid, or None once every point was handed out
+    pub fn next_grid_point(&mut self) -> Option<TestParams> {
+        let params = self
+            .space
+            .as_ref()?
+            .point(self.grid_index, &self.current_params)?;
+        self.grid_index += 1;
+        self.current_params = params.clone();
+        Some(params)
     }
     
-    pub fn record_result(&mut self, params: &TestParams, score: f64) {
-        if score > self.best_score {
+    /// Score a trial with the objective and feed it to the strategy. Only trials
+    /// that meet every constraint can become the best parameters.
+    pub fn record_result(&mut self, params: &TestParams, results: &BenchmarkResults) -> Evaluation {
+        let evaluation = self.objective.evaluate(results);
+        let score = evaluation.score;
+        if let Some(genetic) = &mut self.genetic {
+            genetic.record(score);
+        }
+        if let Some(annealing) = &mut self.annealing {
+            annealing.record(score);
+        }
+        if evaluation.feasible && score > self.best_score {
             self.best_score = score;
             self.best_params = Some(params.clone());
         }
+        self.evaluated.push((params.clone(), evaluation.clone()));
+        evaluation
+    }
+
+    /// Feasible trials that no other feasible trial beats on every goal, best
+    /// score first. A point measured several times keeps its best measurement.
+    pub fn pareto_front(&self) -> Vec<(TestParams, Evaluation)> {
+        let mut candidates: Vec<&(TestParams, Evaluation)> = Vec::new();
+        for entry in self.evaluated.iter().filter(|(_, e)| e.feasible) {
+            match candidates.iter_mut().find(|(p, _)| *p == entry.0) {
+                Some(existing) if existing.1.score < entry.1.score => *existing = entry,
+                Some(_) => {}
+                None => candidates.push(entry),
+            }
+        }
+        let mut front: Vec<(TestParams, Evaluation)> = candidates
+            .iter()
+            .filter(|(_, e)| {
+                !candidates
+                    .iter()
+                    .any(|(_, other)| self.objective.dominates(other, e))
+            })
+            .map(|&(p, e)| (p.clone(), e.clone()))
+            .collect();
+        front.sort_by(|a, b| b.1.score.total_cmp(&a.1.score));
+        front
     }
     
     pub fn best_params(&self) -> Option<&TestParams> {
@@ -97,3 +219,72 @@ impl SearchEngine {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(queue_depth: usize) -> TestParams {
+        TestParams {
+            queue_depth,
+            ..Default::default()
+        }
+    }
+
+    /// A search over IOPS and p99 with trials recorded directly, scored IOPS per μs of p99
+    fn search_with(trials: &[(usize, f64, f64, bool)]) -> SearchEngine {
+        let mut search = SearchEngine::new(SearchStrategy::Exhaustive)
+            .with_objective("iops,p99".parse().unwrap());
+        for &(queue_depth, iops, p99, feasible) in trials {
+            search.evaluated.push((
+                params(queue_depth),
+                Evaluation {
+                    score: iops / p99,
+                    feasible,
+                    goals: vec![iops, p99],
+                },
+            ));
+        }
+        search
+    }
+
+    fn queue_depths(front: &[(TestParams, Evaluation)]) -> Vec<usize> {
+        front.iter().map(|(p, _)| p.queue_depth).collect()
+    }
+
+    #[test]
+    fn dominated_and_infeasible_points_leave_the_front() {
+        let search = search_with(&[
+            (1, 100.0, 10.0, true),
+            (2, 200.0, 20.0, true),
+            (4, 150.0, 30.0, true),
+            (8, 1000.0, 1.0, false),
+        ]);
+        assert_eq!(queue_depths(&search.pareto_front()), vec![1, 2]);
+    }
+
+    #[test]
+    fn equal_goals_do_not_dominate_each_other() {
+        let search = search_with(&[(2, 200.0, 20.0, true), (4, 200.0, 20.0, true)]);
+        assert_eq!(queue_depths(&search.pareto_front()), vec![2, 4]);
+    }
+
+    #[test]
+    fn repeated_points_keep_their_best_measurement() {
+        let search = search_with(&[
+            (1, 100.0, 10.0, true),
+            (2, 200.0, 20.0, true),
+            (1, 120.0, 10.0, true),
+            (2, 50.0, 50.0, true),
+        ]);
+        let front = search.pareto_front();
+        assert_eq!(queue_depths(&front), vec![1, 2]);
+        assert_eq!(front[0].1.goals, vec![120.0, 10.0]);
+        assert_eq!(front[1].1.goals, vec![200.0, 20.0]);
+    }
+
+    #[test]
+    fn empty_without_feasible_trials() {
+        let search = search_with(&[(1, 100.0, 10.0, false)]);
+        assert!(search.pareto_front().is_empty());
+    }
+}
@@ -0,0 +1,168 @@
+use super::objective::{Evaluation, Objective};
+use super::search::SearchEngine;
+use super::{BottleneckDetector, ParameterTuner};
+use crate::config::{self, Config, TestParams};
+use crate::io::engine::{BenchmarkResults, IoEngine};
+use crate::io::queue::QueueTuning;
+use crate::monitor::MonitorCollector;
+use anyhow::Result;
+
+/// One evaluated point of a parameter search
+#[derive(Debug, Clone)]
+pub struct Trial {
+    pub params: TestParams,
+    pub evaluation: Evaluation,
+    pub results: BenchmarkResults,
+}
+
+/// Evaluate the candidates a search proposes with short IoEngine runs of the
+/// base configuration until the search completes
+pub fn run_search(base: &Config, search: &mut SearchEngine) -> Result<Vec<Trial>> {
+    let mut trials: Vec<Trial> = Vec::new();
+    while let Some(params) = search.next_candidate() {
+        let round = search.round();
+        trials.push(run_trial(base, search, params, round, trials.len() + 1)?);
+    }
+    Ok(trials)
+}
+
+/// Refine the best parameters of a finished search for `rounds` more trials: the
+/// first measures the best point again, and each later one follows the tuner's
+/// response to the bottleneck the previous trial ran into
+pub fn run_tuning(
+    base: &Config,
+    search: &mut SearchEngine,
+    tuner: ParameterTuner,
+    rounds: usize,
+    trials: &mut Vec<Trial>,
+) -> Result<()> {
+    let Some(best) = search.best_params().cloned() else {
+        return Ok(());
+    };
+    let mut tuner = tuner.starting_from(TestParams {
+        io_pattern: base.workload.into(),
+        ..best
+    });
+    let detector = BottleneckDetector::new();
+    let mut collector = MonitorCollector::new(base.devices.first().cloned());
+    for round in 1..=rounds {
+        // Reset the CPU counters so the report covers this trial only
+        collector.collect_metrics()?;
+        let params = tuner.current_params().clone();
+        trials.push(run_trial(base, search, params, round, trials.len() + 1)?);
+        let report = collector.collect_metrics()?;
+        println!("      tune {:<3} {}", round, detector.analyze(&report));
+        tuner.tune(&report);
+    }
+    Ok(())
+}
+
+/// Run one candidate, with its block queue attributes applied for the run only
+fn run_trial(
+    base: &Config,
+    search: &mut SearchEngine,
+    params: TestParams,
+    round: usize,
+    number: usize,
+) -> Result<Trial> {
+    let config = params.apply(base);
+    let tuning = if config.set_queue.is_empty() {
+        None
+    } else {
+        Some(QueueTuning::apply(&config.devices, &config.set_queue)?)
+    };
+    let mut results = IoEngine::new(config)?.run()?;
+    if let Some(tuning) = tuning {
+        results.queue_changes = tuning.restore();
+    }
+    let evaluation = search.record_result(&params, &results);
+
+    println!(
+        "[{:>3}] round {:<3} {:<34} {:>10.0} IOPS {:>9.2} MB/s p99 {:>9.2} μs{}",
+        number,
+        round,
+        describe_params(&params),
+        results.iops,
+        results.throughput_read_mbps + results.throughput_write_mbps,
+        results.p99_latency_us,
+        if evaluation.feasible {
+            ""
+        } else {
+            "  (infeasible)"
+        }
+    );
+    Ok(Trial {
+        params,
+        evaluation,
+        results,
+    })
+}
+
+pub fn describe_params(params: &TestParams) -> String {
+    let mut description = format!(
+        "bs={} qd={} threads={} jobs={}",
+        config::format_block_size(params.block_size),
+        params.queue_depth,
+        params.num_threads,
+        params.num_jobs
+    );
+    if !params.queue.is_empty() {
+        description.push_str(&format!(" queue={}", params.queue));
+    }
+    description
+}
+
+/// The Pareto front of a search and the parameters it recommends
+pub fn print_search(trials: &[Trial], front: &[(TestParams, Evaluation)], objective: &Objective) {
+    let feasible = trials.iter().filter(|t| t.evaluation.feasible).count();
+
+    println!("\n{}", "=".repeat(90));
+    println!(
+        "Optimization Results ({} trials, {} feasible)",
+        trials.len(),
+        feasible
+    );
+    println!("Objective: {}", objective.describe());
+    println!("{}", "=".repeat(90));
+
+    if front.is_empty() {
+        println!("No trial met the constraints");
+        println!("{}", "=".repeat(90));
+        return;
+    }
+
+    println!("\nPareto front ({} points, best score first):", front.len());
+    let header: Vec<String> = objective
+        .goals
+        .iter()
+        .map(|(goal, _)| format!("{:>22}", goal.to_string()))
+        .collect();
+    println!("  {:<34}{}", "Parameters", header.join(""));
+    for (params, evaluation) in front {
+        let values: Vec<String> = evaluation
+            .goals
+            .iter()
+            .map(|value| format!("{:>22.2}", value))
+            .collect();
+        println!("  {:<34}{}", describe_params(params), values.join(""));
+    }
+
+    let best = &front[0].0;
+    println!("\nBest: {}", describe_params(best));
+    if let Some(trial) = trials
+        .iter()
+        .filter(|t| t.params == *best)
+        .max_by(|a, b| a.evaluation.score.total_cmp(&b.evaluation.score))
+    {
+        let r = &trial.results;
+        println!(
+            "  {:.0} IOPS, {:.2} MB/s, avg {:.2} μs, p99 {:.2} μs, {:.2} CPU cores",
+            r.iops,
+            r.throughput_read_mbps + r.throughput_write_mbps,
+            r.avg_latency_us,
+            r.p99_latency_us,
+            r.cpu_cores
+        );
+    }
+    println!("{}", "=".repeat(90));
+}
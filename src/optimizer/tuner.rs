@@ -1,10 +1,11 @@
-use crate::config::{TestParams, IoMode};
+use crate::config::{IoMode, NumaPolicy, TestParams};
 use crate::monitor::BottleneckReport;
 
 /// Parameter tuner for adaptive optimization
 pub struct ParameterTuner {
     current_params: TestParams,
     iteration: usize,
+    tune_queue: bool,
 }
 
 impl ParameterTuner {
@@ -12,8 +13,21 @@ impl ParameterTuner {
         Self {
             current_params: TestParams::default(),
             iteration: 0,
+            tune_queue: false,
         }
     }
+
+    /// Start tuning from these parameters instead of the defaults
+    pub fn starting_from(mut self, params: TestParams) -> Self {
+        self.current_params = params;
+        self
+    }
+
+    /// Also tune block queue attributes (scheduler, nr_requests, rq_affinity, nomerges)
+    pub fn with_queue_tuning(mut self) -> Self {
+        self.tune_queue = true;
+        self
+    }
     
     pub fn tune(&mut self, report: &BottleneckReport) -> TestParams {
         self.iteration += 1;
@@ -41,7 +55,13 @@ impl ParameterTuner {
     }
     
     fn reduce_cpu_load(&mut self) {
-        if self.current_params.num_threads > 1 {
+        let queue = &mut self.current_params.queue;
+        if self.tune_queue && queue.get("rq_affinity") != Some("2") {
+            // Complete requests on the submitting CPU instead of bouncing them
+            queue.set("rq_affinity", 2);
+        } else if self.tune_queue && queue.get("scheduler") != Some("none") {
+            queue.set("scheduler", "none");
+        } else if self.current_params.num_threads > 1 {
             self.current_params.num_threads = (self.current_params.num_threads * 3 / 4).max(1);
         } else {
             self.current_params.block_size = (self.current_params.block_size * 2).min(1048576);
@@ -54,13 +74,33 @@ impl ParameterTuner {
     
     fn increase_io_capacity(&mut self) {
         self.current_params.queue_depth = (self.current_params.queue_depth * 2).min(1024);
+        if self.tune_queue {
+            // Enough block layer requests for every submitted I/O
+            let needed = self.current_params.queue_depth * self.current_params.num_threads;
+            let queue = &mut self.current_params.queue;
+            let current = queue
+                .get("nr_requests")
+                .and_then(|n| n.parse::<usize>().ok())
+                .unwrap_or(0);
+            if current < needed {
+                queue.set("nr_requests", needed);
+            }
+        }
     }
     
     fn optimize_numa(&mut self) {
-        // TODO: Implement NUMA optimization
+        // Keep workers and buffers on the device's home node
+        self.current_params.numa = Some(NumaPolicy::Auto);
     }
     
     fn optimize_for_throughput(&mut self) {
+        // Random I/O never merges, so don't spend time trying
+        if self.tune_queue
+            && self.current_params.io_pattern == IoMode::Random
+            && self.current_params.queue.get("nomerges").is_none()
+        {
+            self.current_params.queue.set("nomerges", 2);
+        }
         // Gradually increase parameters
         self.current_params.queue_depth = (self.current_params.queue_depth * 11 / 10).min(1024);
     }
@@ -0,0 +1,343 @@
+use crate::config::{self, Workload};
+use crate::io::engine::BenchmarkResults;
+use crate::io::histogram::LatencySnapshot;
+use anyhow::{Context, Result};
+use serde_json::{json, Map, Value};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Percentiles fio reports for clat_ns by default
+const FIO_PERCENTILES: [f64; 17] = [
+    1.0, 5.0, 10.0, 20.0, 30.0, 40.0, 50.0, 60.0, 70.0, 80.0, 90.0, 95.0, 99.0, 99.5, 99.9, 99.95,
+    99.99,
+];
+
+/// A headline result metric, as compared and summarized across runs
+pub struct Metric {
+    pub name: &'static str,
+    pub unit: &'static str,
+    pub higher_is_better: bool,
+    pub value: fn(&BenchmarkResults) -> f64,
+}
+
+pub const METRICS: [Metric; 7] = [
+    Metric {
+        name: "IOPS",
+        unit: "",
+        higher_is_better: true,
+        value: |r| r.iops,
+    },
+    Metric {
+        name: "Read MB/s",
+        unit: "",
+        higher_is_better: true,
+        value: |r| r.throughput_read_mbps,
+    },
+    Metric {
+        name: "Write MB/s",
+        unit: "",
+        higher_is_better: true,
+        value: |r| r.throughput_write_mbps,
+    },
+    Metric {
+        name: "Avg latency",
+        unit: "µs",
+        higher_is_better: false,
+        value: |r| r.avg_latency_us,
+    },
+    Metric {
+        name: "p50 latency",
+        unit: "µs",
+        higher_is_better: false,
+        value: |r| r.p50_latency_us,
+    },
+    Metric {
+        name: "p99 latency",
+        unit: "µs",
+        higher_is_better: false,
+        value: |r| r.p99_latency_us,
+    },
+    Metric {
+        name: "p99.9 latency",
+        unit: "µs",
+        higher_is_better: false,
+        value: |r| r.p999_latency_us,
+    },
+];
+
+/// How run results are reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Normal, // Human-readable report on stdout
+    Json,    // BenchmarkResults as JSON
+    FioJson, // fio's --output-format=json schema
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "normal" | "text" => Ok(OutputFormat::Normal),
+            "json" => Ok(OutputFormat::Json),
+            "fio-json" | "fio" => Ok(OutputFormat::FioJson),
+            _ => Err(anyhow::anyhow!(
+                "Invalid output format: {}. Valid options: normal, json, fio-json",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputFormat::Normal => write!(f, "normal"),
+            OutputFormat::Json => write!(f, "json"),
+            OutputFormat::FioJson => write!(f, "fio-json"),
+        }
+    }
+}
+
+/// Render results in a machine-readable format. A single run is one document;
+/// several job groups become an array (json) or share one jobs[] list (fio-json).
+pub fn render(format: OutputFormat, results: &[BenchmarkResults]) -> Result<String> {
+    match format {
+        OutputFormat::Normal => Err(anyhow::anyhow!("normal output is printed, not rendered")),
+        OutputFormat::Json => Ok(match results {
+            [single] => serde_json::to_string_pretty(single)?,
+            _ => serde_json::to_string_pretty(results)?,
+        }),
+        OutputFormat::FioJson => Ok(serde_json::to_string_pretty(&fio_json(results))?),
+    }
+}
+
+/// Results in the layout of fio's JSON output. Latency is measured from submission
+/// to completion, so clat_ns and lat_ns carry the same values and slat_ns is zero.
+pub fn fio_json(results: &[BenchmarkResults]) -> Value {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    let mut jobs = Vec::new();
+    let mut groupid = 0;
+    for result in results {
+        if result.jobs.is_empty() {
+            jobs.push(fio_job(result, groupid));
+            groupid += 1;
+        } else if result.config.workload == Workload::All && result.config.jobs.is_empty() {
+            // "all" runs each workload after the other, like stonewalled fio jobs
+            for job in &result.jobs {
+                jobs.push(fio_job(job, groupid));
+                groupid += 1;
+            }
+        } else {
+            jobs.extend(result.jobs.iter().map(|job| fio_job(job, groupid)));
+            groupid += 1;
+        }
+    }
+
+    let disk_util: Vec<Value> = results
+        .iter()
+        .flat_map(|r| &r.disk_util)
+        .map(|disk| {
+            json!({
+                "name": disk.name,
+                "read_ios": disk.stats.read_ios,
+                "write_ios": disk.stats.write_ios,
+                "read_merges": disk.stats.read_merges,
+                "write_merges": disk.stats.write_merges,
+                "read_ticks": disk.stats.read_ticks,
+                "write_ticks": disk.stats.write_ticks,
+                "in_queue": disk.stats.time_in_queue,
+                "util": disk.util,
+            })
+        })
+        .collect();
+
+    let mut global = Map::new();
+    if let Some(first) = results.first() {
+        global.insert("filename".into(), filenames(first).into());
+        global.insert("ioengine".into(), "io_uring".into());
+        global.insert(
+            "direct".into(),
+            if first.config.buffered { "0" } else { "1" }.into(),
+        );
+        global.insert(
+            "runtime".into(),
+            first.config.duration.as_secs().to_string().into(),
+        );
+        if !first.config.ramp_time.is_zero() {
+            global.insert(
+                "ramp_time".into(),
+                first.config.ramp_time.as_secs().to_string().into(),
+            );
+        }
+    }
+
+    json!({
+        "fio version": format!("storage-bench-{}", env!("CARGO_PKG_VERSION")),
+        "timestamp": now.as_secs(),
+        "timestamp_ms": now.as_millis() as u64,
+        "time": ctime(now.as_secs() as libc::time_t),
+        "global options": global,
+        "jobs": jobs,
+        "disk_util": disk_util,
+    })
+}
+
+fn fio_job(job: &BenchmarkResults, groupid: usize) -> Value {
+    let config = &job.config;
+    let rw = match config.workload {
+        Workload::SeqRead => "read",
+        Workload::SeqWrite => "write",
+        Workload::RandRead => "randread",
+        Workload::RandWrite => "randwrite",
+        Workload::Seq => "rw",
+        Workload::Rand | Workload::All => "randrw",
+        // fio has no write-zeroes or secure erase; they are trims of a kind
+        Workload::Trim | Workload::WriteZeroes | Workload::SecureErase => "randtrim",
+        // The closest fio job is sequential writes with fdatasync
+        Workload::Wal => "write",
+    };
+    let mut options = Map::new();
+    options.insert("filename".into(), filenames(job).into());
+    options.insert("rw".into(), rw.into());
+    options.insert(
+        "bs".into(),
+        config::format_block_size(config.block_size).into(),
+    );
+    options.insert("iodepth".into(), config.queue_depth.to_string().into());
+    options.insert("numjobs".into(), config.threads.to_string().into());
+    if let Some(read_percent) = config.read_percent {
+        options.insert("rwmixread".into(), read_percent.to_string().into());
+    }
+    if let Some(split) = &config.block_size_split() {
+        options.insert("bssplit".into(), split.to_string().into());
+    }
+    if config.distribution != config::AccessDistribution::Uniform {
+        options.insert(
+            "random_distribution".into(),
+            config.distribution.to_string().into(),
+        );
+    }
+    if let Some(flush) = config.flush {
+        options.insert(flush.kind.to_string(), flush.writes.to_string().into());
+    }
+
+    let runtime_ms = job.duration.as_millis() as u64;
+    json!({
+        "jobname": job.name,
+        "groupid": groupid,
+        "error": 0,
+        "total_err": job.failed_ops,
+        "elapsed": job.duration.as_secs(),
+        "job options": options,
+        "read": fio_direction(job.total_bytes_read, job.read_ops, &job.read_latency, job),
+        "write": fio_direction(job.total_bytes_written, job.write_ops, &job.write_latency, job),
+        "trim": fio_direction(job.total_bytes_trimmed, job.trim_ops, &job.trim_latency, job),
+        "sync": fio_sync(job),
+        "job_runtime": runtime_ms,
+    })
+}
+
+fn fio_direction(bytes: u64, ops: u64, latency: &LatencySnapshot, job: &BenchmarkResults) -> Value {
+    let secs = job.duration.as_secs_f64();
+    let per_sec = |n: u64| if secs > 0.0 { n as f64 / secs } else { 0.0 };
+    let stats = json!({
+        "min": latency.min_ns(),
+        "max": latency.max_ns(),
+        "mean": latency.mean_ns(),
+        "stddev": latency.stddev_ns(),
+        "N": latency.count,
+    });
+    let mut clat = stats.clone();
+    if latency.count > 0 {
+        let percentiles: Map<String, Value> = FIO_PERCENTILES
+            .iter()
+            .map(|&p| (format!("{:.6}", p), latency.percentile_ns(p).into()))
+            .collect();
+        clat["percentile"] = Value::Object(percentiles);
+    }
+
+    json!({
+        "io_bytes": bytes,
+        "io_kbytes": bytes / 1024,
+        "bw_bytes": per_sec(bytes) as u64,
+        "bw": (per_sec(bytes) / 1024.0) as u64,
+        "iops": per_sec(ops),
+        "runtime": if ops > 0 { job.duration.as_millis() as u64 } else { 0 },
+        "total_ios": ops,
+        "short_ios": 0,
+        "drop_ios": 0,
+        "slat_ns": { "min": 0, "max": 0, "mean": 0.0, "stddev": 0.0, "N": 0 },
+        "clat_ns": clat,
+        "lat_ns": stats,
+    })
+}
+
+/// fio reports fsync and fdatasync latency in a "sync" section of its own
+fn fio_sync(job: &BenchmarkResults) -> Value {
+    let latency = &job.flush_latency;
+    let mut lat = json!({
+        "min": latency.min_ns(),
+        "max": latency.max_ns(),
+        "mean": latency.mean_ns(),
+        "stddev": latency.stddev_ns(),
+        "N": latency.count,
+    });
+    if latency.count > 0 {
+        let percentiles: Map<String, Value> = FIO_PERCENTILES
+            .iter()
+            .map(|&p| (format!("{:.6}", p), latency.percentile_ns(p).into()))
+            .collect();
+        lat["percentile"] = Value::Object(percentiles);
+    }
+    json!({
+        "total_ios": job.flush_ops,
+        "lat_ns": lat,
+    })
+}
+
+/// Targets joined the way fio separates several filenames
+fn filenames(results: &BenchmarkResults) -> String {
+    results
+        .config
+        .devices
+        .iter()
+        .map(|d| d.display().to_string())
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Local time in ctime(3) form, as fio prints it
+fn ctime(secs: libc::time_t) -> String {
+    let mut buf = [0 as libc::c_char; 32];
+    let formatted = unsafe { libc::ctime_r(&secs, buf.as_mut_ptr()) };
+    if formatted.is_null() {
+        return String::new();
+    }
+    unsafe { std::ffi::CStr::from_ptr(buf.as_ptr()) }
+        .to_string_lossy()
+        .trim_end()
+        .to_string()
+}
+
+/// Load results written by `--output-format json`: one run or an array of runs
+pub fn load_results(path: &Path) -> Result<Vec<BenchmarkResults>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let value: Value = serde_json::from_str(&text)
+        .with_context(|| format!("{} is not valid JSON", path.display()))?;
+    let results = match value {
+        Value::Array(_) => serde_json::from_value(value),
+        _ => serde_json::from_value(value).map(|single| vec![single]),
+    };
+    results.with_context(|| {
+        format!(
+            "{} does not hold storage-bench results (written with --output-format json)",
+            path.display()
+        )
+    })
+}
@@ -0,0 +1,243 @@
+use crate::config::{QueueSettings, TestParams, Workload};
+use crate::io::engine::BenchmarkResults;
+use crate::io::device::{DeviceInfo, OpenMode};
+use crate::io::Device;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// What makes two devices interchangeable for tuning purposes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileKey {
+    pub model: String,
+    pub firmware: String,
+    pub link_speed: String,
+}
+
+impl ProfileKey {
+    pub fn from_info(info: &DeviceInfo) -> Self {
+        let field = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .unwrap_or("unknown")
+                .to_string()
+        };
+        ProfileKey {
+            model: field(&info.model),
+            firmware: field(&info.firmware),
+            link_speed: field(&info.link_speed),
+        }
+    }
+
+    /// File name of the key's profiles in a store
+    fn file_name(&self) -> String {
+        let name = format!("{}_{}_{}", self.model, self.firmware, self.link_speed);
+        let name: String = name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        format!("{}.json", name)
+    }
+}
+
+impl std::fmt::Display for ProfileKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} (firmware {}, link {})",
+            self.model, self.firmware, self.link_speed
+        )
+    }
+}
+
+/// Best parameters an optimize run found for one workload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TunedProfile {
+    pub workload: Workload,
+    /// Objective the parameters were optimized for
+    pub objective: String,
+    pub block_size: usize,
+    pub queue_depth: usize,
+    pub threads: usize,
+    pub jobs: usize,
+    /// Block queue attributes the parameters were measured with
+    #[serde(default)]
+    pub queue: QueueSettings,
+    pub iops: f64,
+    pub p99_latency_us: f64,
+    /// Unix time of the optimize run
+    pub tuned_at: u64,
+    /// Device the parameters were measured on
+    pub device: PathBuf,
+}
+
+impl TunedProfile {
+    pub fn new(
+        workload: Workload,
+        objective: String,
+        params: &TestParams,
+        results: &BenchmarkResults,
+        device: &Path,
+    ) -> Self {
+        let tuned_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        TunedProfile {
+            workload,
+            objective,
+            block_size: params.block_size,
+            queue_depth: params.queue_depth,
+            threads: params.num_threads,
+            jobs: params.num_jobs,
+            queue: params.queue.clone(),
+            iops: results.iops,
+            p99_latency_us: results.p99_latency_us,
+            tuned_at,
+            device: device.to_path_buf(),
+        }
+    }
+
+    pub fn params(&self) -> TestParams {
+        TestParams {
+            block_size: self.block_size,
+            queue_depth: self.queue_depth,
+            num_threads: self.threads,
+            num_jobs: self.jobs,
+            queue: self.queue.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Profile file: every tuned workload of one device key
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProfileFile {
+    pub key: ProfileKey,
+    pub profiles: Vec<TunedProfile>,
+}
+
+/// Directory of profile files, one per device key
+pub struct ProfileStore {
+    dir: PathBuf,
+}
+
+impl ProfileStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// $XDG_DATA_HOME/storage-bench/profiles, or ~/.local/share/storage-bench/profiles
+    pub fn default_dir() -> Result<PathBuf> {
+        let data = match std::env::var_os("XDG_DATA_HOME").filter(|d| !d.is_empty()) {
+            Some(dir) => PathBuf::from(dir),
+            None => std::env::var_os("HOME")
+                .map(|home| PathBuf::from(home).join(".local/share"))
+                .ok_or_else(|| {
+                    anyhow::anyhow!("Neither XDG_DATA_HOME nor HOME is set; use --profile-dir")
+                })?,
+        };
+        Ok(data.join("storage-bench").join("profiles"))
+    }
+
+    /// Profile key of each device, in order
+    pub fn device_keys(devices: &[PathBuf]) -> Result<Vec<ProfileKey>> {
+        devices
+            .iter()
+            .map(|path| {
+                let device = Device::open(path, OpenMode::read_only())
+                    .with_context(|| format!("Failed to open {}", path.display()))?;
+                Ok(ProfileKey::from_info(&device.info()))
+            })
+            .collect()
+    }
+
+    /// The profile that `run --profile auto` uses: all devices must share one
+    pub fn lookup(
+        &self,
+        devices: &[PathBuf],
+        workload: Workload,
+    ) -> Result<(ProfileKey, TunedProfile)> {
+        let keys = Self::device_keys(devices)?;
+        let key = keys[0].clone();
+        if let Some((path, other)) = devices.iter().zip(&keys).find(|(_, k)| **k != key) {
+            return Err(anyhow::anyhow!(
+                "Devices have different profiles: {} is {}, {} is {}",
+                devices[0].display(),
+                key,
+                path.display(),
+                other
+            ));
+        }
+        let profile = self.load(&key, workload)?.ok_or_else(|| {
+            anyhow::anyhow!(
+                "No {} profile for {} in {}; create one with `storage-bench optimize`",
+                workload.name(),
+                key,
+                self.dir.display()
+            )
+        })?;
+        Ok((key, profile))
+    }
+
+    /// Store a profile, replacing the key's earlier profile for the same workload.
+    /// Returns the file written.
+    pub fn save(&self, key: &ProfileKey, profile: TunedProfile) -> Result<PathBuf> {
+        let path = self.dir.join(key.file_name());
+        let mut file = match Self::read(&path)? {
+            Some(file) => file,
+            None => ProfileFile {
+                key: key.clone(),
+                profiles: Vec::new(),
+            },
+        };
+        file.profiles.retain(|p| p.workload != profile.workload);
+        file.profiles.push(profile);
+
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        std::fs::write(&path, serde_json::to_string_pretty(&file)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// The key's profile for a workload, if one was saved
+    pub fn load(&self, key: &ProfileKey, workload: Workload) -> Result<Option<TunedProfile>> {
+        let file = Self::read(&self.dir.join(key.file_name()))?;
+        Ok(file.and_then(|file| file.profiles.into_iter().find(|p| p.workload == workload)))
+    }
+
+    /// A profile file given by path, e.g. one copied from another machine
+    pub fn load_file(path: &Path, workload: Workload) -> Result<TunedProfile> {
+        let file = Self::read(path)?
+            .ok_or_else(|| anyhow::anyhow!("Profile file {} not found", path.display()))?;
+        file.profiles
+            .into_iter()
+            .find(|p| p.workload == workload)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "{} has no profile for workload {}",
+                    path.display(),
+                    workload.name()
+                )
+            })
+    }
+
+    fn read(path: &Path) -> Result<Option<ProfileFile>> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map(Some)
+                .with_context(|| format!("Invalid profile file {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+}
@@ -0,0 +1,249 @@
+/// Arithmetic mean (0 for an empty slice)
+pub fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Sample standard deviation (n - 1 denominator; 0 with fewer than two values)
+pub fn stddev(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let m = mean(values);
+    let sum_sq: f64 = values.iter().map(|v| (v - m).powi(2)).sum();
+    (sum_sq / (values.len() - 1) as f64).sqrt()
+}
+
+/// Spread of repeated measurements of one metric
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Summary {
+    pub mean: f64,
+    pub stddev: f64,
+    /// Coefficient of variation: stddev relative to the mean, in percent
+    pub cv_percent: f64,
+    /// 95% confidence interval of the mean (collapses to the mean for one sample)
+    pub ci_low: f64,
+    pub ci_high: f64,
+}
+
+impl Summary {
+    pub fn of(values: &[f64]) -> Self {
+        let (mean, stddev) = (mean(values), stddev(values));
+        let cv_percent = if mean != 0.0 {
+            stddev / mean.abs() * 100.0
+        } else {
+            0.0
+        };
+        let half_width = if values.len() >= 2 {
+            let n = values.len() as f64;
+            t_critical(0.95, n - 1.0) * stddev / n.sqrt()
+        } else {
+            0.0
+        };
+        Summary {
+            mean,
+            stddev,
+            cv_percent,
+            ci_low: mean - half_width,
+            ci_high: mean + half_width,
+        }
+    }
+}
+
+/// Two-sided critical value of Student's t distribution for the given confidence
+/// level (e.g., 0.95), found by bisection on the CDF
+pub fn t_critical(confidence: f64, df: f64) -> f64 {
+    let target = 1.0 - (1.0 - confidence) / 2.0;
+    let (mut low, mut high) = (0.0, 1000.0);
+    for _ in 0..100 {
+        let mid = (low + high) / 2.0;
+        if student_t_cdf(mid, df) < target {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    (low + high) / 2.0
+}
+
+/// Two-sided p-value of Welch's t-test, for two samples with possibly different
+/// variances. Needs at least two values per sample.
+pub fn welch_t_test(a: &[f64], b: &[f64]) -> Option<f64> {
+    if a.len() < 2 || b.len() < 2 {
+        return None;
+    }
+    let (na, nb) = (a.len() as f64, b.len() as f64);
+    let (va, vb) = (stddev(a).powi(2) / na, stddev(b).powi(2) / nb);
+    let diff = mean(a) - mean(b);
+    if va + vb == 0.0 {
+        // Both samples are constant: the difference is either exact or absent
+        return Some(if diff == 0.0 { 1.0 } else { 0.0 });
+    }
+
+    let t = diff / (va + vb).sqrt();
+    let df = (va + vb).powi(2) / (va.powi(2) / (na - 1.0) + vb.powi(2) / (nb - 1.0));
+    let p_value = 2.0 * (1.0 - student_t_cdf(t.abs(), df));
+    Some(p_value.clamp(0.0, 1.0))
+}
+
+/// Cumulative distribution function of Student's t distribution
+pub fn student_t_cdf(t: f64, df: f64) -> f64 {
+    let x = df / (df + t * t);
+    let tail = 0.5 * regularized_incomplete_beta(df / 2.0, 0.5, x);
+    if t >= 0.0 {
+        1.0 - tail
+    } else {
+        tail
+    }
+}
+
+/// Regularized incomplete beta function I_x(a, b) (continued fraction, Lentz's method)
+fn regularized_incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let ln_front = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln();
+    // The continued fraction converges quickly only below the mean; use symmetry above it
+    if x > (a + 1.0) / (a + b + 2.0) {
+        return 1.0 - regularized_incomplete_beta(b, a, 1.0 - x);
+    }
+
+    const EPSILON: f64 = 1e-14;
+    const TINY: f64 = 1e-300;
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut fraction = d;
+    for m in 1..=300 {
+        let m = m as f64;
+        // Even step
+        let numerator = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        d = 1.0 + numerator * d;
+        d = if d.abs() < TINY { 1.0 / TINY } else { 1.0 / d };
+        c = 1.0 + numerator / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        fraction *= d * c;
+        // Odd step
+        let numerator = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        d = 1.0 + numerator * d;
+        d = if d.abs() < TINY { 1.0 / TINY } else { 1.0 / d };
+        c = 1.0 + numerator / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        let delta = d * c;
+        fraction *= delta;
+        if (delta - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+    ln_front.exp() * fraction / a
+}
+
+/// Natural log of the gamma function (Lanczos approximation)
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.180_091_729_471_46,
+        -86.505_320_329_416_77,
+        24.014_098_240_830_91,
+        -1.231_739_572_450_155,
+        0.120_865_097_386_617_9e-2,
+        -0.539_523_938_495_3e-5,
+    ];
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let mut series = 1.000_000_000_190_015;
+    for (i, c) in COEFFICIENTS.iter().enumerate() {
+        series += c / (x + 1.0 + i as f64);
+    }
+    -tmp + (2.506_628_274_631_000_5 * series / x).ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(actual: f64, expected: f64, tolerance: f64) -> bool {
+        (actual - expected).abs() <= tolerance
+    }
+
+    #[test]
+    fn t_critical_matches_tables() {
+        // Two-sided critical values from the Student's t table
+        for (confidence, df, expected) in [
+            (0.95, 1.0, 12.706),
+            (0.95, 2.0, 4.303),
+            (0.95, 4.0, 2.776),
+            (0.95, 9.0, 2.262),
+            (0.95, 29.0, 2.045),
+            (0.95, 120.0, 1.980),
+            (0.99, 9.0, 3.250),
+            (0.90, 5.0, 2.015),
+        ] {
+            let actual = t_critical(confidence, df);
+            assert!(close(actual, expected, 1e-3), "{} df={}: {}", confidence, df, actual);
+        }
+    }
+
+    #[test]
+    fn student_t_cdf_matches_closed_forms() {
+        for t in [-3.0, -0.5, 0.0, 0.7, 2.0, 10.0] {
+            // df = 1 is the Cauchy distribution, df = 2 has an algebraic CDF
+            let cauchy = 0.5 + f64::atan(t) / std::f64::consts::PI;
+            let two = 0.5 + t / (2.0 * (2.0 + t * t).sqrt());
+            assert!(close(student_t_cdf(t, 1.0), cauchy, 1e-9), "df=1 t={}", t);
+            assert!(close(student_t_cdf(t, 2.0), two, 1e-9), "df=2 t={}", t);
+        }
+    }
+
+    #[test]
+    fn welch_p_value_matches_known_results() {
+        // Equal variances and sizes of two give t = -sqrt(2) with 2 degrees of freedom
+        let p = welch_t_test(&[0.0, 2.0], &[2.0, 4.0]).unwrap();
+        assert!(close(p, 1.0 - 1.0 / 2f64.sqrt(), 1e-9), "{}", p);
+
+        // Shifting one sample by the 95% critical value's worth of standard errors
+        // lands on p = 0.05
+        let a = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0];
+        let shift = t_critical(0.95, 18.0) * (2.0 * stddev(&a).powi(2) / 10.0).sqrt();
+        let b: Vec<f64> = a.iter().map(|v| v + shift).collect();
+        let p = welch_t_test(&a, &b).unwrap();
+        assert!(close(p, 0.05, 1e-6), "{}", p);
+
+        assert!(close(welch_t_test(&a, &a).unwrap(), 1.0, 1e-12));
+    }
+
+    #[test]
+    fn welch_handles_constant_and_short_samples() {
+        assert_eq!(welch_t_test(&[5.0, 5.0, 5.0], &[5.0, 5.0]), Some(1.0));
+        assert_eq!(welch_t_test(&[5.0, 5.0, 5.0], &[6.0, 6.0]), Some(0.0));
+        // One constant sample leaves the other's variance
+        let p = welch_t_test(&[5.0, 5.0, 5.0], &[4.0, 5.0, 6.0, 7.0]).unwrap();
+        assert!(p > 0.0 && p < 1.0, "{}", p);
+        assert_eq!(welch_t_test(&[1.0], &[1.0, 2.0]), None);
+        assert_eq!(welch_t_test(&[], &[]), None);
+    }
+
+    #[test]
+    fn summary_confidence_interval() {
+        let summary = Summary::of(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(summary.mean, 3.0);
+        assert!(close(summary.stddev, 2.5f64.sqrt(), 1e-12));
+        let half_width = 2.776 * 2.5f64.sqrt() / 5f64.sqrt();
+        assert!(close(summary.ci_high - summary.mean, half_width, 1e-3));
+        assert!(close(summary.mean - summary.ci_low, half_width, 1e-3));
+
+        let single = Summary::of(&[7.0]);
+        assert_eq!((single.ci_low, single.ci_high, single.stddev), (7.0, 7.0, 0.0));
+    }
+}
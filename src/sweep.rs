@@ -0,0 +1,278 @@
+use crate::config::{self, Config};
+use crate::io::engine::{BenchmarkResults, IoEngine};
+use crate::io::queue::QueueTuning;
+use crate::optimizer::search::{SearchEngine, SearchSpace};
+use crate::optimizer::SearchStrategy;
+use anyhow::Result;
+use std::fmt::Write as _;
+use std::path::Path;
+
+// A queue depth step that adds less IOPS than this, while latency keeps growing
+// by more than the second threshold, is past the knee
+const KNEE_MAX_IOPS_GAIN_PERCENT: f64 = 10.0;
+const KNEE_MIN_LATENCY_GROWTH_PERCENT: f64 = 10.0;
+
+/// One grid point of a sweep and what it measured
+#[derive(Debug, Clone)]
+pub struct SweepPoint {
+    pub block_size: usize,
+    pub queue_depth: usize,
+    pub threads: usize,
+    pub results: BenchmarkResults,
+}
+
+/// Queue depth past which a block size / thread count combination only adds latency
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Knee {
+    pub block_size: usize,
+    pub threads: usize,
+    pub queue_depth: usize,
+}
+
+/// Run the base configuration at every point of the grid
+pub fn run_sweep(base: &Config, space: SearchSpace) -> Result<Vec<SweepPoint>> {
+    let total = space.num_points();
+    let mut search = SearchEngine::new(SearchStrategy::Exhaustive).with_space(space);
+    let mut points = Vec::with_capacity(total);
+    // Restored when dropped, so errors put the settings back too
+    let tuning = if base.set_queue.is_empty() {
+        None
+    } else {
+        Some(QueueTuning::apply(&base.devices, &base.set_queue)?)
+    };
+    while let Some(params) = search.next_grid_point() {
+        let config = params.apply(base);
+        eprintln!(
+            "[{}/{}] bs={} qd={} threads={}",
+            points.len() + 1,
+            total,
+            config::format_block_size(config.block_size),
+            config.queue_depth,
+            config.threads
+        );
+        let results = IoEngine::new(config)?.run()?;
+        search.record_result(&params, &results);
+        points.push(SweepPoint {
+            block_size: params.block_size,
+            queue_depth: params.queue_depth,
+            threads: params.num_threads,
+            results,
+        });
+    }
+    if let Some(tuning) = tuning {
+        let changes = tuning.restore();
+        for point in &mut points {
+            point.results.queue_changes = changes.clone();
+        }
+    }
+    Ok(points)
+}
+
+/// Find the knee of each queue depth curve: the first depth after which no deeper
+/// queue gains more than KNEE_MAX_IOPS_GAIN_PERCENT IOPS while the next step
+/// still raises average latency
+pub fn find_knees(points: &[SweepPoint]) -> Vec<Knee> {
+    let mut knees = Vec::new();
+    for (block_size, threads) in series_keys(points) {
+        let mut curve: Vec<&SweepPoint> = points
+            .iter()
+            .filter(|p| p.block_size == block_size && p.threads == threads)
+            .collect();
+        curve.sort_by_key(|p| p.queue_depth);
+
+        for (i, point) in curve.iter().enumerate() {
+            let Some(next) = curve.get(i + 1) else {
+                break;
+            };
+            let iops = point.results.iops;
+            let best_after = curve[i + 1..]
+                .iter()
+                .map(|p| p.results.iops)
+                .fold(0.0, f64::max);
+            let latency = point.results.avg_latency_us;
+            let latency_growth = if latency > 0.0 {
+                (next.results.avg_latency_us - latency) / latency * 100.0
+            } else {
+                0.0
+            };
+            if iops > 0.0
+                && best_after < iops * (1.0 + KNEE_MAX_IOPS_GAIN_PERCENT / 100.0)
+                && latency_growth > KNEE_MIN_LATENCY_GROWTH_PERCENT
+            {
+                knees.push(Knee {
+                    block_size,
+                    threads,
+                    queue_depth: point.queue_depth,
+                });
+                break;
+            }
+        }
+    }
+    knees
+}
+
+/// Block size / thread count combinations in grid order
+fn series_keys(points: &[SweepPoint]) -> Vec<(usize, usize)> {
+    let mut keys = Vec::new();
+    for point in points {
+        let key = (point.block_size, point.threads);
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+    keys
+}
+
+fn is_knee(point: &SweepPoint, knees: &[Knee]) -> bool {
+    knees.contains(&Knee {
+        block_size: point.block_size,
+        threads: point.threads,
+        queue_depth: point.queue_depth,
+    })
+}
+
+pub fn print_sweep(points: &[SweepPoint], knees: &[Knee]) {
+    println!("\n{}", "=".repeat(100));
+    println!("Sweep Results");
+    println!("{}", "=".repeat(100));
+    println!(
+        "{:>8} {:>5} {:>7} {:>12} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+        "BS", "QD", "Threads", "IOPS", "MB/s", "Avg μs", "p50 μs", "p90 μs", "p99 μs", "p99.9 μs"
+    );
+    for point in points {
+        let r = &point.results;
+        println!(
+            "{:>8} {:>5} {:>7} {:>12.0} {:>10.2} {:>10.2} {:>10.2} {:>10.2} {:>10.2} {:>10.2}{}",
+            config::format_block_size(point.block_size),
+            point.queue_depth,
+            point.threads,
+            r.iops,
+            r.throughput_read_mbps + r.throughput_write_mbps,
+            r.avg_latency_us,
+            r.p50_latency_us,
+            r.p90_latency_us,
+            r.p99_latency_us,
+            r.p999_latency_us,
+            if is_knee(point, knees) {
+                "  <- knee"
+            } else {
+                ""
+            }
+        );
+    }
+
+    println!("\nKnees:");
+    for (block_size, threads) in series_keys(points) {
+        let knee = knees
+            .iter()
+            .find(|k| k.block_size == block_size && k.threads == threads);
+        match knee {
+            Some(knee) => println!(
+                "  bs={} threads={}: QD {} (deeper queues add latency, not IOPS)",
+                config::format_block_size(block_size),
+                threads,
+                knee.queue_depth
+            ),
+            None => println!(
+                "  bs={} threads={}: none (IOPS still scaling at the deepest queue)",
+                config::format_block_size(block_size),
+                threads
+            ),
+        }
+    }
+    println!("{}", "=".repeat(100));
+}
+
+/// One row per grid point, for plotting IOPS/latency curves
+pub fn write_csv(path: &Path, points: &[SweepPoint], knees: &[Knee]) -> Result<()> {
+    let mut csv = String::from(
+        "block_size,queue_depth,threads,iops,read_mbps,write_mbps,\
+         avg_latency_us,p50_latency_us,p90_latency_us,p99_latency_us,p999_latency_us,knee\n",
+    );
+    for point in points {
+        let r = &point.results;
+        writeln!(
+            csv,
+            "{},{},{},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2},{}",
+            point.block_size,
+            point.queue_depth,
+            point.threads,
+            r.iops,
+            r.throughput_read_mbps,
+            r.throughput_write_mbps,
+            r.avg_latency_us,
+            r.p50_latency_us,
+            r.p90_latency_us,
+            r.p99_latency_us,
+            r.p999_latency_us,
+            is_knee(point, knees)
+        )?;
+    }
+    std::fs::write(path, csv)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 4k single-thread curve from (queue depth, IOPS, average latency μs) samples
+    fn curve(samples: &[(usize, f64, f64)]) -> Vec<SweepPoint> {
+        samples
+            .iter()
+            .map(|&(queue_depth, iops, avg_latency_us)| SweepPoint {
+                block_size: 4096,
+                queue_depth,
+                threads: 1,
+                results: BenchmarkResults {
+                    iops,
+                    avg_latency_us,
+                    ..Default::default()
+                },
+            })
+            .collect()
+    }
+
+    #[test]
+    fn knee_where_iops_saturates_and_latency_climbs() {
+        let points = curve(&[
+            (1, 10_000.0, 100.0),
+            (2, 19_000.0, 105.0),
+            (4, 36_000.0, 110.0),
+            (8, 40_000.0, 200.0),
+            (16, 41_000.0, 390.0),
+            (32, 41_500.0, 770.0),
+        ]);
+        assert_eq!(
+            find_knees(&points),
+            vec![Knee {
+                block_size: 4096,
+                threads: 1,
+                queue_depth: 8
+            }]
+        );
+    }
+
+    #[test]
+    fn no_knee_while_still_scaling() {
+        let points = curve(&[
+            (1, 10_000.0, 100.0),
+            (2, 20_000.0, 100.0),
+            (4, 39_000.0, 102.0),
+            (8, 76_000.0, 105.0),
+            (16, 140_000.0, 114.0),
+        ]);
+        assert!(find_knees(&points).is_empty());
+    }
+
+    #[test]
+    fn no_knee_when_flat_iops_keeps_flat_latency() {
+        let points = curve(&[
+            (1, 50_000.0, 20.0),
+            (2, 50_500.0, 20.5),
+            (4, 51_000.0, 21.0),
+            (8, 50_800.0, 21.2),
+        ]);
+        assert!(find_knees(&points).is_empty());
+    }
+}